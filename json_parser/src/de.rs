@@ -0,0 +1,733 @@
+//! `serde` support, for deserializing straight from a document or from a `JsonValue`
+
+use super::*;
+
+use crate::error::position;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// An error from deserializing, which holds the line and column it was found at
+/// when it came from a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+    position: Option<(usize, usize)>,
+}
+
+impl Error {
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|(line, _)| line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|(_, column)| column)
+    }
+
+    /// Errors raised by a `Deserialize` impl don't know where they happened,
+    /// so they are placed wherever the deserializer had gotten to
+    fn or_at(mut self, doc: &str, remaining: usize) -> Self {
+        if self.position.is_none() {
+            let (_, line, column) = position(doc, remaining);
+            self.position = Some((line, column));
+        }
+        self
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(e: JsonError) -> Self {
+        Self {
+            message: e.kind().to_string(),
+            position: Some((e.line(), e.column())),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => {
+                write!(f, "{} at line {} column {}", self.message, line, column)
+            }
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self {
+            message: msg.to_string(),
+            position: None,
+        }
+    }
+}
+
+/// Deserializes a `T` from a complete JSON document, within the default `Limits`
+///
+/// Strings without escapes are borrowed from `doc`, so `T` may hold `&str`s
+pub fn from_str<'de, T: de::Deserialize<'de>>(doc: &'de str) -> Result<T, Error> {
+    from_str_limited(doc, Limits::default())
+}
+
+/// Deserializes a `T` from a complete JSON document, failing if it goes past any of the `limits`
+pub fn from_str_limited<'de, T: de::Deserialize<'de>>(
+    doc: &'de str,
+    limits: Limits,
+) -> Result<T, Error> {
+    let mut deserializer = Deserializer::with_limits(doc, limits)?;
+
+    T::deserialize(&mut deserializer)
+        .and_then(|value| deserializer.end().map(|()| value))
+        .map_err(|e| e.or_at(doc, deserializer.input.len()))
+}
+
+/// Deserializes a `T` from a `JsonValue`, within the default `Limits`
+pub fn from_value<T: DeserializeOwned>(value: JsonValue) -> Result<T, Error> {
+    from_value_limited(value, Limits::default())
+}
+
+/// Deserializes a `T` from a `JsonValue`, failing if it nests deeper than `limits.max_depth`
+/// or has a list or object with more than `limits.max_items` items
+///
+/// The value is already in memory, so `limits.max_len` doesn't apply to it
+pub fn from_value_limited<T: DeserializeOwned>(
+    value: JsonValue,
+    limits: Limits,
+) -> Result<T, Error> {
+    T::deserialize(ValueDeserializer {
+        value,
+        depth: 0,
+        limits,
+    })
+}
+
+/// Deserializes directly from the text of a document, without building a `JsonValue` first
+pub struct Deserializer<'de> {
+    doc: &'de str,
+    input: &'de str,
+    limits: Limits,
+    /// How many lists and objects the deserializer is inside of
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    /// A deserializer with the default `Limits`, which no document is too long for
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(doc: &'de str) -> Self {
+        Self {
+            doc,
+            input: doc,
+            limits: Limits::default(),
+            depth: 0,
+        }
+    }
+
+    /// A deserializer that fails once it goes past any of the `limits`, or right away if
+    /// `doc` is longer than `limits.max_len`
+    pub fn with_limits(doc: &'de str, limits: Limits) -> Result<Self, Error> {
+        let deserializer = Self {
+            limits,
+            ..Self::from_str(doc)
+        };
+
+        if doc.len() > limits.max_len {
+            return Err(deserializer.error(ErrorKind::TooLong));
+        }
+
+        Ok(deserializer)
+    }
+
+    /// Checks that nothing but whitespace is left after the value
+    pub fn end(&mut self) -> Result<(), Error> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error(ErrorKind::TrailingCharacters)),
+        }
+    }
+
+    fn error(&self, kind: ErrorKind) -> Error {
+        JsonError::new(self.doc, self.input.len(), kind).into()
+    }
+
+    /// Deserializes a list or object with `f`, one level deeper than the deserializer is,
+    /// as long as that isn't deeper than the limit
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= self.limits.max_depth {
+            return Err(self.error(ErrorKind::DepthExceeded));
+        }
+
+        self.depth += 1;
+        let out = f(self);
+        self.depth -= 1;
+        out
+    }
+
+    /// Skips whitespace and returns the next character
+    fn peek(&mut self) -> Option<char> {
+        self.input = grammar::eat_white_space(Dialect::strict())
+            .parse(self.input)
+            .0;
+        self.input.chars().next()
+    }
+
+    fn expect(&mut self, c: char, kind: ErrorKind) -> Result<(), Error> {
+        if self.peek() == Some(c) {
+            self.input = &self.input[c.len_utf8()..];
+            Ok(())
+        } else {
+            Err(self.error(kind))
+        }
+    }
+
+    fn literal(&mut self, expected: &'static str) -> Result<(), Error> {
+        let (input, out) = grammar::match_literal(expected).parse(self.input);
+
+        match out {
+            Ok(()) => {
+                self.input = input;
+                Ok(())
+            }
+            Err(_) => Err(self.error(ErrorKind::ExpectedValue)),
+        }
+    }
+
+    fn string(&mut self) -> Result<Cow<'de, str>, Error> {
+        if let Some(s) = grammar::borrowed_string(self.input) {
+            self.input = &self.input[s.len() + 2..];
+            return Ok(Cow::Borrowed(s));
+        }
+
+        let (input, out) = grammar::string(Dialect::strict()).parse(self.input);
+
+        match out {
+            Ok(s) => {
+                self.input = input;
+                Ok(Cow::Owned(s))
+            }
+            Err(e) => {
+                let (remaining, kind) = e.diagnose();
+                Err(JsonError::new(self.doc, remaining, kind).into())
+            }
+        }
+    }
+
+    fn number<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        let start = self.input;
+        let (input, out) = grammar::number(Dialect::strict()).parse(self.input);
+
+        let n = out.map_err(|e| match e {
+            grammar::NumberError::OutOfRange => self.error(ErrorKind::NumberOutOfRange),
+            _ => self.error(ErrorKind::InvalidNumber),
+        })?;
+        self.input = input;
+
+        // integers are visited as such, so that they don't lose precision by going through `f64`
+        let text = &start[..start.len() - input.len()];
+        if let Ok(n) = text.parse() {
+            visitor.visit_u64(n)
+        } else if let Ok(n) = text.parse() {
+            visitor.visit_i64(n)
+        } else {
+            visitor.visit_f64(n)
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.peek() {
+            Some('n') => {
+                self.literal("null")?;
+                visitor.visit_unit()
+            }
+            Some('t') => {
+                self.literal("true")?;
+                visitor.visit_bool(true)
+            }
+            Some('f') => {
+                self.literal("false")?;
+                visitor.visit_bool(false)
+            }
+            Some('"') => match self.string()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Some('[') => self.nested(|de| {
+                de.input = &de.input[1..];
+                let value = visitor.visit_seq(Seq {
+                    de: &mut *de,
+                    len: 0,
+                })?;
+                de.expect(']', ErrorKind::UnclosedList)?;
+                Ok(value)
+            }),
+            Some('{') => self.nested(|de| {
+                de.input = &de.input[1..];
+                let value = visitor.visit_map(Map {
+                    de: &mut *de,
+                    len: 0,
+                })?;
+                de.expect('}', ErrorKind::UnclosedObject)?;
+                Ok(value)
+            }),
+            Some('-') | Some('0'..='9') => self.number(visitor),
+            _ => Err(self.error(ErrorKind::ExpectedValue)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek() == Some('n') {
+            self.literal("null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Unit variants are written as a string holding their name, and all other variants
+    /// as an object with a single member, whose key is the name of the variant
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.peek() {
+            Some('"') => visitor.visit_enum(self.string()?.into_deserializer()),
+            Some('{') => self.nested(|de| {
+                de.input = &de.input[1..];
+                let value = visitor.visit_enum(Enum { de: &mut *de })?;
+                de.expect('}', ErrorKind::UnclosedObject)?;
+                Ok(value)
+            }),
+            _ => Err(self.error(ErrorKind::ExpectedValue)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    /// How many items have been deserialized so far
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        match self.de.peek() {
+            Some(']') => return Ok(None),
+            _ if self.len == 0 => (),
+            _ => self.de.expect(',', ErrorKind::UnclosedList)?,
+        }
+
+        self.len += 1;
+        if self.len > self.de.limits.max_items {
+            return Err(self.de.error(ErrorKind::TooManyItems));
+        }
+
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Map<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    /// How many members have been deserialized so far
+    len: usize,
+}
+
+impl<'de> MapAccess<'de> for Map<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.de.peek() {
+            Some('}') => return Ok(None),
+            _ if self.len == 0 => (),
+            _ => self.de.expect(',', ErrorKind::UnclosedObject)?,
+        }
+
+        if self.de.peek() != Some('"') {
+            return Err(self.de.error(ErrorKind::ExpectedString));
+        }
+
+        self.len += 1;
+        if self.len > self.de.limits.max_items {
+            return Err(self.de.error(ErrorKind::TooManyItems));
+        }
+
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.de.expect(':', ErrorKind::ExpectedColon)?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        if self.de.peek() != Some('"') {
+            return Err(self.de.error(ErrorKind::ExpectedString));
+        }
+
+        let variant = self.de.string()?;
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(variant))?;
+        self.de.expect(':', ErrorKind::ExpectedColon)?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+fn visit_number<'de, V: Visitor<'de>>(n: f64, visitor: V) -> Result<V::Value, Error> {
+    // every integer below 2^53 fits in an `f64` exactly, so those are visited as integers
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        if n < 0.0 {
+            visitor.visit_i64(n as i64)
+        } else {
+            visitor.visit_u64(n as u64)
+        }
+    } else {
+        visitor.visit_f64(n)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for JsonValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Deserializes within the default `Limits`
+impl<'de> de::Deserializer<'de> for JsonValue {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        ValueDeserializer::new(self).deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        ValueDeserializer::new(self).deserialize_option(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        ValueDeserializer::new(self).deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        ValueDeserializer::new(self).deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserializes a `JsonValue` that is `depth` lists and objects deep in the value that
+/// deserializing started from
+struct ValueDeserializer {
+    value: JsonValue,
+    depth: usize,
+    limits: Limits,
+}
+
+impl ValueDeserializer {
+    fn new(value: JsonValue) -> Self {
+        Self {
+            value,
+            depth: 0,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Makes the deserializers for the items of a list or object with `len` items, which is
+    /// at `depth`, as long as its items don't go past the `limits`
+    fn items(
+        depth: usize,
+        limits: Limits,
+        len: usize,
+    ) -> Result<impl Fn(JsonValue) -> Self, Error> {
+        if depth >= limits.max_depth {
+            return Err(de::Error::custom(ErrorKind::DepthExceeded));
+        }
+
+        if len > limits.max_items {
+            return Err(de::Error::custom(ErrorKind::TooManyItems));
+        }
+
+        Ok(move |value| Self {
+            value,
+            depth: depth + 1,
+            limits,
+        })
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let Self {
+            value,
+            depth,
+            limits,
+        } = self;
+
+        match value {
+            JsonValue::Null => visitor.visit_unit(),
+            JsonValue::Bool(b) => visitor.visit_bool(b),
+            JsonValue::Number(n) => visit_number(n, visitor),
+            JsonValue::String(s) => visitor.visit_string(s),
+            JsonValue::List(list) => {
+                let item = Self::items(depth, limits, list.len())?;
+                let mut seq = de::value::SeqDeserializer::new(list.into_iter().map(item));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            JsonValue::Object(object) => {
+                let item = Self::items(depth, limits, object.len())?;
+                let mut map = de::value::MapDeserializer::new(
+                    object.into_iter().map(|(key, value)| (key, item(value))),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            JsonValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        match self.value {
+            JsonValue::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            JsonValue::Object(object) if object.len() == 1 => {
+                let item = Self::items(self.depth, self.limits, 1)?;
+                let (variant, value) = object.into_iter().next().unwrap();
+                visitor.visit_enum(ValueEnum {
+                    variant,
+                    value: item(value),
+                })
+            }
+            _ => Err(de::Error::custom(
+                "expected a string or an object with a single member for an enum",
+            )),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct ValueEnum {
+    variant: String,
+    value: ValueDeserializer,
+}
+
+impl<'de> EnumAccess<'de> for ValueEnum {
+    type Error = Error;
+    type Variant = ValueDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, ValueDeserializer), Error> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for JsonValue {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, d: D) -> Result<JsonValue, D::Error> {
+        de::Deserialize::deserialize(d)
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n as f64))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n as f64))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> Result<JsonValue, E> {
+        Ok(JsonValue::Number(n))
+    }
+
+    fn visit_str<E>(self, s: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(s.to_owned()))
+    }
+
+    fn visit_string<E>(self, s: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut list = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            list.push(item);
+        }
+        Ok(JsonValue::List(list))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut object = HashMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            object.insert(key, value);
+        }
+        Ok(JsonValue::Object(object))
+    }
+}
@@ -0,0 +1,131 @@
+use std::error::Error;
+use std::fmt;
+use std::str::Utf8Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    ExpectedValue,
+    ExpectedString,
+    ExpectedColon,
+    InvalidNumber,
+    NumberOutOfRange,
+    UnterminatedString,
+    ControlCharacter,
+    InvalidEscape,
+    InvalidUnicode,
+    UnclosedList,
+    UnclosedObject,
+    TrailingCharacters,
+    InvalidUtf8,
+    DepthExceeded,
+    TooManyItems,
+    TooLong,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorKind::ExpectedValue => "expected a value",
+            ErrorKind::ExpectedString => "expected a string",
+            ErrorKind::ExpectedColon => "expected `:` after an object key",
+            ErrorKind::InvalidNumber => "invalid number",
+            ErrorKind::NumberOutOfRange => "number is too large to be represented",
+            ErrorKind::UnterminatedString => "unterminated string",
+            ErrorKind::ControlCharacter => "control characters must be escaped in strings",
+            ErrorKind::InvalidEscape => "invalid escape sequence",
+            ErrorKind::InvalidUnicode => "escape sequence is not a valid unicode scalar value",
+            ErrorKind::UnclosedList => "expected `,` or `]`",
+            ErrorKind::UnclosedObject => "expected `,` or `}`",
+            ErrorKind::TrailingCharacters => "unexpected characters after the document",
+            ErrorKind::InvalidUtf8 => "invalid UTF-8",
+            ErrorKind::DepthExceeded => "lists and objects are nested too deeply",
+            ErrorKind::TooManyItems => "too many items in a list or object",
+            ErrorKind::TooLong => "the document is too long",
+        })
+    }
+}
+
+/// Finds the byte offset, line and column of the point in `doc` where `remaining` bytes are left
+pub(crate) fn position(doc: &str, remaining: usize) -> (usize, usize, usize) {
+    let offset = doc.len() - remaining;
+    let before = &doc[..offset];
+
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+        offset,
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// An error in a JSON document, along with where it was found
+///
+/// Lines and columns both start at 1, and columns are counted in `char`s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    kind: ErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl JsonError {
+    /// `remaining` is the length of the input that was left when the error was found
+    pub(crate) fn new(doc: &str, remaining: usize, kind: ErrorKind) -> Self {
+        let (offset, line, column) = position(doc, remaining);
+
+        Self {
+            kind,
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /// The error for a document that isn't valid UTF-8, found at the first byte that isn't
+    pub fn invalid_utf8(doc: &[u8], error: Utf8Error) -> Self {
+        // the valid part of the document leads up to the bad byte
+        let valid = String::from_utf8_lossy(&doc[..error.valid_up_to()]);
+        Self::new(&valid, 0, ErrorKind::InvalidUtf8)
+    }
+
+    /// Moves an error found in a single line to where that line is in a larger document,
+    /// given the byte offset and number of the line
+    pub(crate) fn on_line(self, offset: usize, line: usize) -> Self {
+        Self {
+            offset: self.offset + offset,
+            line: self.line + line - 1,
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The byte offset of the error in the document
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl Error for JsonError {}
@@ -0,0 +1,306 @@
+use super::*;
+
+use crate::grammar::{borrowed_string, eat_white_space, key, literal, number, string};
+use crate::grammar::{NumberError, StringError};
+
+use std::borrow::Cow;
+
+/// A piece of a JSON document, as read by `JsonEvents`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    StartObject,
+    /// The key of an object member, which is followed by the events of its value
+    Key(Cow<'a, str>),
+    Value(Scalar<'a>),
+    EndObject,
+    StartArray,
+    EndArray,
+}
+
+/// A value that holds no other values
+///
+/// Strings are borrowed from the document whenever they need no unescaping
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar<'a> {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+}
+
+impl From<Scalar<'_>> for JsonValue {
+    fn from(scalar: Scalar<'_>) -> Self {
+        match scalar {
+            Scalar::Null => JsonValue::Null,
+            Scalar::Bool(b) => JsonValue::Bool(b),
+            Scalar::Number(n) => JsonValue::Number(n),
+            Scalar::String(s) => JsonValue::String(s.into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Array,
+    Object,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// `may_end` is set where the enclosing array may be closed instead
+    Value {
+        may_end: bool,
+    },
+    /// `may_end` is set where the enclosing object may be closed instead
+    Key {
+        may_end: bool,
+    },
+    AfterValue,
+    Finished,
+}
+
+/// A pull parser, which reads a document one `Event` at a time instead of building
+/// a `JsonValue` for all of it
+///
+/// Apart from the document itself, memory use only grows with how deeply the
+/// document is nested. After the first error the iterator is finished.
+#[derive(Debug, Clone)]
+pub struct JsonEvents<'a> {
+    doc: &'a str,
+    rest: &'a str,
+    dialect: Dialect,
+    stack: Vec<Container>,
+    state: State,
+}
+
+impl<'a> JsonEvents<'a> {
+    pub fn new(doc: &'a str) -> Self {
+        Self::with_dialect(doc, Dialect::strict())
+    }
+
+    pub fn with_dialect(doc: &'a str, dialect: Dialect) -> Self {
+        Self {
+            doc,
+            rest: doc,
+            dialect,
+            stack: Vec::new(),
+            state: State::Value { may_end: false },
+        }
+    }
+
+    /// How many arrays and objects the parser is currently inside of
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Reads the next value in the document as a whole `JsonValue`
+    ///
+    /// This is meant for picking out parts of a document, for example by calling it
+    /// after the `Key` of the member that is wanted. If the next event is a key, the
+    /// value of that member is read. If the next event closes an array or object,
+    /// or the document has ended, that is consumed and `None` is returned.
+    pub fn read_value(&mut self) -> Result<Option<JsonValue>, JsonError> {
+        enum Partial {
+            Array(Vec<JsonValue>),
+            Object(HashMap<String, JsonValue>, String),
+        }
+
+        let mut partials = Vec::new();
+
+        loop {
+            let value = match self.next_event()? {
+                None => return Ok(None),
+                Some(Event::StartArray) => {
+                    partials.push(Partial::Array(Vec::new()));
+                    continue;
+                }
+                Some(Event::StartObject) => {
+                    partials.push(Partial::Object(HashMap::new(), String::new()));
+                    continue;
+                }
+                Some(Event::Key(k)) => {
+                    if let Some(Partial::Object(_, key)) = partials.last_mut() {
+                        *key = k.into_owned();
+                    }
+                    continue;
+                }
+                Some(Event::Value(scalar)) => JsonValue::from(scalar),
+                Some(Event::EndArray) | Some(Event::EndObject) => match partials.pop() {
+                    Some(Partial::Array(array)) => JsonValue::List(array),
+                    Some(Partial::Object(object, _)) => JsonValue::Object(object),
+                    None => return Ok(None),
+                },
+            };
+
+            match partials.last_mut() {
+                None => return Ok(Some(value)),
+                Some(Partial::Array(array)) => array.push(value),
+                Some(Partial::Object(object, key)) => {
+                    object.insert(std::mem::take(key), value);
+                }
+            }
+        }
+    }
+
+    fn error(&mut self, remaining: usize, kind: ErrorKind) -> JsonError {
+        self.state = State::Finished;
+        JsonError::new(self.doc, remaining, kind)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest.starts_with(c) {
+            self.rest = &self.rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn end(&mut self, container: Container) -> Event<'a> {
+        self.stack.pop();
+        self.state = State::AfterValue;
+
+        match container {
+            Container::Array => Event::EndArray,
+            Container::Object => Event::EndObject,
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, JsonError> {
+        loop {
+            self.rest = eat_white_space(self.dialect).parse(self.rest).0;
+
+            match (self.state, self.stack.last().copied()) {
+                (State::Finished, _) => return Ok(None),
+                (State::Value { may_end: true }, Some(Container::Array)) if self.eat(']') => {
+                    return Ok(Some(self.end(Container::Array)))
+                }
+                (State::Value { .. }, _) => return self.value().map(Some),
+                (State::Key { may_end: true }, _) if self.eat('}') => {
+                    return Ok(Some(self.end(Container::Object)))
+                }
+                (State::Key { .. }, _) => return self.key().map(Some),
+                (State::AfterValue, None) if self.rest.is_empty() => {
+                    self.state = State::Finished;
+                    return Ok(None);
+                }
+                (State::AfterValue, None) => {
+                    return Err(self.error(self.rest.len(), ErrorKind::TrailingCharacters))
+                }
+                (State::AfterValue, Some(container)) => {
+                    let (end, kind) = match container {
+                        Container::Array => (']', ErrorKind::UnclosedList),
+                        Container::Object => ('}', ErrorKind::UnclosedObject),
+                    };
+
+                    if self.eat(',') {
+                        let may_end = self.dialect.trailing_commas;
+                        self.state = match container {
+                            Container::Array => State::Value { may_end },
+                            Container::Object => State::Key { may_end },
+                        };
+                    } else if self.eat(end) {
+                        return Ok(Some(self.end(container)));
+                    } else {
+                        return Err(self.error(self.rest.len(), kind));
+                    }
+                }
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Event<'a>, JsonError> {
+        let key = match borrowed_string(self.rest) {
+            Some(key) => {
+                self.rest = &self.rest[key.len() + 2..];
+                Cow::Borrowed(key)
+            }
+            None => match key(self.dialect).parse(self.rest) {
+                (rest, Ok(key)) => {
+                    self.rest = rest;
+                    Cow::Owned(key)
+                }
+                (_, Err(e)) => {
+                    let (remaining, kind) = e.diagnose();
+                    return Err(self.error(remaining, kind));
+                }
+            },
+        };
+
+        self.rest = eat_white_space(self.dialect).parse(self.rest).0;
+
+        if !self.eat(':') {
+            return Err(self.error(self.rest.len(), ErrorKind::ExpectedColon));
+        }
+
+        self.state = State::Value { may_end: false };
+        Ok(Event::Key(key))
+    }
+
+    fn value(&mut self) -> Result<Event<'a>, JsonError> {
+        if self.eat('[') {
+            self.stack.push(Container::Array);
+            self.state = State::Value { may_end: true };
+            return Ok(Event::StartArray);
+        }
+
+        if self.eat('{') {
+            self.stack.push(Container::Object);
+            self.state = State::Key { may_end: true };
+            return Ok(Event::StartObject);
+        }
+
+        let scalar = self.scalar()?;
+        self.state = State::AfterValue;
+        Ok(Event::Value(scalar))
+    }
+
+    /// Tries each kind of scalar in turn, and reports the error in the same way as `parse_json`
+    fn scalar(&mut self) -> Result<Scalar<'a>, JsonError> {
+        let remaining = self.rest.len();
+
+        if let Some(s) = borrowed_string(self.rest) {
+            self.rest = &self.rest[s.len() + 2..];
+            return Ok(Scalar::String(Cow::Borrowed(s)));
+        }
+
+        if let (rest, Ok(value)) = literal().parse(self.rest) {
+            self.rest = rest;
+            return Ok(match value {
+                JsonValue::Bool(b) => Scalar::Bool(b),
+                _ => Scalar::Null,
+            });
+        }
+
+        match string(self.dialect).parse(self.rest) {
+            (rest, Ok(s)) => {
+                self.rest = rest;
+                return Ok(Scalar::String(Cow::Owned(s)));
+            }
+            (_, Err(StringError::NoStart(_))) => (),
+            (_, Err(e)) => {
+                let (remaining, kind) = e.diagnose();
+                return Err(self.error(remaining, kind));
+            }
+        }
+
+        match number(self.dialect).parse(self.rest) {
+            (rest, Ok(n)) => {
+                self.rest = rest;
+                Ok(Scalar::Number(n))
+            }
+            (_, Err(NumberError::OutOfRange)) => {
+                Err(self.error(remaining, ErrorKind::NumberOutOfRange))
+            }
+            (_, Err(_)) => Err(self.error(remaining, ErrorKind::ExpectedValue)),
+        }
+    }
+}
+
+impl<'a> Iterator for JsonEvents<'a> {
+    type Item = Result<Event<'a>, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
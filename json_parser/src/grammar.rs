@@ -0,0 +1,918 @@
+use parser_combinators::prelude::*;
+
+use parser_combinators::filter::FilterError;
+use parser_combinators::introspect::Describe;
+use parser_combinators::limit::{Depth, DepthExceeded, InputTooLong};
+use parser_combinators::repeat::{FoundZero, TooMany};
+
+use either::Either;
+
+use std::collections::HashMap;
+
+use crate::{ErrorKind, JsonValue};
+
+/// The extensions to JSON that the parser accepts
+///
+/// The default is strict RFC 8259 JSON, `jsonc` adds the comments and trailing commas
+/// that are common in config files, and `json5` turns on everything below to accept JSON5.
+///
+/// The one place that falls short of JSON5 is identifier keys, which follow ECMAScript's rules
+/// with letters and digits as `char::is_alphabetic` and `char::is_alphanumeric` see them,
+/// rather than by Unicode general category
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// `// line` and `/* block */` comments wherever whitespace is allowed
+    pub comments: bool,
+    /// White space other than space, tab, line feed and carriage return: `\v`, `\f`,
+    /// the byte order mark, and Unicode spaces and line breaks like `U+00A0` and `U+2028`
+    pub unicode_white_space: bool,
+    /// A `,` after the last item of a list or the last member of an object
+    pub trailing_commas: bool,
+    /// Object keys written as bare identifiers, like `{ key: 1 }`, which may hold `\uXXXX`
+    /// escapes
+    pub identifier_keys: bool,
+    /// Strings in single quotes, like `'say "hi"'`
+    pub single_quotes: bool,
+    /// Hexadecimal numbers, like `0xFF`
+    pub hex_numbers: bool,
+    /// `Infinity` and `NaN`, with an optional sign
+    pub non_finite_numbers: bool,
+    /// Numbers with a leading `+`, or with no digits on one side of the decimal point,
+    /// like `.5` and `5.`
+    pub lenient_numbers: bool,
+    /// A backslash before a line break in a string, which continues the string on the next line
+    pub multiline_strings: bool,
+    /// The `\'`, `\v`, `\0` and `\xFF` escapes, and escaped characters that stand for themselves,
+    /// like `\a`
+    pub extra_escapes: bool,
+}
+
+impl Dialect {
+    pub fn strict() -> Self {
+        Self::default()
+    }
+
+    pub fn jsonc() -> Self {
+        Self {
+            comments: true,
+            trailing_commas: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn json5() -> Self {
+        Self {
+            comments: true,
+            unicode_white_space: true,
+            trailing_commas: true,
+            identifier_keys: true,
+            single_quotes: true,
+            hex_numbers: true,
+            non_finite_numbers: true,
+            lenient_numbers: true,
+            multiline_strings: true,
+            extra_escapes: true,
+        }
+    }
+}
+
+/// Bounds on the size and shape of a document, for parsing input that can't be trusted
+///
+/// Every level of nesting takes up stack space while parsing, so documents nested deeper than
+/// `max_depth` are rejected. Levels that don't fit on the stack of the thread doing the parsing
+/// carry on in newly allocated segments of stack, so that the limit holds on any thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// How many lists and objects may be nested inside of each other
+    pub max_depth: usize,
+    /// How many items a single list, or members a single object, may have
+    pub max_items: usize,
+    /// How long the document may be, in bytes
+    pub max_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_items: usize::MAX,
+            max_len: usize::MAX,
+        }
+    }
+}
+
+/// What the values at one level of a document are parsed with
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Level {
+    dialect: Dialect,
+    limits: Limits,
+    /// Counts the lists and objects that the value is inside of
+    depth: Depth,
+}
+
+impl Level {
+    /// The level of the items of a list or object at this level
+    fn deeper(self) -> Self {
+        Self {
+            depth: self.depth.deeper(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct EmptyInput;
+
+#[derive(Debug)]
+pub(crate) struct Disabled;
+
+/// Succeeds without consuming anything when `allowed`, which lets a part of the grammar
+/// that only some dialects have be switched on and off
+fn enabled(allowed: bool) -> impl for<'a> Parser<&'a str, Output = (), Error = Disabled> + Copy {
+    (move |_: &mut &str| if allowed { Ok(()) } else { Err(Disabled) }).as_parser_in_place()
+}
+
+pub(crate) fn any_char() -> impl for<'a> Parser<&'a str, Output = char, Error = EmptyInput> {
+    (|s: &mut &str| {
+        let c = s.chars().next().ok_or(EmptyInput)?;
+        *s = &s[c.len_utf8()..];
+        Ok(c)
+    })
+    .as_parser_in_place()
+}
+
+pub(crate) fn match_char(
+    find: char,
+) -> impl for<'a> Parser<&'a str, Output = (), Error = FilterError<EmptyInput>> {
+    any_char().filter(move |&c: &char| c == find).map(drop)
+}
+
+#[derive(Debug)]
+pub(crate) struct LiteralError;
+
+pub(crate) fn match_literal(
+    expected: &'static str,
+) -> impl for<'a> Parser<&'a str, Output = (), Error = LiteralError> + Describe + Copy {
+    util::literal(expected).map_err(|_| LiteralError)
+}
+
+/// Pairs the error of `p` with the amount of input that was left when it failed,
+/// which is enough to find the error in the document later on
+fn located<P, Output, Error>(
+    p: P,
+) -> impl for<'a> Parser<&'a str, Output = Output, Error = (usize, Error)>
+where
+    P: for<'a> Parser<&'a str, Output = Output, Error = Error>,
+{
+    (move |s: &mut &str| {
+        let remaining = s.len();
+        let (rest, out) = p.parse(*s);
+        *s = rest;
+        out.map_err(|e| (remaining, e))
+    })
+    .as_parser_in_place()
+}
+
+fn comment() -> impl for<'a> Parser<&'a str, Output = (), Error = LiteralError> {
+    (|s: &mut &str| {
+        let len = if s.starts_with("//") {
+            // the line break is left for the white space after the comment
+            s.find(is_line_break).unwrap_or(s.len())
+        } else if let Some(rest) = s.strip_prefix("/*") {
+            rest.find("*/").ok_or(LiteralError)? + 4
+        } else {
+            return Err(LiteralError);
+        };
+
+        *s = &s[len..];
+        Ok(())
+    })
+    .as_parser_in_place()
+}
+
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+/// The white space of JSON5, which is that of ECMAScript
+fn is_unicode_white_space(c: char) -> bool {
+    // `char::is_whitespace` covers every space separator and line break, but also the
+    // next line character, which isn't white space in ECMAScript
+    (c.is_whitespace() && c != '\u{85}') || c == '\u{feff}'
+}
+
+pub(crate) fn eat_white_space(
+    dialect: Dialect,
+) -> impl for<'a> Parser<&'a str, Output = (), Error = util::Infallible> {
+    // RFC 8259 only allows space, horizontal tab, line feed and carriage return
+    any_char()
+        .filter(move |&x: &char| {
+            x == ' '
+                || x == '\t'
+                || x == '\n'
+                || x == '\r'
+                || (dialect.unicode_white_space && is_unicode_white_space(x))
+        })
+        .map(drop)
+        .or(enabled(dialect.comments).then(comment()).map(drop))
+        .zero_or_more(util::ignore)
+        .map(drop)
+}
+
+pub(crate) fn literal(
+) -> impl for<'a> Parser<&'a str, Output = JsonValue, Error = LiteralError> + Describe {
+    match_literal("true")
+        .map(|()| JsonValue::Bool(true))
+        .or(match_literal("false").map(|()| JsonValue::Bool(false)))
+        .map(Either::into_inner)
+        .or(match_literal("null").map(|()| JsonValue::Null))
+        .map(Either::into_inner)
+        .map_err(|_| LiteralError)
+}
+
+#[derive(Debug)]
+pub(crate) enum NumberError {
+    OutOfRange,
+    MissingInteger,
+    MalformedFloat,
+}
+
+impl From<FoundZero> for NumberError {
+    fn from(_: FoundZero) -> Self {
+        NumberError::MalformedFloat
+    }
+}
+
+pub(crate) fn digits() -> impl for<'a> Parser<&'a str, Output = String, Error = FoundZero> {
+    any_char()
+        .filter(|x: &char| x.is_ascii_digit())
+        .one_or_more(String::new)
+}
+
+/// Digits, or nothing at all if `lenient`
+fn lenient_digits(lenient: bool) -> impl for<'a> Parser<&'a str, Output = String, Error = ()> {
+    digits()
+        .or(enabled(lenient).map(|()| String::new()))
+        .map_both(Either::into_inner, drop)
+}
+
+fn integer(lenient: bool) -> impl for<'a> Parser<&'a str, Output = String, Error = NumberError> {
+    // a leading zero may not be followed by more digits, so `0` is its own case
+    match_char('0')
+        .map(|()| String::from("0"))
+        .or(lenient_digits(lenient))
+        .map_both(Either::into_inner, |_| NumberError::MissingInteger)
+}
+
+fn fraction(lenient: bool) -> impl for<'a> Parser<&'a str, Output = String, Error = NumberError> {
+    match_char('.')
+        .then(lenient_digits(lenient))
+        .map_both(util::snd, |_| NumberError::MalformedFloat)
+}
+
+fn exponent() -> impl for<'a> Parser<&'a str, Output = String, Error = NumberError> {
+    any_char()
+        .filter(|&x: &char| x == 'e' || x == 'E')
+        .then(
+            any_char()
+                .filter(|&x: &char| x == '+' || x == '-')
+                .optional(),
+        )
+        .map_both(util::snd, util::unwrap_left)
+        .then(digits())
+        .map_both(
+            |(sign, digits)| match sign {
+                Ok(sign) => format!("{}{}", sign, digits),
+                Err(_) => digits,
+            },
+            |_| NumberError::MalformedFloat,
+        )
+}
+
+fn decimal_number(
+    lenient: bool,
+) -> impl for<'a> Parser<&'a str, Output = f64, Error = NumberError> {
+    integer(lenient)
+        .then(fraction(lenient).optional())
+        .map_err(util::unwrap_left)
+        .then(exponent().optional())
+        .map_err(util::unwrap_left)
+        .flat_map(|((integer, fraction), exponent): ((String, _), _)| {
+            let mut l = integer;
+            if let Ok(fraction) = fraction {
+                let fraction: String = fraction;
+                l.push('.');
+                l += &fraction;
+            }
+            // a lenient number may leave out the digits on one side of the point, but not both
+            if l.is_empty() || l == "." {
+                return Err(NumberError::MissingInteger);
+            }
+            if let Ok(exponent) = exponent {
+                let exponent: String = exponent;
+                l.push('e');
+                l += &exponent;
+            }
+            let n: f64 = l.parse().map_err(|_| NumberError::MalformedFloat)?;
+            // numbers too large for an `f64` round to infinity, which JSON has no way to write
+            if n.is_infinite() {
+                Err(NumberError::OutOfRange)
+            } else {
+                Ok(n)
+            }
+        })
+}
+
+fn hex_number(allowed: bool) -> impl for<'a> Parser<&'a str, Output = f64, Error = ()> {
+    enabled(allowed)
+        .then(match_literal("0x").or(match_literal("0X")))
+        .map_err(drop)
+        .then(
+            any_char()
+                .filter(|x: &char| x.is_ascii_hexdigit())
+                .one_or_more(String::new),
+        )
+        .map_both(
+            |(_, digits): (_, String)| {
+                digits
+                    .chars()
+                    .filter_map(|x| x.to_digit(16))
+                    .fold(0.0, |n, x| n * 16.0 + f64::from(x))
+            },
+            drop,
+        )
+}
+
+fn non_finite_number(allowed: bool) -> impl for<'a> Parser<&'a str, Output = f64, Error = ()> {
+    enabled(allowed)
+        .then(
+            match_literal("Infinity")
+                .map(|()| f64::INFINITY)
+                .or(match_literal("NaN").map(|()| f64::NAN))
+                .map(Either::into_inner),
+        )
+        .map_both(util::snd, drop)
+}
+
+pub(crate) fn number(
+    dialect: Dialect,
+) -> impl for<'a> Parser<&'a str, Output = f64, Error = NumberError> {
+    any_char()
+        .filter(move |&x: &char| x == '-' || (dialect.lenient_numbers && x == '+'))
+        .optional()
+        .then(
+            // hexadecimal numbers go first, as the `0` of `0x` is a decimal number by itself
+            hex_number(dialect.hex_numbers)
+                .or(non_finite_number(dialect.non_finite_numbers))
+                .map(Either::into_inner)
+                .or(decimal_number(dialect.lenient_numbers))
+                .map_both(Either::into_inner, util::snd),
+        )
+        .map_both(
+            |(sign, n): (Result<char, _>, f64)| match sign {
+                Ok('-') => -n,
+                _ => n,
+            },
+            util::unwrap_right,
+        )
+}
+
+/// Every variant but `NoStart` holds the remaining input at the offending character
+#[derive(Debug)]
+pub(crate) enum StringError {
+    NoStart(usize),
+    NoEnd(usize),
+    ControlCharacter(usize),
+    InvalidEscape(usize),
+    InvalidUnicode(usize),
+}
+
+impl StringError {
+    pub(crate) fn diagnose(self) -> (usize, ErrorKind) {
+        match self {
+            StringError::NoStart(at) => (at, ErrorKind::ExpectedString),
+            StringError::NoEnd(at) => (at, ErrorKind::UnterminatedString),
+            StringError::ControlCharacter(at) => (at, ErrorKind::ControlCharacter),
+            StringError::InvalidEscape(at) => (at, ErrorKind::InvalidEscape),
+            StringError::InvalidUnicode(at) => (at, ErrorKind::InvalidUnicode),
+        }
+    }
+}
+
+fn hex_escape(chars: &mut std::str::Chars<'_>, len: usize, at: usize) -> Result<u16, StringError> {
+    let mut code = 0;
+
+    for _ in 0..len {
+        let digit = chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .ok_or(StringError::InvalidEscape(at))?;
+
+        code = code * 16 + digit as u16;
+    }
+
+    Ok(code)
+}
+
+fn unicode_escape(chars: &mut std::str::Chars<'_>, at: usize) -> Result<char, StringError> {
+    let high = hex_escape(chars, 4, at)?;
+
+    let code = match high {
+        0xD800..=0xDBFF => {
+            // a high surrogate must be directly followed by an escaped low surrogate
+            if chars.next() != Some('\\') || chars.next() != Some('u') {
+                return Err(StringError::InvalidUnicode(at));
+            }
+
+            let low = hex_escape(chars, 4, at)?;
+
+            match low {
+                0xDC00..=0xDFFF => {
+                    0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(low) - 0xDC00)
+                }
+                _ => return Err(StringError::InvalidUnicode(at)),
+            }
+        }
+        _ => u32::from(high),
+    };
+
+    std::char::from_u32(code).ok_or(StringError::InvalidUnicode(at))
+}
+
+/// The rest of a string that was opened with `quote`
+fn string_contents(
+    quote: char,
+    dialect: Dialect,
+) -> impl for<'a> Parser<&'a str, Output = String, Error = StringError> {
+    (move |s: &mut &str| {
+        // an unterminated string is reported at its opening quote
+        let start = s.len() + 1;
+        let mut contents = String::new();
+        let mut chars = s.chars();
+
+        loop {
+            let at = chars.as_str().len();
+
+            let c = match chars.next().ok_or(StringError::NoEnd(start))? {
+                c if c == quote => break,
+                '\\' => match chars.next().ok_or(StringError::NoEnd(start))? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => unicode_escape(&mut chars, at)?,
+                    '\'' if dialect.single_quotes || dialect.extra_escapes => '\'',
+                    'v' if dialect.extra_escapes => '\u{b}',
+                    // `\0` may not be followed by a digit, as it would look like an octal escape
+                    '0' if dialect.extra_escapes
+                        && !chars.as_str().starts_with(|c: char| c.is_ascii_digit()) =>
+                    {
+                        '\0'
+                    }
+                    'x' if dialect.extra_escapes => {
+                        char::from(hex_escape(&mut chars, 2, at)? as u8)
+                    }
+                    '\r' if dialect.multiline_strings => {
+                        if chars.as_str().starts_with('\n') {
+                            chars.next();
+                        }
+                        continue;
+                    }
+                    '\n' | '\u{2028}' | '\u{2029}' if dialect.multiline_strings => continue,
+                    c if dialect.extra_escapes && !c.is_ascii_digit() && c >= '\u{20}' => c,
+                    _ => return Err(StringError::InvalidEscape(at)),
+                },
+                c if c < '\u{20}' => return Err(StringError::ControlCharacter(at)),
+                c => c,
+            };
+
+            contents.push(c);
+        }
+
+        *s = chars.as_str();
+        Ok(contents)
+    })
+    .as_parser_in_place()
+}
+
+pub(crate) fn string(
+    dialect: Dialect,
+) -> impl for<'a> Parser<&'a str, Output = String, Error = StringError> {
+    located(any_char().filter(move |&x: &char| x == '"' || (dialect.single_quotes && x == '\'')))
+        .and_then(move |quote| string_contents(quote, dialect))
+        .map_err(|e| match e {
+            Either::Left((at, _)) => StringError::NoStart(at),
+            Either::Right(e) => e,
+        })
+}
+
+/// Finds the contents of the string at the start of `input`, as long as they need no unescaping
+pub(crate) fn borrowed_string(input: &str) -> Option<&str> {
+    let contents = input.strip_prefix('"')?;
+    let end = contents.find(|c: char| c == '"' || c == '\\' || c < '\u{20}')?;
+
+    if contents[end..].starts_with('"') {
+        Some(&contents[..end])
+    } else {
+        None
+    }
+}
+
+/// Takes a character of an identifier off the front of `s` if it is `allowed`, where the
+/// character may be written as a `\uXXXX` escape
+fn identifier_char(s: &mut &str, allowed: impl Fn(char) -> bool) -> Option<char> {
+    let mut chars = s.chars();
+
+    let c = match chars.next()? {
+        '\\' if chars.next() == Some('u') => {
+            let code = hex_escape(&mut chars, 4, 0).ok()?;
+            std::char::from_u32(u32::from(code))?
+        }
+        '\\' => return None,
+        c => c,
+    };
+
+    if allowed(c) {
+        *s = chars.as_str();
+        Some(c)
+    } else {
+        None
+    }
+}
+
+/// An ECMAScript identifier, where an escaped character has to be one that the identifier
+/// could hold as it is
+fn identifier() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> {
+    (|s: &mut &str| {
+        let start = |c: char| c.is_alphabetic() || c == '$' || c == '_';
+        // along with the zero width non-joiner and joiner
+        let part = |c: char| start(c) || c.is_alphanumeric() || c == '\u{200c}' || c == '\u{200d}';
+
+        let mut identifier = String::from(identifier_char(s, start).ok_or(())?);
+        while let Some(c) = identifier_char(s, part) {
+            identifier.push(c);
+        }
+
+        Ok(identifier)
+    })
+    .as_parser_in_place()
+}
+
+pub(crate) fn key(
+    dialect: Dialect,
+) -> impl for<'a> Parser<&'a str, Output = String, Error = StringError> {
+    string(dialect)
+        .or(enabled(dialect.identifier_keys)
+            .then(identifier())
+            .map(util::snd))
+        .map_both(Either::into_inner, util::fst)
+}
+
+#[derive(Debug)]
+enum ItemError {
+    Key(StringError),
+    Colon(usize),
+    Value(Box<ValueError>),
+}
+
+impl From<Either<Either<StringError, (usize, FilterError<EmptyInput>)>, ValueError>> for ItemError {
+    fn from(e: Either<Either<StringError, (usize, FilterError<EmptyInput>)>, ValueError>) -> Self {
+        match e {
+            Either::Left(Either::Left(x)) => ItemError::Key(x),
+            Either::Left(Either::Right((at, _))) => ItemError::Colon(at),
+            Either::Right(x) => ItemError::Value(Box::new(x)),
+        }
+    }
+}
+
+impl ItemError {
+    fn diagnose(self) -> (usize, ErrorKind) {
+        match self {
+            ItemError::Key(e) => e.diagnose(),
+            ItemError::Colon(at) => (at, ErrorKind::ExpectedColon),
+            ItemError::Value(e) => e.diagnose(),
+        }
+    }
+}
+
+fn item(
+    level: Level,
+) -> impl for<'a> Parser<&'a str, Output = (String, JsonValue), Error = ItemError> {
+    let dialect = level.dialect;
+
+    key(dialect)
+        .then(eat_white_space(dialect))
+        .map_both(util::fst, util::unwrap_left)
+        .then(located(match_char(':')))
+        .map(util::fst)
+        .then(eat_white_space(dialect))
+        .map_both(util::fst, util::unwrap_left)
+        .then(value(level))
+        .then(eat_white_space(dialect))
+        .map_both(util::fst, util::unwrap_left)
+        .map_err(ItemError::from)
+}
+
+#[derive(Debug)]
+enum ListError<E> {
+    NoStart,
+    NoEnd(usize),
+    Item(Box<E>),
+    DepthExceeded,
+    TooMany,
+}
+
+impl<E> ListError<E> {
+    /// `start` is the remaining input at the opening bracket of the list
+    fn diagnose(
+        self,
+        start: usize,
+        unclosed: ErrorKind,
+        item: fn(E) -> (usize, ErrorKind),
+    ) -> (usize, ErrorKind) {
+        match self {
+            // the caller knows where the value started, so it reports this case
+            ListError::NoStart => unreachable!("a list that never started has nothing to report"),
+            ListError::NoEnd(at) => (at, unclosed),
+            ListError::Item(e) => item(*e),
+            ListError::DepthExceeded => (start, ErrorKind::DepthExceeded),
+            ListError::TooMany => (start, ErrorKind::TooManyItems),
+        }
+    }
+}
+
+use parser_combinators::repeat::collections::Collection;
+
+/// Collects the items of a list until one of them fails to parse, then holds on to that error
+struct Items<C, E>(Result<C, E>);
+
+impl<Output, Error, C: Collection<Output>> Collection<Result<Output, Error>> for Items<C, Error> {
+    #[inline]
+    fn put(&mut self, item: Result<Output, Error>) {
+        match (&mut self.0, item) {
+            (Ok(c), Ok(item)) => c.put(item),
+            (Ok(_), Err(e)) => self.0 = Err(e),
+            (Err(_), _) => (),
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, C::len)
+    }
+}
+
+/// Succeeds without consuming anything, unless trailing separators are `allowed`
+/// and the list ends right away
+fn not_trailing(
+    allowed: bool,
+    end: char,
+) -> impl for<'a> Parser<&'a str, Output = (), Error = Disabled> + Copy {
+    (move |s: &mut &str| {
+        if allowed && s.starts_with(end) {
+            Err(Disabled)
+        } else {
+            Ok(())
+        }
+    })
+    .as_parser_in_place()
+}
+
+fn generalized_list<
+    Output,
+    Error,
+    P: for<'a> Parser<&'a str, Output = Output, Error = Error>,
+    Item,
+    F,
+    C,
+>(
+    level: Level,
+    start: char,
+    end: char,
+    sep: char,
+    item: Item,
+    f: F,
+) -> impl for<'a> Parser<&'a str, Output = C, Error = ListError<Error>>
+where
+    C: Default + Collection<Output> + 'static,
+    Item: Fn() -> P + Copy,
+    F: Fn(Output) -> C + Copy,
+{
+    let Level {
+        dialect,
+        limits,
+        depth,
+    } = level;
+
+    match_char(start)
+        .then(eat_white_space(dialect))
+        .map_both(util::fst, |e| match e {
+            Either::Left(_) => ListError::NoStart,
+            Either::Right(e) => util::from_infallible(e),
+        })
+        .then(
+            item()
+                .then(eat_white_space(dialect))
+                .map_both(util::fst, util::unwrap_left)
+                .and_then(move |x| {
+                    // once a separator is found the item after it is required, unless the
+                    // dialect allows a trailing separator, so its error is kept instead of
+                    // being reported as a missing `end`
+                    match_char(sep)
+                        .then(eat_white_space(dialect))
+                        .map_both(util::fst, util::unwrap_left)
+                        // a trailing separator ends the loop, and is eaten after it
+                        .then(not_trailing(dialect.trailing_commas, end))
+                        .then(item().optional())
+                        .map_both(util::snd, util::unwrap_left)
+                        .then(eat_white_space(dialect))
+                        .map_both(util::fst, util::unwrap_left)
+                        .at_most(limits.max_items.saturating_sub(1), move || Items(Ok(f(x))))
+                        .then(
+                            enabled(dialect.trailing_commas)
+                                .then(match_char(sep))
+                                .then(eat_white_space(dialect))
+                                .optional(),
+                        )
+                        .map_both(util::fst, util::unwrap_left)
+                })
+                .map_err(|e| match e {
+                    Either::Left(e) => ListError::Item(Box::new(e)),
+                    Either::Right(TooMany) => ListError::TooMany,
+                })
+                // the first item counts against the limit too, which the loop after it
+                // can't check when no items are allowed at all
+                .flat_map(move |items| {
+                    if limits.max_items == 0 {
+                        Err(ListError::TooMany)
+                    } else {
+                        Ok(items)
+                    }
+                })
+                .optional()
+                // the items are where the grammar recurses, so the guard has to cover them
+                .depth_limit(depth)
+                .map_err(|e| match e {
+                    Either::Left(DepthExceeded) => ListError::DepthExceeded,
+                    Either::Right(e) => util::from_infallible(e),
+                }),
+        )
+        .map_both(util::snd, Either::into_inner)
+        .and_then(move |items| {
+            located(match_char(end))
+                .optional()
+                .map(move |end| (items, end))
+        })
+        .map_err(|e| match e {
+            Either::Left(e) => e,
+            Either::Right(e) => util::from_infallible(e),
+        })
+        .flat_map(|(items, end)| match (items, end) {
+            (Ok(Items(Ok(c))), Ok(())) => Ok(c),
+            // the first item is allowed to be missing, as long as the list ends right away
+            (Err(ListError::Item(_)), Ok(())) => Ok(C::default()),
+            (Ok(Items(Err(e))), _) => Err(ListError::Item(Box::new(e))),
+            (Err(e), _) => Err(e),
+            (Ok(Items(Ok(_))), Err((at, _))) => Err(ListError::NoEnd(at)),
+        })
+}
+
+fn object(
+    level: Level,
+) -> impl for<'a> Parser<&'a str, Output = HashMap<String, JsonValue>, Error = ListError<ItemError>>
+{
+    let inner = level.deeper();
+
+    generalized_list(
+        inner,
+        '{',
+        '}',
+        ',',
+        move || item(inner),
+        |(id, value)| {
+            let mut hm = HashMap::new();
+            hm.insert(id, value);
+            hm
+        },
+    )
+}
+
+fn list(
+    level: Level,
+) -> impl for<'a> Parser<&'a str, Output = Vec<JsonValue>, Error = ListError<ValueError>> {
+    let inner = level.deeper();
+
+    generalized_list(inner, '[', ']', ',', move || value(inner), |x| vec![x])
+}
+
+#[derive(Debug)]
+pub(crate) struct ValueError {
+    position: usize,
+    number_error: NumberError,
+    string_error: StringError,
+    list_error: ListError<ValueError>,
+    object_error: ListError<ItemError>,
+}
+
+#[allow(clippy::type_complexity)]
+impl
+    From<(
+        usize,
+        (
+            (
+                ((LiteralError, NumberError), StringError),
+                ListError<ValueError>,
+            ),
+            ListError<ItemError>,
+        ),
+    )> for ValueError
+{
+    fn from(
+        (position, ((((LiteralError, number_error), string_error), list_error), object_error)): (
+            usize,
+            (
+                (
+                    ((LiteralError, NumberError), StringError),
+                    ListError<ValueError>,
+                ),
+                ListError<ItemError>,
+            ),
+        ),
+    ) -> Self {
+        Self {
+            position,
+            number_error,
+            string_error,
+            list_error,
+            object_error,
+        }
+    }
+}
+
+impl ValueError {
+    /// Finds the alternative that got past its first character, as that is the one
+    /// the document was trying to write, and reports its error
+    pub(crate) fn diagnose(self) -> (usize, ErrorKind) {
+        match (self.string_error, self.list_error, self.object_error) {
+            (StringError::NoStart(_), ListError::NoStart, ListError::NoStart) => (),
+            (StringError::NoStart(_), ListError::NoStart, e) => {
+                return e.diagnose(
+                    self.position,
+                    ErrorKind::UnclosedObject,
+                    ItemError::diagnose,
+                )
+            }
+            (StringError::NoStart(_), e, _) => {
+                return e.diagnose(self.position, ErrorKind::UnclosedList, ValueError::diagnose)
+            }
+            (e, _, _) => return e.diagnose(),
+        }
+
+        match self.number_error {
+            NumberError::OutOfRange => (self.position, ErrorKind::NumberOutOfRange),
+            NumberError::MissingInteger | NumberError::MalformedFloat => {
+                (self.position, ErrorKind::ExpectedValue)
+            }
+        }
+    }
+}
+
+pub(crate) fn value(
+    level: Level,
+) -> Box<dyn for<'a> Parser<&'a str, Output = JsonValue, Error = ValueError> + Send + Sync> {
+    // The box breaks up the otherwise infinite type of a value holding lists of values
+    Box::new(
+        located(defer(move || {
+            literal()
+                .or(number(level.dialect).map(JsonValue::from))
+                .map(Either::into_inner)
+                .or(string(level.dialect).map(JsonValue::from))
+                .map(Either::into_inner)
+                .or(list(level).map(JsonValue::from))
+                .map(Either::into_inner)
+                .or(object(level).map(JsonValue::from))
+                .map(Either::into_inner)
+        }))
+        .map_err(ValueError::from),
+    ) as Box<dyn for<'a> Parser<&'a str, Output = _, Error = _> + Send + Sync>
+}
+
+pub(crate) fn json_text(
+    dialect: Dialect,
+    limits: Limits,
+) -> impl for<'a> Parser<&'a str, Output = JsonValue, Error = Either<InputTooLong, ValueError>> {
+    eat_white_space(dialect)
+        .then(value(Level {
+            dialect,
+            limits,
+            depth: Depth::limit(limits.max_depth),
+        }))
+        .map_both(util::snd, util::unwrap_right)
+        .then(eat_white_space(dialect))
+        .map_both(util::fst, util::unwrap_left)
+        .max_input_len(limits.max_len)
+}
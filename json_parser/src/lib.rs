@@ -0,0 +1,97 @@
+#![forbid(unsafe_code)]
+
+use parser_combinators::limit::InputTooLong;
+use parser_combinators::prelude::*;
+
+use either::Either;
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+pub mod de;
+mod error;
+mod events;
+mod grammar;
+mod lines;
+mod query;
+mod ser;
+
+pub use error::{ErrorKind, JsonError};
+pub use events::{Event, JsonEvents, Scalar};
+pub use grammar::{Dialect, Limits};
+pub use lines::{JsonLines, ParJsonLines};
+pub use query::{JsonPath, PathError};
+pub use ser::{Formatted, WriteOptions};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Object(HashMap<String, JsonValue>),
+    List(Vec<JsonValue>),
+}
+
+impl From<bool> for JsonValue {
+    fn from(s: bool) -> Self {
+        JsonValue::Bool(s)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(s: f64) -> Self {
+        JsonValue::Number(s)
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<HashMap<String, JsonValue>> for JsonValue {
+    fn from(s: HashMap<String, JsonValue>) -> Self {
+        JsonValue::Object(s)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(s: Vec<JsonValue>) -> Self {
+        JsonValue::List(s)
+    }
+}
+
+/// Parses a complete JSON document, which may be surrounded by whitespace but nothing else
+pub fn parse_json(doc: &str) -> Result<JsonValue, JsonError> {
+    parse_json_with(doc, Dialect::strict())
+}
+
+/// Parses a complete document written in the given dialect of JSON
+pub fn parse_json_with(doc: &str, dialect: Dialect) -> Result<JsonValue, JsonError> {
+    parse_json_limited(doc, dialect, Limits::default())
+}
+
+/// Parses a complete document, failing if it goes past any of the `limits`
+pub fn parse_json_limited(
+    doc: &str,
+    dialect: Dialect,
+    limits: Limits,
+) -> Result<JsonValue, JsonError> {
+    let (rest, value) = grammar::json_text(dialect, limits).parse(doc);
+
+    match value {
+        Ok(value) if rest.is_empty() => Ok(value),
+        Ok(_) => Err(JsonError::new(
+            doc,
+            rest.len(),
+            ErrorKind::TrailingCharacters,
+        )),
+        Err(Either::Left(InputTooLong)) => Err(JsonError::new(doc, doc.len(), ErrorKind::TooLong)),
+        Err(Either::Right(e)) => {
+            let (remaining, kind) = e.diagnose();
+            Err(JsonError::new(doc, remaining, kind))
+        }
+    }
+}
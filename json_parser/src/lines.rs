@@ -0,0 +1,231 @@
+use super::*;
+
+use rayon::prelude::*;
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read};
+
+/// Reads newline delimited JSON (NDJSON or JSON Lines), one value per line
+///
+/// Each line is parsed on its own, so a malformed record is reported with its line number
+/// and iteration carries on with the next line. Lines holding nothing but whitespace are skipped.
+///
+/// Every line is parsed within the `Limits` given to `with_limits`. A line longer than
+/// `max_len` bytes is reported as `TooLong` at its start, without ever holding more than
+/// `max_len` bytes of it in memory.
+///
+/// Iteration stops at the first error from the reader itself, which is then available
+/// from `io_error`
+#[derive(Debug)]
+pub struct JsonLines<R> {
+    reader: R,
+    dialect: Dialect,
+    limits: Limits,
+    offset: usize,
+    line: usize,
+    io_error: Option<io::Error>,
+}
+
+/// A line that was read, but not parsed yet
+struct Line {
+    offset: usize,
+    number: usize,
+    /// The line, or `None` if it was longer than the limit and never read in full
+    bytes: Option<Vec<u8>>,
+}
+
+impl<R: BufRead> JsonLines<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, Dialect::strict())
+    }
+
+    pub fn with_dialect(reader: R, dialect: Dialect) -> Self {
+        Self::with_limits(reader, dialect, Limits::default())
+    }
+
+    pub fn with_limits(reader: R, dialect: Dialect, limits: Limits) -> Self {
+        Self {
+            reader,
+            dialect,
+            limits,
+            offset: 0,
+            line: 0,
+            io_error: None,
+        }
+    }
+
+    /// The error that stopped the iteration, if the reader failed
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.io_error.as_ref()
+    }
+
+    /// Parses batches of `batch_size` lines in parallel, while still yielding the values
+    /// in the order of the lines they were on
+    pub fn parallel(self, batch_size: usize) -> ParJsonLines<R> {
+        ParJsonLines {
+            lines: self,
+            batch_size: batch_size.max(1),
+            parsed: VecDeque::new(),
+        }
+    }
+
+    fn read_line(&mut self) -> Option<Line> {
+        if self.io_error.is_some() {
+            return None;
+        }
+
+        let mut bytes = Vec::new();
+        // room for the line break after the longest line allowed
+        let max_len = self.limits.max_len.saturating_add(2);
+
+        let read = (&mut self.reader)
+            .take(max_len as u64)
+            .read_until(b'\n', &mut bytes)
+            .and_then(|len| {
+                if line_contents(&bytes).len() <= self.limits.max_len {
+                    Ok((len, Some(bytes)))
+                } else if bytes.ends_with(b"\n") {
+                    Ok((len, None))
+                } else {
+                    Ok((len + self.skip_line()?, None))
+                }
+            });
+
+        match read {
+            Ok((0, _)) => None,
+            Ok((len, bytes)) => {
+                let line = Line {
+                    offset: self.offset,
+                    number: self.line + 1,
+                    bytes,
+                };
+
+                self.offset += len;
+                self.line += 1;
+                Some(line)
+            }
+            Err(e) => {
+                self.io_error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Reads past the rest of the current line without keeping it, returning its length
+    fn skip_line(&mut self) -> io::Result<usize> {
+        let mut len = 0;
+
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    self.reader.consume(i + 1);
+                    return Ok(len + i + 1);
+                }
+                None if buf.is_empty() => return Ok(len),
+                None => {
+                    let read = buf.len();
+                    self.reader.consume(read);
+                    len += read;
+                }
+            }
+        }
+    }
+}
+
+/// A line without its line break
+fn line_contents(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.strip_suffix(b"\r").unwrap_or(bytes)
+}
+
+impl Line {
+    /// Parses the line, or returns `None` if it is blank
+    fn parse(&self, dialect: Dialect, limits: Limits) -> Option<Result<JsonValue, JsonError>> {
+        let bytes = match &self.bytes {
+            Some(bytes) => line_contents(bytes),
+            None => {
+                return Some(Err(
+                    JsonError::new("", 0, ErrorKind::TooLong).on_line(self.offset, self.number)
+                ))
+            }
+        };
+
+        let result = match std::str::from_utf8(bytes) {
+            Ok(doc) if doc.trim().is_empty() => return None,
+            Ok(doc) => parse_json_limited(doc, dialect, limits),
+            Err(e) => Err(JsonError::invalid_utf8(bytes, e)),
+        };
+
+        Some(result.map_err(|e| e.on_line(self.offset, self.number)))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLines<R> {
+    type Item = Result<JsonValue, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = self.read_line()?;
+
+            if let Some(result) = line.parse(self.dialect, self.limits) {
+                return Some(result);
+            }
+        }
+    }
+}
+
+/// Reads newline delimited JSON like `JsonLines`, but parses many lines at once using rayon
+///
+/// Reading stays sequential, so this pays off when parsing is the bottleneck,
+/// such as for long records
+#[derive(Debug)]
+pub struct ParJsonLines<R> {
+    lines: JsonLines<R>,
+    batch_size: usize,
+    parsed: VecDeque<Result<JsonValue, JsonError>>,
+}
+
+impl<R> ParJsonLines<R> {
+    /// The error that stopped the iteration, if the reader failed
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.lines.io_error.as_ref()
+    }
+}
+
+impl<R: BufRead> Iterator for ParJsonLines<R> {
+    type Item = Result<JsonValue, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.parsed.is_empty() {
+            let mut batch = Vec::with_capacity(self.batch_size);
+            while batch.len() < self.batch_size {
+                match self.lines.read_line() {
+                    Some(line) => batch.push(line),
+                    None => break,
+                }
+            }
+
+            if batch.is_empty() {
+                return None;
+            }
+
+            let JsonLines {
+                dialect, limits, ..
+            } = self.lines;
+            let parsed: Vec<_> = batch
+                .par_iter()
+                .filter_map(|line| line.parse(dialect, limits))
+                .collect();
+
+            self.parsed.extend(parsed);
+        }
+
+        self.parsed.pop_front()
+    }
+}
@@ -1,308 +1,175 @@
 #![forbid(unsafe_code)]
 
-use parser_combinators::prelude::*;
+use json_parser::{parse_json_with, Dialect, JsonError, WriteOptions};
 
-use parser_combinators::filter::FilterError;
-use parser_combinators::repeat::FoundZero;
+use std::io::Read;
+use std::process::exit;
 
-use either::Either;
+const USAGE: &str = "\
+usage: json_parser [--validate | --pretty | --compact | --query <path>] [OPTIONS] [FILE]...
 
-use std::collections::HashMap;
+Reads each FILE, or standard input when there are none or FILE is `-`.
 
-#[derive(Debug)]
-struct EmptyInput;
+    --validate         only check that the input is valid JSON
+    --pretty           print the input as indented JSON (default)
+    --compact          print the input as JSON without any whitespace
+    --query <path>     print the value at the given JSON Pointer, like `/a/0/b`, or
+                       every value matched by a JSONPath, like `$.a[*].b`
 
-fn any_char() -> impl for<'a> Parser<&'a str, Output = char, Error = EmptyInput> {
-    (|s: &mut &str| {
-        let c = s.chars().next().ok_or(EmptyInput)?;
-        *s = &s[c.len_utf8()..];
-        Ok(c)
-    })
-    .as_parser_in_place()
-}
+OPTIONS:
+    --jsonc            accept comments and trailing commas
+    --json5            accept JSON5
+    --indent <n>       indent pretty output by n spaces per level (default 2)
+    --sort-keys        print object members ordered by their keys
+    --ascii            escape all non-ASCII characters in strings";
 
-fn match_char(
-    find: char,
-) -> impl for<'a> Parser<&'a str, Output = (), Error = FilterError<EmptyInput>> {
-    any_char().filter(move |&c: &char| c == find).map(drop)
+enum Mode {
+    Validate,
+    Pretty,
+    Compact,
+    Query(String),
 }
 
-fn eat_white_space() -> impl for<'a> Parser<&'a str, Output = (), Error = util::Infallible> {
-    any_char()
-        .filter(|x: &char| x.is_whitespace())
-        .zero_or_more(util::ignore)
-        .map(drop)
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}\n\n{}", message, USAGE);
+    exit(2)
 }
 
-use std::num::ParseFloatError;
-
-#[derive(Debug)]
-enum NumberError {
-    ParseError(ParseFloatError),
-    MalformedFloat,
-}
+fn read_input(path: &str) -> std::io::Result<Vec<u8>> {
+    let mut doc = Vec::new();
 
-impl From<ParseFloatError> for NumberError {
-    fn from(p: ParseFloatError) -> Self {
-        NumberError::ParseError(p)
+    if path == "-" {
+        std::io::stdin().read_to_end(&mut doc)?;
+    } else {
+        std::fs::File::open(path)?.read_to_end(&mut doc)?;
     }
-}
-
-impl From<FoundZero> for NumberError {
-    fn from(_: FoundZero) -> Self {
-        NumberError::MalformedFloat
-    }
-}
 
-fn number() -> impl for<'a> Parser<&'a str, Output = f64, Error = NumberError> {
-    any_char()
-        .filter(|x: &char| x.is_numeric())
-        .one_or_more(String::new)
-        .then(
-            match_char('.')
-                .then(
-                    any_char()
-                        .filter(|x: &char| x.is_numeric())
-                        .one_or_more(String::new),
-                )
-                .map(util::snd)
-                .optional(),
-        )
-        .map_err(util::unwrap_left)
-        .map_err(Into::into)
-        .flat_map(|(mut l, r): (String, Result<String, _>)| {
-            if let Ok(r) = r {
-                let r: String = r;
-                l.push('.');
-                l += &r;
+    Ok(doc)
+}
+
+/// Prints the error along with the line it was found on, and a caret under the offending column
+fn render_error(path: &str, doc: &str, error: &JsonError) {
+    let line = doc.lines().nth(error.line() - 1).unwrap_or("");
+    let gutter = error.line().to_string().len();
+
+    eprintln!("error: {}", error.kind());
+    eprintln!(
+        "{:gutter$}--> {}:{}:{}",
+        "",
+        path,
+        error.line(),
+        error.column(),
+        gutter = gutter
+    );
+    eprintln!("{:gutter$} |", "", gutter = gutter);
+    eprintln!("{} | {}", error.line(), line);
+    eprintln!(
+        "{:gutter$} | {:>column$}",
+        "",
+        "^",
+        gutter = gutter,
+        column = error.column()
+    );
+}
+
+fn main() {
+    let mut mode = Mode::Pretty;
+    let mut dialect = Dialect::strict();
+    let mut options = WriteOptions::pretty();
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--validate" => mode = Mode::Validate,
+            "--pretty" => mode = Mode::Pretty,
+            "--compact" => mode = Mode::Compact,
+            "--query" => match args.next() {
+                Some(path) => mode = Mode::Query(path),
+                None => usage_error("`--query` needs a JSON Pointer or JSONPath"),
+            },
+            "--jsonc" => dialect = Dialect::jsonc(),
+            "--json5" => dialect = Dialect::json5(),
+            "--indent" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(indent) => options.indent = Some(indent),
+                None => usage_error("`--indent` needs a number of spaces"),
+            },
+            "--sort-keys" => options.sort_keys = true,
+            "--ascii" => options.ascii_only = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
             }
-            Ok(l.parse()?)
-        })
-}
-
-#[derive(Debug)]
-enum StringError {
-    NoStart,
-    NoEnd,
-}
-
-impl From<Either<FilterError<EmptyInput>, FilterError<EmptyInput>>> for StringError {
-    fn from(e: Either<FilterError<EmptyInput>, FilterError<EmptyInput>>) -> Self {
-        match e {
-            Either::Left(_) => StringError::NoStart,
-            Either::Right(_) => StringError::NoEnd,
-        }
-    }
-}
-
-fn string() -> impl for<'a> Parser<&'a str, Output = String, Error = StringError> {
-    match_char('"')
-        .then(
-            any_char()
-                .filter(|&x: &char| x != '"')
-                .zero_or_more(String::new),
-        )
-        .map_both(util::snd, util::unwrap_left)
-        .then(match_char('"'))
-        .map(util::fst)
-        .map_err(StringError::from)
-}
-
-#[derive(Debug)]
-enum ItemError {
-    Key(StringError),
-    Colon,
-    Value(Box<ValueError>),
-}
-
-impl From<Either<Either<StringError, FilterError<EmptyInput>>, ValueError>> for ItemError {
-    fn from(e: Either<Either<StringError, FilterError<EmptyInput>>, ValueError>) -> Self {
-        match e {
-            Either::Left(Either::Left(x)) => ItemError::Key(x),
-            Either::Left(Either::Right(_)) => ItemError::Colon,
-            Either::Right(x) => ItemError::Value(Box::new(x)),
+            _ if arg.starts_with("--") => usage_error(&format!("unknown option `{}`", arg)),
+            _ => paths.push(arg),
         }
     }
-}
-
-fn item() -> impl for<'a> Parser<&'a str, Output = (String, JsonValue), Error = ItemError> {
-    string()
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(match_char(':'))
-        .map(util::fst)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(value())
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .map_err(ItemError::from)
-}
-
-#[derive(Debug)]
-enum ListError {
-    NoStart,
-    NoEnd,
-}
 
-impl From<Either<FilterError<EmptyInput>, FilterError<EmptyInput>>> for ListError {
-    fn from(e: Either<FilterError<EmptyInput>, FilterError<EmptyInput>>) -> Self {
-        match e {
-            Either::Left(_) => ListError::NoStart,
-            Either::Right(_) => ListError::NoEnd,
-        }
+    if paths.is_empty() {
+        paths.push(String::from("-"));
     }
-}
-
-use parser_combinators::repeat::collections::Collection;
-
-fn generalized_list<
-    Output,
-    Error,
-    P: for<'a> Parser<&'a str, Output = Output, Error = Error>,
-    Item,
-    F,
-    C,
->(
-    start: char,
-    end: char,
-    sep: char,
-    item: Item,
-    f: F,
-) -> impl for<'a> Parser<&'a str, Output = C, Error = ListError>
-where
-    C: Default + Collection<Output> + 'static,
-    Item: Fn() -> P,
-    F: Fn(Output) -> C + Copy,
-{
-    match_char(start)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(
-            item()
-                .and_then(move |x| {
-                    match_char(sep)
-                        .then(eat_white_space())
-                        .map_both(util::fst, util::unwrap_left)
-                        .then(item())
-                        .map(util::snd)
-                        .then(eat_white_space())
-                        .map_both(util::fst, util::unwrap_left)
-                        .zero_or_more(move || f(x))
-                })
-                .map_err(util::unwrap_left)
-                .optional(),
-        )
-        .map_both(util::snd, util::unwrap_left)
-        .then(match_char(end))
-        .map(util::fst)
-        .map_both(Result::unwrap_or_default, ListError::from)
-}
 
-fn object() -> impl for<'a> Parser<&'a str, Output = HashMap<String, JsonValue>, Error = ListError>
-{
-    generalized_list('{', '}', ',', item, |(id, value)| {
-        let mut hm = HashMap::new();
-        hm.insert(id, value);
-        hm
-    })
-}
+    let compact = WriteOptions {
+        indent: None,
+        ..options
+    };
 
-fn list() -> impl for<'a> Parser<&'a str, Output = Vec<JsonValue>, Error = ListError> {
-    generalized_list('[', ']', ',', value, |x| vec![x])
-}
+    let mut failed = false;
 
-#[derive(Debug)]
-struct ValueError {
-    number_error: NumberError,
-    string_error: StringError,
-    object_error: ListError,
-    list_error: ListError,
-}
-
-impl From<(((NumberError, StringError), ListError), ListError)> for ValueError {
-    fn from(
-        (((number_error, string_error), object_error), list_error): (
-            ((NumberError, StringError), ListError),
-            ListError,
-        ),
-    ) -> Self {
-        Self {
-            number_error,
-            string_error,
-            object_error,
-            list_error,
+    for path in &paths {
+        let doc = match read_input(path) {
+            Ok(doc) => doc,
+            Err(e) => {
+                eprintln!("error: could not read {}: {}", path, e);
+                exit(2)
+            }
+        };
+
+        let value = match std::str::from_utf8(&doc) {
+            Ok(text) => parse_json_with(text, dialect).map_err(|e| (e, text.into())),
+            Err(e) => Err((
+                JsonError::invalid_utf8(&doc, e),
+                String::from_utf8_lossy(&doc),
+            )),
+        };
+
+        let value = match value {
+            Ok(value) => value,
+            Err((e, text)) => {
+                render_error(path, &text, &e);
+                failed = true;
+                continue;
+            }
+        };
+
+        match &mode {
+            Mode::Validate => (),
+            Mode::Pretty => println!("{}", value.formatted(options)),
+            Mode::Compact => println!("{}", value.formatted(compact)),
+            Mode::Query(query) if query.starts_with('$') => match value.query(query) {
+                Ok(values) => {
+                    for value in &values {
+                        println!("{}", value.formatted(options));
+                    }
+
+                    if values.is_empty() {
+                        eprintln!("error: {} has no values matching `{}`", path, query);
+                        failed = true;
+                    }
+                }
+                Err(e) => usage_error(&e.to_string()),
+            },
+            Mode::Query(pointer) => match value.pointer(pointer) {
+                Some(value) => println!("{}", value.formatted(options)),
+                None => {
+                    eprintln!("error: {} has no value at `{}`", path, pointer);
+                    failed = true;
+                }
+            },
         }
     }
-}
-
-fn value() -> Box<dyn for<'a> Parser<&'a str, Output = JsonValue, Error = ValueError> + Send + Sync>
-{
-    // This box doesn't allocate, because the insides are zero-sized
-    Box::new(
-        defer(|| {
-            number()
-                .map(JsonValue::from)
-                .or(string().map(JsonValue::from))
-                .map(Either::into_inner)
-                .or(list().map(JsonValue::from))
-                .map(Either::into_inner)
-                .or(object().map(JsonValue::from))
-                .map(Either::into_inner)
-        })
-        .map_err(ValueError::from),
-    ) as Box<dyn for<'a> Parser<&'a str, Output = _, Error = _> + Send + Sync>
-}
-
-#[derive(Debug)]
-enum JsonValue {
-    Number(f64),
-    String(String),
-    Object(HashMap<String, JsonValue>),
-    List(Vec<JsonValue>),
-}
-
-impl From<f64> for JsonValue {
-    fn from(s: f64) -> Self {
-        JsonValue::Number(s)
-    }
-}
-
-impl From<String> for JsonValue {
-    fn from(s: String) -> Self {
-        JsonValue::String(s)
-    }
-}
-
-impl From<HashMap<String, JsonValue>> for JsonValue {
-    fn from(s: HashMap<String, JsonValue>) -> Self {
-        JsonValue::Object(s)
-    }
-}
 
-impl From<Vec<JsonValue>> for JsonValue {
-    fn from(s: Vec<JsonValue>) -> Self {
-        JsonValue::List(s)
+    if failed {
+        exit(1)
     }
 }
-
-fn main() -> std::io::Result<()> {
-    use std::fs::File;
-    use std::io::Read;
-
-    let mut file = File::open("E:/Programming/Rust/parser-combin/text.json")?;
-
-    let mut doc = String::new();
-    file.read_to_string(&mut doc)?;
-    let doc: &str = &doc;
-
-    let mut parser = value();
-
-    let (doc, value) = parser.parse_mut(doc);
-    println!("{:#?}", value);
-    // let (doc, value) = parser.parse(doc);
-    // println!("{:#?}", value);
-    println!("{}", doc);
-
-    Ok(())
-}
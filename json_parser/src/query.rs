@@ -0,0 +1,510 @@
+use super::*;
+
+use crate::grammar::{any_char, digits, eat_white_space, literal, match_literal};
+
+use parser_combinators::introspect::{Describe, Grammar};
+use parser_combinators::repeat::FoundZero;
+
+use either::Either;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+impl JsonValue {
+    /// Looks up a value by an RFC 6901 JSON Pointer, such as `/a/0/b`
+    ///
+    /// The empty pointer refers to the whole document, `~1` stands for `/`
+    /// and `~0` stands for `~` inside of a reference token
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+
+        if !pointer.starts_with('/') {
+            return None;
+        }
+
+        pointer[1..]
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .try_fold(self, |value, token| match value {
+                JsonValue::Object(object) => object.get(&token),
+                JsonValue::List(list) => list_index(&token).and_then(|i| list.get(i)),
+                _ => None,
+            })
+    }
+
+    /// Finds every value matched by a JSONPath expression, see `JsonPath` for what is supported
+    pub fn query(&self, path: &str) -> Result<Vec<&JsonValue>, PathError> {
+        Ok(path.parse::<JsonPath>()?.select(self))
+    }
+}
+
+/// Array indices in a pointer are plain decimal numbers without leading zeros
+fn list_index(token: &str) -> Option<usize> {
+    let is_digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+
+    if is_digits && (token == "0" || !token.starts_with('0')) {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// A JSONPath expression
+///
+/// This supports the following subset of JSONPath
+///
+/// * `$` the root of the document, which every path starts with
+/// * `.name` or `['name']` a member of an object
+/// * `.*` or `[*]` every item of a list or member of an object
+/// * `[1]` an item of a list, where negative indices count from the end
+/// * `[start:end:step]` a slice of a list, where each part is optional
+/// * `[?(@.a.b < 10)]` the items or members for which the comparison holds, using any of
+///   `==`, `!=`, `<`, `<=`, `>` or `>=` against a JSON literal; leaving out the comparison
+///   only checks that `@.a.b` exists
+/// * `..` followed by any of the above, which applies it to every value in the document
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, Option<i64>),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    path: Vec<String>,
+    comparison: Option<(Comparison, JsonValue)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A malformed JSONPath expression, along with the column it stopped making sense at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathError {
+    column: usize,
+}
+
+impl PathError {
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSONPath at column {}", self.column)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Hides how `parser` works from the grammar of JSONPath, which shows it as a rule called
+/// `name` instead, for the parts that follow the rules of JSON
+fn opaque<P, Output, Error>(
+    name: &'static str,
+    parser: P,
+) -> impl for<'a> Parser<&'a str, Output = Output, Error = Error> + Describe
+where
+    P: for<'a> Parser<&'a str, Output = Output, Error = Error>,
+{
+    (move |s: &mut &str| {
+        let (rest, out) = parser.parse(*s);
+        *s = rest;
+        out
+    })
+    .as_parser_in_place()
+    .trace(name)
+}
+
+fn white_space() -> impl for<'a> Parser<&'a str, Output = (), Error = util::Infallible> + Describe {
+    opaque("white_space", eat_white_space(Dialect::strict()))
+}
+
+fn name() -> impl for<'a> Parser<&'a str, Output = String, Error = FoundZero> + Describe {
+    opaque(
+        "name",
+        any_char()
+            .filter(|&c: &char| c.is_alphanumeric() || c == '_')
+            .one_or_more(String::new),
+    )
+}
+
+fn quoted_name() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Describe {
+    match_literal("'")
+        .then(opaque(
+            "quoted_text",
+            any_char()
+                .filter(|&c: &char| c != '\'')
+                .zero_or_more(String::new),
+        ))
+        .map_both(util::snd, util::unwrap_left)
+        .then(match_literal("'"))
+        .map_both(util::fst, drop)
+        .or(opaque("string", grammar::string(Dialect::strict())))
+        .map_both(Either::into_inner, drop)
+        .trace("quoted_name")
+}
+
+fn index() -> impl for<'a> Parser<&'a str, Output = i64, Error = ()> + Describe {
+    match_literal("-")
+        .optional()
+        .then(opaque("digits", digits()))
+        .map_both(
+            |(sign, digits): (Result<(), _>, String)| match sign {
+                Ok(()) => format!("-{}", digits),
+                Err(_) => digits,
+            },
+            drop,
+        )
+        .flat_map(|index: String| index.parse().map_err(drop))
+        .trace("index")
+}
+
+/// An index of a slice, which is an error if it was left out
+type SliceIndex = Result<i64, ()>;
+
+fn slice() -> impl for<'a> Parser<&'a str, Output = Selector, Error = ()> + Describe {
+    index()
+        .optional()
+        .then(white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(match_literal(":"))
+        .map_both(util::fst, drop)
+        .then(white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(index().optional())
+        .map_err(util::unwrap_left)
+        .then(
+            white_space()
+                .then(match_literal(":"))
+                .then(white_space())
+                .then(index().optional())
+                .map(util::snd)
+                .optional(),
+        )
+        .map_both(
+            |((start, end), step): ((SliceIndex, SliceIndex), Result<SliceIndex, _>)| {
+                Selector::Slice(start.ok(), end.ok(), step.ok().and_then(Result::ok))
+            },
+            util::unwrap_left,
+        )
+        .trace("slice")
+}
+
+fn comparison() -> impl for<'a> Parser<&'a str, Output = Comparison, Error = ()> + Describe {
+    // the two character operators go first, so that `<=` isn't read as `<`
+    match_literal("==")
+        .map(|()| Comparison::Eq)
+        .or(match_literal("!=").map(|()| Comparison::Ne))
+        .map(Either::into_inner)
+        .or(match_literal("<=").map(|()| Comparison::Le))
+        .map(Either::into_inner)
+        .or(match_literal(">=").map(|()| Comparison::Ge))
+        .map(Either::into_inner)
+        .or(match_literal("<").map(|()| Comparison::Lt))
+        .map(Either::into_inner)
+        .or(match_literal(">").map(|()| Comparison::Gt))
+        .map_both(Either::into_inner, drop)
+        .trace("comparison")
+}
+
+fn filter_value() -> impl for<'a> Parser<&'a str, Output = JsonValue, Error = ()> + Describe {
+    literal()
+        .or(opaque("number", grammar::number(Dialect::strict())).map(JsonValue::from))
+        .map(Either::into_inner)
+        .or(quoted_name().map(JsonValue::from))
+        .map_both(Either::into_inner, drop)
+        .trace("filter_value")
+}
+
+/// The comparison of a filter, which is an error if it was left out
+type Condition<E> = Result<(Comparison, JsonValue), E>;
+
+fn filter() -> impl for<'a> Parser<&'a str, Output = Selector, Error = ()> + Describe {
+    match_literal("?(")
+        .then(white_space())
+        .then(match_literal("@"))
+        .map_err(drop)
+        .then(
+            match_literal(".")
+                .then(name())
+                .map(util::snd)
+                .zero_or_more(Vec::new),
+        )
+        .map_both(util::snd, util::unwrap_left)
+        .then(
+            white_space()
+                .then(comparison())
+                .map_both(util::snd, drop)
+                .then(white_space())
+                .map_both(util::fst, drop)
+                .then(filter_value())
+                .optional(),
+        )
+        .map_err(util::unwrap_left)
+        .then(white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(match_literal(")"))
+        .map_both(
+            |((path, comparison), ()): ((Vec<String>, Condition<_>), ())| {
+                Selector::Filter(Filter {
+                    path,
+                    comparison: comparison.ok(),
+                })
+            },
+            drop,
+        )
+        .trace("filter")
+}
+
+fn bracket() -> impl for<'a> Parser<&'a str, Output = Selector, Error = ()> + Describe {
+    match_literal("[")
+        .then(white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(
+            match_literal("*")
+                .map(|()| Selector::Wildcard)
+                .or(filter())
+                .map(Either::into_inner)
+                .or(slice())
+                .map(Either::into_inner)
+                .or(index().map(Selector::Index))
+                .map(Either::into_inner)
+                .or(quoted_name().map(Selector::Name))
+                .map(Either::into_inner),
+        )
+        .map_both(util::snd, drop)
+        .then(white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(match_literal("]"))
+        .map_both(util::fst, drop)
+        .trace("bracket")
+}
+
+fn dotted() -> impl for<'a> Parser<&'a str, Output = Selector, Error = ()> + Describe {
+    match_literal("*")
+        .map(|()| Selector::Wildcard)
+        .or(name().map(Selector::Name))
+        .map_both(Either::into_inner, drop)
+        .trace("dotted")
+}
+
+fn segment() -> impl for<'a> Parser<&'a str, Output = Segment, Error = ()> + Describe {
+    match_literal("..")
+        .then(dotted().or(bracket()).map(Either::into_inner))
+        .map(|((), selector)| Segment::Descendant(selector))
+        .or(match_literal(".")
+            .then(dotted())
+            .map(|((), selector)| Segment::Child(selector)))
+        .map(Either::into_inner)
+        .or(bracket().map(Segment::Child))
+        .map_both(Either::into_inner, drop)
+        .trace("segment")
+}
+
+fn path() -> impl for<'a> Parser<&'a str, Output = Vec<Segment>, Error = ()> + Describe {
+    match_literal("$")
+        .then(segment().zero_or_more(Vec::new))
+        .map_both(util::snd, drop)
+        .trace("path")
+}
+
+impl FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, PathError> {
+        let (rest, segments) = path().parse(s);
+
+        match segments {
+            Ok(segments) if rest.is_empty() => Ok(Self { segments }),
+            _ => Err(PathError {
+                column: s[..s.len() - rest.len()].chars().count() + 1,
+            }),
+        }
+    }
+}
+
+impl JsonPath {
+    /// The grammar of the paths that `JsonPath` reads, which can be written out as EBNF with
+    /// `Grammar::to_ebnf` or drawn with `Grammar::to_svg`
+    ///
+    /// Names, digits, numbers, strings and white space are special sequences, where names are
+    /// letters, digits and `_`, text in single quotes can't hold a `'`, and the others are
+    /// as in JSON.
+    pub fn grammar() -> Grammar {
+        Grammar::new(&path())
+    }
+
+    /// Finds every value in `value` that this path matches
+    pub fn select<'v>(&self, value: &'v JsonValue) -> Vec<&'v JsonValue> {
+        let mut current = vec![value];
+
+        for segment in &self.segments {
+            let mut next = Vec::new();
+
+            for value in current {
+                match segment {
+                    Segment::Child(selector) => selector.select(value, &mut next),
+                    Segment::Descendant(selector) => {
+                        for_each_descendant(value, &mut |value| selector.select(value, &mut next))
+                    }
+                }
+            }
+
+            current = next;
+        }
+
+        current
+    }
+}
+
+/// Calls `f` on `value` and then on everything nested inside of it
+fn for_each_descendant<'v>(value: &'v JsonValue, f: &mut dyn FnMut(&'v JsonValue)) {
+    f(value);
+
+    match value {
+        JsonValue::List(list) => list.iter().for_each(|item| for_each_descendant(item, f)),
+        JsonValue::Object(object) => object
+            .values()
+            .for_each(|item| for_each_descendant(item, f)),
+        _ => (),
+    }
+}
+
+fn children(value: &JsonValue) -> Box<dyn Iterator<Item = &JsonValue> + '_> {
+    match value {
+        JsonValue::List(list) => Box::new(list.iter()),
+        JsonValue::Object(object) => Box::new(object.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+impl Selector {
+    fn select<'v>(&self, value: &'v JsonValue, out: &mut Vec<&'v JsonValue>) {
+        match (self, value) {
+            (Selector::Name(name), JsonValue::Object(object)) => out.extend(object.get(name)),
+            (Selector::Wildcard, _) => out.extend(children(value)),
+            (&Selector::Index(index), JsonValue::List(list)) => {
+                let index = if index < 0 {
+                    index + list.len() as i64
+                } else {
+                    index
+                };
+
+                if index >= 0 {
+                    out.extend(list.get(index as usize))
+                }
+            }
+            (&Selector::Slice(start, end, step), JsonValue::List(list)) => {
+                out.extend(slice_indices(list.len(), start, end, step).map(|i| &list[i]))
+            }
+            (Selector::Filter(filter), _) => {
+                out.extend(children(value).filter(|item| filter.matches(item)))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Works out which indices a slice covers, following the same rules as Python's slices
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+) -> impl Iterator<Item = usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+
+    let normalize = |i: i64| if i < 0 { i + len } else { i };
+
+    let (mut i, end) = if step >= 0 {
+        (
+            start.map_or(0, normalize).max(0).min(len),
+            end.map_or(len, normalize).max(0).min(len),
+        )
+    } else {
+        (
+            start.map_or(len - 1, normalize).max(-1).min(len - 1),
+            end.map_or(-1, normalize).max(-1).min(len - 1),
+        )
+    };
+
+    std::iter::from_fn(move || {
+        let in_range = match step.cmp(&0) {
+            Ordering::Greater => i < end,
+            Ordering::Less => i > end,
+            // a step of zero would never get anywhere
+            Ordering::Equal => false,
+        };
+
+        if in_range {
+            let index = i as usize;
+            i += step;
+            Some(index)
+        } else {
+            None
+        }
+    })
+}
+
+impl Filter {
+    fn matches(&self, item: &JsonValue) -> bool {
+        let target = self.path.iter().try_fold(item, |value, name| match value {
+            JsonValue::Object(object) => object.get(name),
+            _ => None,
+        });
+
+        match (target, &self.comparison) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(target), Some((comparison, expected))) => comparison.holds(target, expected),
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, a: &JsonValue, b: &JsonValue) -> bool {
+        // only numbers and strings have an order, everything else can only be compared for equality
+        let ordering = match (a, b) {
+            (JsonValue::Number(a), JsonValue::Number(b)) => a.partial_cmp(b),
+            (JsonValue::String(a), JsonValue::String(b)) => Some(a.cmp(b)),
+            _ if a == b => Some(Ordering::Equal),
+            _ => None,
+        };
+
+        match self {
+            Comparison::Eq => a == b,
+            Comparison::Ne => a != b,
+            Comparison::Lt => ordering == Some(Ordering::Less),
+            Comparison::Le => ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal),
+            Comparison::Gt => ordering == Some(Ordering::Greater),
+            Comparison::Ge => {
+                ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal)
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use super::*;
+
+use std::fmt;
+use std::io;
+
+/// Controls how a `JsonValue` is written out
+///
+/// The default writes compact JSON, with object keys in whatever order the object holds them
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Put every list item and object member on its own line, indented by this many spaces
+    /// per level of nesting
+    pub indent: Option<usize>,
+    /// Write object members ordered by their keys, which makes the output deterministic
+    pub sort_keys: bool,
+    /// Escape every non-ASCII character as `\uXXXX`, using surrogate pairs where needed
+    pub ascii_only: bool,
+}
+
+impl WriteOptions {
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(2),
+            ..Self::default()
+        }
+    }
+}
+
+/// A `JsonValue` that is displayed with the given `WriteOptions`
+#[derive(Debug, Clone, Copy)]
+pub struct Formatted<'a> {
+    value: &'a JsonValue,
+    options: WriteOptions,
+}
+
+impl JsonValue {
+    pub fn formatted(&self, options: WriteOptions) -> Formatted<'_> {
+        Formatted {
+            value: self,
+            options,
+        }
+    }
+
+    pub fn to_string_with(&self, options: WriteOptions) -> String {
+        self.formatted(options).to_string()
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        self.to_string_with(WriteOptions::pretty())
+    }
+
+    /// Serializes the value into `writer`
+    ///
+    /// The output is written in many small pieces, so a buffered writer should be used
+    /// for files and sockets
+    pub fn write_to<W: io::Write>(&self, mut writer: W, options: WriteOptions) -> io::Result<()> {
+        write!(writer, "{}", self.formatted(options))
+    }
+}
+
+/// Formats the value as compact JSON, or as JSON indented by two spaces
+/// when the alternate flag is used (`{:#}`)
+///
+/// JSON has no way to write NaN or the infinities, so they come out as `null`. The parser never
+/// produces them from standard JSON, as it rejects numbers too large for an `f64`, so anything it
+/// parses reads back the same after being written out.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = if f.alternate() {
+            WriteOptions::pretty()
+        } else {
+            WriteOptions::compact()
+        };
+
+        write_value(f, self, &options, 0)
+    }
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self.value, &self.options, 0)
+    }
+}
+
+fn write_value(
+    f: &mut fmt::Formatter<'_>,
+    value: &JsonValue,
+    options: &WriteOptions,
+    depth: usize,
+) -> fmt::Result {
+    match value {
+        JsonValue::Null => f.write_str("null"),
+        JsonValue::Bool(b) => write!(f, "{}", b),
+        // JSON has no representation for NaN or the infinities
+        JsonValue::Number(n) if !n.is_finite() => f.write_str("null"),
+        JsonValue::Number(n) => write_number(f, *n),
+        JsonValue::String(s) => write_string(f, s, options),
+        JsonValue::List(list) => {
+            if list.is_empty() {
+                return f.write_str("[]");
+            }
+
+            f.write_str("[")?;
+            for (i, item) in list.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(",")?;
+                }
+                write_newline(f, options, depth + 1)?;
+                write_value(f, item, options, depth + 1)?;
+            }
+            write_newline(f, options, depth)?;
+            f.write_str("]")
+        }
+        JsonValue::Object(object) => {
+            if object.is_empty() {
+                return f.write_str("{}");
+            }
+
+            let mut members: Vec<_> = object.iter().collect();
+            if options.sort_keys {
+                members.sort_by_key(|&(key, _)| key);
+            }
+
+            f.write_str("{")?;
+            for (i, (key, item)) in members.into_iter().enumerate() {
+                if i != 0 {
+                    f.write_str(",")?;
+                }
+                write_newline(f, options, depth + 1)?;
+                write_string(f, key, options)?;
+                f.write_str(if options.indent.is_some() { ": " } else { ":" })?;
+                write_value(f, item, options, depth + 1)?;
+            }
+            write_newline(f, options, depth)?;
+            f.write_str("}")
+        }
+    }
+}
+
+fn write_number(f: &mut fmt::Formatter<'_>, n: f64) -> fmt::Result {
+    // `Display` never uses an exponent, which spells out numbers like `1e300` digit by digit,
+    // while `Debug` switches to one for very large and small numbers
+    if n.fract() == 0.0 && n.abs() < 1e16 {
+        write!(f, "{}", n)
+    } else {
+        write!(f, "{:?}", n)
+    }
+}
+
+fn write_newline(f: &mut fmt::Formatter<'_>, options: &WriteOptions, depth: usize) -> fmt::Result {
+    match options.indent {
+        Some(indent) => write!(f, "\n{:1$}", "", indent * depth),
+        None => Ok(()),
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str, options: &WriteOptions) -> fmt::Result {
+    f.write_str("\"")?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\u{8}' => f.write_str("\\b")?,
+            '\u{c}' => f.write_str("\\f")?,
+            c if c < '\u{20}' || (options.ascii_only && !c.is_ascii()) => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(f, "\\u{:04x}", unit)?;
+                }
+            }
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    f.write_str("\"")
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for JsonValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{SerializeMap, SerializeSeq};
+
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Bool(b) => serializer.serialize_bool(*b),
+            // integers that an `f64` holds exactly are serialized as integers,
+            // for the benefit of formats that tell the two apart, apart from `-0.0`,
+            // which would lose its sign
+            JsonValue::Number(n)
+                if n.fract() == 0.0
+                    && n.abs() < 9_007_199_254_740_992.0
+                    && !(n.is_sign_negative() && *n == 0.0) =>
+            {
+                serializer.serialize_i64(*n as i64)
+            }
+            JsonValue::Number(n) => serializer.serialize_f64(*n),
+            JsonValue::String(s) => serializer.serialize_str(s),
+            JsonValue::List(list) => {
+                let mut seq = serializer.serialize_seq(Some(list.len()))?;
+                for item in list {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(object) => {
+                let mut map = serializer.serialize_map(Some(object.len()))?;
+                for (key, value) in object {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
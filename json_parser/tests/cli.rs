@@ -0,0 +1,143 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the command line tool on `stdin`, returning its exit code, standard output and
+/// standard error
+fn run(args: &[&str], stdin: &[u8]) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json_parser"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+const DOC: &[u8] = r#"{"b": [1, 2.5], "a": "xé"}"#.as_bytes();
+
+#[test]
+fn output_modes() {
+    assert_eq!(
+        run(&["--sort-keys"], DOC),
+        (
+            0,
+            "{\n  \"a\": \"x\u{e9}\",\n  \"b\": [\n    1,\n    2.5\n  ]\n}\n".to_string(),
+            String::new()
+        )
+    );
+    assert_eq!(
+        run(&["--compact", "--sort-keys", "--ascii"], DOC).1,
+        "{\"a\":\"x\\u00e9\",\"b\":[1,2.5]}\n"
+    );
+    assert_eq!(
+        run(&["--indent", "1", "--sort-keys"], DOC).1,
+        "{\n \"a\": \"x\u{e9}\",\n \"b\": [\n  1,\n  2.5\n ]\n}\n"
+    );
+    assert_eq!(run(&["--validate"], DOC), (0, String::new(), String::new()));
+}
+
+#[test]
+fn queries() {
+    assert_eq!(run(&["--query", "/b/1"], DOC).1, "2.5\n");
+    assert_eq!(run(&["--query", "$.b[*]"], DOC).1, "1\n2.5\n");
+
+    let (code, stdout, stderr) = run(&["--query", "/c"], DOC);
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert_eq!(stderr, "error: - has no value at `/c`\n");
+
+    let (code, _, stderr) = run(&["--query", "$.c"], DOC);
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "error: - has no values matching `$.c`\n");
+}
+
+#[test]
+fn dialects() {
+    let doc = b"// a comment\n[1, 2,]";
+
+    assert_eq!(run(&["--compact"], doc).0, 1);
+    assert_eq!(run(&["--compact", "--jsonc"], doc).1, "[1,2]\n");
+    assert_eq!(run(&["--compact", "--json5"], b"{a: 0x10}").1, "{\"a\":16}\n");
+}
+
+#[test]
+fn errors_point_at_their_line_and_column() {
+    let (code, stdout, stderr) = run(&[], b"{\n  \"a\": tru\n}");
+
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert_eq!(
+        stderr,
+        "error: expected a value\n \
+         --> -:2:8\n  \
+         |\n\
+         2 |   \"a\": tru\n  \
+         |        ^\n"
+    );
+}
+
+#[test]
+fn invalid_utf8_is_a_parse_error() {
+    let (code, _, stderr) = run(&["--validate"], b"[\n  \"a\xff\"]");
+
+    assert_eq!(code, 1);
+    assert_eq!(
+        stderr,
+        "error: invalid UTF-8\n \
+         --> -:2:5\n  \
+         |\n\
+         2 |   \"a\u{fffd}\"]\n  \
+         |     ^\n"
+    );
+}
+
+#[test]
+fn usage_and_io_errors() {
+    let (code, _, stderr) = run(&["--frobnicate"], DOC);
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: unknown option `--frobnicate`\n\nusage:"));
+
+    assert_eq!(run(&["--query"], DOC).0, 2);
+    assert_eq!(run(&["--indent", "x"], DOC).0, 2);
+
+    let (code, _, stderr) = run(&["does/not/exist.json"], b"");
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: could not read does/not/exist.json: "));
+
+    let (code, stdout, _) = run(&["--help"], b"");
+    assert_eq!(code, 0);
+    assert!(stdout.starts_with("usage: json_parser"));
+}
+
+#[test]
+fn every_file_is_read() {
+    let dir = std::env::temp_dir().join(format!("json_parser_cli_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.json");
+    let bad = dir.join("bad.json");
+    std::fs::write(&good, "[true]").unwrap();
+    std::fs::write(&bad, "[true").unwrap();
+
+    // a bad file doesn't stop the ones after it, but still fails the run
+    let (code, stdout, stderr) = run(
+        &[
+            "--compact",
+            bad.to_str().unwrap(),
+            "-",
+            good.to_str().unwrap(),
+        ],
+        b"null",
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "null\n[true]\n");
+    assert!(stderr.starts_with("error: expected `,` or `]`\n"));
+}
@@ -0,0 +1,210 @@
+use json_parser::{parse_json, parse_json_with, Dialect, ErrorKind, JsonValue};
+
+fn parse(doc: &str, dialect: Dialect) -> Result<JsonValue, ErrorKind> {
+    parse_json_with(doc, dialect).map_err(|e| e.kind())
+}
+
+/// Which of strict JSON, JSONC and JSON5 accept `doc`
+fn accepted_by(doc: &str) -> [bool; 3] {
+    [Dialect::strict(), Dialect::jsonc(), Dialect::json5()].map(|d| parse(doc, d).is_ok())
+}
+
+fn json5(doc: &str) -> JsonValue {
+    parse(doc, Dialect::json5()).unwrap_or_else(|e| panic!("{:?}: {}", doc, e))
+}
+
+#[test]
+fn comments() {
+    for doc in [
+        "// before\n[1]",
+        "[1] // after",
+        "[1, /* inside */ 2]",
+        "/* multi\nline */ [1]",
+        "[1 /**/]",
+        "/***/[1]",
+    ] {
+        assert_eq!(accepted_by(doc), [false, true, true], "{:?}", doc);
+    }
+
+    for doc in ["/* unclosed [1]", "/ [1]", "[1] /", "# [1]"] {
+        assert_eq!(accepted_by(doc), [false, false, false], "{:?}", doc);
+    }
+
+    // a comment is white space, so it can't split a token
+    assert!(parse("[tr/**/ue]", Dialect::jsonc()).is_err());
+    // `//` is a comment up to the end of the line, whichever line break ends it
+    assert_eq!(accepted_by("// a\r[1]"), [false, true, true]);
+    assert_eq!(accepted_by("[// a\u{2028}1]"), [false, false, true]);
+}
+
+#[test]
+fn trailing_commas() {
+    for doc in ["[1,]", "[1, 2 ,\n]", r#"{"a": 1,}"#, "[[],]"] {
+        assert_eq!(accepted_by(doc), [false, true, true], "{:?}", doc);
+    }
+
+    // only one, and only after an item
+    for doc in ["[,]", "{,}", "[1,,]", r#"{"a": 1,,}"#, "[,1]"] {
+        assert_eq!(accepted_by(doc), [false, false, false], "{:?}", doc);
+    }
+}
+
+#[test]
+fn json5_strings() {
+    let string = |doc: &str| match json5(doc) {
+        JsonValue::String(s) => s,
+        value => panic!("{:?} isn't a string", value),
+    };
+
+    assert_eq!(string(r#"'say "hi"'"#), "say \"hi\"");
+    assert_eq!(string(r"'it\'s'"), "it's");
+    assert_eq!(string(r#""it\'s""#), "it's");
+    assert_eq!(string(r"'\x41\v\0'"), "A\u{b}\0");
+    // any other character stands for itself, apart from digits
+    assert_eq!(string(r"'\a\q\ '"), "aq ");
+    // a backslash before a line break continues the string on the next line
+    assert_eq!(
+        string("'one \\\ntwo \\\r\nthree \\\u{2028}four'"),
+        "one two three four"
+    );
+
+    for doc in [r"'\1'", r"'\01'", r"'\x4'", "'unclosed", "'two\nlines'"] {
+        assert!(parse(doc, Dialect::json5()).is_err(), "{:?}", doc);
+    }
+
+    assert_eq!(accepted_by("'single'"), [false, false, true]);
+    assert_eq!(accepted_by(r#""\x41""#), [false, false, true]);
+}
+
+#[test]
+fn json5_numbers() {
+    let number = |doc: &str| match json5(doc) {
+        JsonValue::Number(n) => n,
+        value => panic!("{:?} isn't a number", value),
+    };
+
+    assert_eq!(number("0xFF"), 255.0);
+    assert_eq!(number("0Xdecaf"), 912_559.0);
+    assert_eq!(number("-0x10"), -16.0);
+    assert_eq!(number("+1"), 1.0);
+    assert_eq!(number(".5"), 0.5);
+    assert_eq!(number("5."), 5.0);
+    assert_eq!(number("-.5e1"), -5.0);
+    assert_eq!(number("Infinity"), f64::INFINITY);
+    assert_eq!(number("+Infinity"), f64::INFINITY);
+    assert_eq!(number("-Infinity"), f64::NEG_INFINITY);
+    assert!(number("NaN").is_nan());
+    assert!(number("-NaN").is_nan());
+
+    for doc in [
+        "0x", "0xG", ".", "+", "01", "infinity", "nan", "++1", "0x1.5",
+    ] {
+        assert!(parse(doc, Dialect::json5()).is_err(), "{:?}", doc);
+    }
+
+    for doc in ["0x1", "Infinity", "NaN", ".5", "5.", "+1"] {
+        assert_eq!(accepted_by(doc), [false, false, true], "{:?}", doc);
+    }
+}
+
+#[test]
+fn json5_identifier_keys() {
+    let keys = |doc: &str| match json5(doc) {
+        JsonValue::Object(object) => {
+            let mut keys: Vec<_> = object.into_keys().collect();
+            keys.sort();
+            keys
+        }
+        value => panic!("{:?} isn't an object", value),
+    };
+
+    assert_eq!(keys("{a: 1, $b: 2, _c: 3, d4: 4}"), ["$b", "_c", "a", "d4"]);
+    assert_eq!(keys("{caf\u{e9}: 1, \u{3c0}: 2}"), ["caf\u{e9}", "\u{3c0}"]);
+    // escapes stand for the characters they escape
+    assert_eq!(keys(r"{\u0061b: 1, a\u0062c: 2}"), ["ab", "abc"]);
+    assert_eq!(keys("{a\u{200d}b: 1}"), ["a\u{200d}b"]);
+
+    for doc in [
+        "{1a: 1}",
+        "{a b: 1}",
+        "{a-b: 1}",
+        "{\u{200d}a: 1}",
+        // an escape has to stand for a character that could have been written as is
+        r"{\u0031: 1}",
+        r"{a\u0020: 1}",
+        r"{\x61: 1}",
+        r"{\u006: 1}",
+        r"{\uD800: 1}",
+    ] {
+        assert!(parse(doc, Dialect::json5()).is_err(), "{:?}", doc);
+    }
+
+    assert_eq!(accepted_by("{a: 1}"), [false, false, true]);
+}
+
+#[test]
+fn json5_white_space() {
+    for space in [
+        "\u{b}", "\u{c}", "\u{a0}", "\u{feff}", "\u{2028}", "\u{2029}", "\u{1680}", "\u{2000}",
+        "\u{200a}", "\u{202f}", "\u{205f}", "\u{3000}",
+    ] {
+        let doc = format!("{}[1,{}2]{}", space, space, space);
+        assert_eq!(accepted_by(&doc), [false, false, true], "{:?}", space);
+    }
+
+    // the next line character isn't white space in ECMAScript, and neither are zero
+    // width characters other than the byte order mark
+    for other in ["\u{85}", "\u{200b}", "\u{2060}", "\u{180e}"] {
+        let doc = format!("[1,{}2]", other);
+        assert_eq!(accepted_by(&doc), [false, false, false], "{:?}", other);
+    }
+}
+
+#[test]
+fn json5_example() {
+    // the example from json5.org
+    let doc = r#"// comments
+{
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+}"#;
+    let expected = r#"{
+  "unquoted": "and you can quote me on that",
+  "singleQuotes": "I can use \"double quotes\" here",
+  "lineBreaks": "Look, Mom! No \\n's!",
+  "hexadecimal": 912559,
+  "leadingDecimalPoint": 0.8675309, "andTrailing": 8675309,
+  "positiveSign": 1,
+  "trailingComma": "in objects", "andIn": ["arrays"],
+  "backwardsCompatible": "with JSON"
+}"#;
+
+    assert_eq!(json5(doc), parse_json(expected).unwrap());
+}
+
+#[test]
+fn features_can_be_picked_one_at_a_time() {
+    let only_single_quotes = Dialect {
+        single_quotes: true,
+        ..Dialect::strict()
+    };
+
+    assert!(parse("['a']", only_single_quotes).is_ok());
+    assert!(parse("['a',]", only_single_quotes).is_err());
+    assert!(parse("[0x1]", only_single_quotes).is_err());
+
+    let only_white_space = Dialect {
+        unicode_white_space: true,
+        ..Dialect::strict()
+    };
+
+    assert!(parse("[\u{a0}1]", only_white_space).is_ok());
+    assert!(parse("[1] // no", only_white_space).is_err());
+}
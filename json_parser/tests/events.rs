@@ -0,0 +1,221 @@
+use json_parser::{parse_json, parse_json_with, Dialect, ErrorKind, Event, JsonEvents, Scalar};
+
+use std::borrow::Cow;
+
+/// Every event of `doc`, stopping at the first error
+fn events(doc: &str) -> Result<Vec<Event<'_>>, (ErrorKind, usize)> {
+    JsonEvents::new(doc)
+        .collect::<Result<_, _>>()
+        .map_err(|e| (e.kind(), e.offset()))
+}
+
+fn key(k: &str) -> Event<'_> {
+    Event::Key(Cow::Borrowed(k))
+}
+
+fn string(s: &str) -> Event<'_> {
+    Event::Value(Scalar::String(Cow::Borrowed(s)))
+}
+
+fn number(n: f64) -> Event<'static> {
+    Event::Value(Scalar::Number(n))
+}
+
+#[test]
+fn nested_objects_and_arrays() {
+    use Event::*;
+
+    let doc = r#"{"a": [1, {"b": null}, []], "c": {"d": [true, "x"]}, "e": {}}"#;
+
+    assert_eq!(
+        events(doc).unwrap(),
+        vec![
+            StartObject,
+            key("a"),
+            StartArray,
+            number(1.0),
+            StartObject,
+            key("b"),
+            Value(Scalar::Null),
+            EndObject,
+            StartArray,
+            EndArray,
+            EndArray,
+            key("c"),
+            StartObject,
+            key("d"),
+            StartArray,
+            Value(Scalar::Bool(true)),
+            string("x"),
+            EndArray,
+            EndObject,
+            key("e"),
+            StartObject,
+            EndObject,
+            EndObject,
+        ]
+    );
+}
+
+#[test]
+fn scalars() {
+    assert_eq!(events(" null ").unwrap(), vec![Event::Value(Scalar::Null)]);
+    assert_eq!(
+        events("false").unwrap(),
+        vec![Event::Value(Scalar::Bool(false))]
+    );
+    assert_eq!(events("-1.5e2").unwrap(), vec![number(-150.0)]);
+    assert_eq!(events(r#""plain""#).unwrap(), vec![string("plain")]);
+
+    // strings are only copied when they have escapes in them
+    let found = events(r#"{"k\u00e9y": "a\nb", "key": "ab"}"#).unwrap();
+    assert!(matches!(&found[1], Event::Key(Cow::Owned(k)) if k == "k\u{e9}y"));
+    assert!(matches!(&found[2], Event::Value(Scalar::String(Cow::Owned(s))) if s == "a\nb"));
+    assert!(matches!(&found[3], Event::Key(Cow::Borrowed("key"))));
+    assert!(matches!(
+        &found[4],
+        Event::Value(Scalar::String(Cow::Borrowed("ab")))
+    ));
+}
+
+#[test]
+fn depth_follows_the_events() {
+    let mut events = JsonEvents::new(r#"[{"a": [1]}, 2]"#);
+    let mut depths = Vec::new();
+
+    while let Some(event) = events.next() {
+        event.unwrap();
+        depths.push(events.depth());
+    }
+
+    assert_eq!(depths, [1, 2, 2, 3, 3, 2, 1, 1, 0]);
+}
+
+#[test]
+fn errors_are_where_parse_json_puts_them() {
+    for doc in [
+        "",
+        "[",
+        "[1",
+        "[1,",
+        "{",
+        "{\"a\"",
+        "{\"a\":",
+        "{\"a\": 1",
+        "{\"a\": 1,",
+        "\"abc",
+        "[tru",
+        "[1 2]",
+        "{1: 2}",
+        "[1,]",
+        "[1] 2",
+        "[1e999]",
+        "[\"\\x\"]",
+    ] {
+        let expected = parse_json(doc).unwrap_err();
+        let found = events(doc).unwrap_err();
+        assert_eq!(found, (expected.kind(), expected.offset()), "{:?}", doc);
+    }
+
+    assert_eq!(events("[1, 2"), Err((ErrorKind::UnclosedList, 5)));
+    assert_eq!(events(r#"{"a" 1}"#), Err((ErrorKind::ExpectedColon, 5)));
+}
+
+#[test]
+fn events_stop_after_an_error() {
+    let mut events = JsonEvents::new("[1, x, 2]");
+
+    assert_eq!(events.next(), Some(Ok(Event::StartArray)));
+    assert_eq!(events.next(), Some(Ok(number(1.0))));
+    assert_eq!(
+        events.next().unwrap().unwrap_err().kind(),
+        ErrorKind::ExpectedValue
+    );
+    assert_eq!(events.next(), None);
+}
+
+#[test]
+fn dialects() {
+    let doc = "// comment\n{a: [1, 'two', 0x3,], /* end */}";
+    let found: Vec<_> = JsonEvents::with_dialect(doc, Dialect::json5())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(
+        found,
+        vec![
+            Event::StartObject,
+            Event::Key(Cow::Owned("a".to_string())),
+            Event::StartArray,
+            number(1.0),
+            Event::Value(Scalar::String(Cow::Owned("two".to_string()))),
+            number(3.0),
+            Event::EndArray,
+            Event::EndObject,
+        ]
+    );
+
+    // each dialect only allows what it turns on
+    for (doc, dialect, accepted) in [
+        ("[1,]", Dialect::strict(), false),
+        ("[1,]", Dialect::jsonc(), true),
+        ("{\"a\": 1,}", Dialect::jsonc(), true),
+        ("[,]", Dialect::jsonc(), false),
+        ("/**/ 1", Dialect::strict(), false),
+        ("/**/ 1", Dialect::jsonc(), true),
+        ("{a: 1}", Dialect::jsonc(), false),
+        ("{a: 1}", Dialect::json5(), true),
+        ("['a']", Dialect::jsonc(), false),
+        ("[+Infinity]", Dialect::json5(), true),
+    ] {
+        let found = JsonEvents::with_dialect(doc, dialect).collect::<Result<Vec<_>, _>>();
+        assert_eq!(found.is_ok(), accepted, "{:?}", doc);
+        assert_eq!(
+            found.is_ok(),
+            parse_json_with(doc, dialect).is_ok(),
+            "{:?}",
+            doc
+        );
+    }
+}
+
+#[test]
+fn read_value_matches_parse_json() {
+    for doc in [
+        "null",
+        "[]",
+        "{}",
+        "\"text\"",
+        r#"{"a": [1, {"b": null}, []], "c": {"d": [true, "x\ty"]}, "a2": -0.5}"#,
+        "[[[[1]], 2], [3], {\"x\": [{}]}]",
+        // the last of a repeated key wins, as in parse_json
+        r#"{"a": 1, "a": 2}"#,
+    ] {
+        let value = JsonEvents::new(doc).read_value().unwrap();
+        assert_eq!(value, Some(parse_json(doc).unwrap()), "{}", doc);
+    }
+}
+
+#[test]
+fn read_value_picks_out_parts() {
+    let doc = r#"{"skip": [1, 2], "want": {"x": [true]}, "last": 3}"#;
+    let mut events = JsonEvents::new(doc);
+
+    assert_eq!(events.next(), Some(Ok(Event::StartObject)));
+    assert_eq!(events.next(), Some(Ok(key("skip"))));
+    events.read_value().unwrap();
+    assert_eq!(events.next(), Some(Ok(key("want"))));
+    assert_eq!(
+        events.read_value().unwrap(),
+        Some(parse_json(r#"{"x": [true]}"#).unwrap())
+    );
+    // a key is read along with its value
+    assert_eq!(events.read_value().unwrap(), Some(parse_json("3").unwrap()));
+    // and the end of the object, then of the document, gives nothing
+    assert_eq!(events.read_value().unwrap(), None);
+    assert_eq!(events.depth(), 0);
+    assert_eq!(events.read_value().unwrap(), None);
+
+    let e = JsonEvents::new("[1, [2,").read_value().unwrap_err();
+    assert_eq!(e.kind(), parse_json("[1, [2,").unwrap_err().kind());
+}
@@ -0,0 +1,41 @@
+//! Runs the parser over the `test_parsing` files of JSONTestSuite
+//! (https://github.com/nst/JSONTestSuite)
+//!
+//! Files starting with `y_` must be accepted and files starting with `n_` must be rejected.
+//! Parsers are free to go either way on `i_` files, so those only have to not panic.
+
+use json_parser::parse_json;
+
+use std::fs;
+use std::path::Path;
+
+fn parse(bytes: &[u8]) -> bool {
+    // a document that isn't UTF-8 can't even be handed to the parser
+    std::str::from_utf8(bytes).is_ok_and(|doc| parse_json(doc).is_ok())
+}
+
+#[test]
+fn json_test_suite() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/JSONTestSuite/test_parsing");
+    let mut failures = Vec::new();
+    let mut count = 0;
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        let accepted = parse(&fs::read(&path).unwrap());
+
+        count += 1;
+
+        match &name[..2] {
+            "y_" if !accepted => failures.push(format!("rejected {}", name)),
+            "n_" if accepted => failures.push(format!("accepted {}", name)),
+            "y_" | "n_" | "i_" => (),
+            _ => panic!("unexpected file {}", name),
+        }
+    }
+
+    // every file of upstream's test_parsing, so that none can go missing unnoticed
+    assert_eq!(count, 318, "expected all 318 test files");
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
@@ -0,0 +1,122 @@
+use json_parser::{parse_json, parse_json_limited, Dialect, ErrorKind, Limits};
+
+fn lists(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+fn objects(depth: usize) -> String {
+    format!("{}null{}", r#"{"a":"#.repeat(depth), "}".repeat(depth))
+}
+
+fn limited(doc: &str, limits: Limits) -> Result<json_parser::JsonValue, ErrorKind> {
+    parse_json_limited(doc, Dialect::strict(), limits).map_err(|e| e.kind())
+}
+
+#[test]
+fn default_depth_limit() {
+    let max = Limits::default().max_depth;
+
+    // the whole default depth has to fit on the stack, even in a debug build
+    assert!(parse_json(&lists(max)).is_ok());
+    assert!(parse_json(&objects(max)).is_ok());
+    assert!(parse_json_limited(&lists(max), Dialect::json5(), Limits::default()).is_ok());
+
+    let e = parse_json(&lists(max + 1)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::DepthExceeded);
+    assert_eq!(e.offset(), max);
+
+    let e = parse_json(&objects(max + 1)).unwrap_err();
+    assert_eq!(e.kind(), ErrorKind::DepthExceeded);
+    assert_eq!(e.offset(), max * r#"{"a":"#.len());
+}
+
+#[test]
+fn depth_limit() {
+    let limits = Limits {
+        max_depth: 3,
+        ..Limits::default()
+    };
+
+    assert!(limited(&lists(3), limits).is_ok());
+    assert_eq!(limited(&lists(4), limits), Err(ErrorKind::DepthExceeded));
+    assert!(limited(&objects(3), limits).is_ok());
+    assert_eq!(limited(&objects(4), limits), Err(ErrorKind::DepthExceeded));
+
+    let none = Limits {
+        max_depth: 0,
+        ..Limits::default()
+    };
+
+    assert!(limited("1", none).is_ok());
+    assert_eq!(limited("[]", none), Err(ErrorKind::DepthExceeded));
+}
+
+#[test]
+fn depth_is_not_bounded_by_the_stack() {
+    let limits = Limits {
+        max_depth: 1000,
+        ..Limits::default()
+    };
+
+    // levels past what a thread's stack holds carry on in new segments of stack
+    assert!(limited(&lists(1000), limits).is_ok());
+    assert!(limited(&objects(1000), limits).is_ok());
+    assert_eq!(limited(&lists(1001), limits), Err(ErrorKind::DepthExceeded));
+}
+
+#[test]
+fn hostile_depth_is_rejected_instead_of_overflowing() {
+    let e = parse_json(&lists(1_000_000)).unwrap_err();
+
+    assert_eq!(e.kind(), ErrorKind::DepthExceeded);
+}
+
+#[test]
+fn item_limit() {
+    let limits = Limits {
+        max_items: 2,
+        ..Limits::default()
+    };
+
+    assert!(limited("[1, 2]", limits).is_ok());
+    assert!(limited(r#"{"a": 1, "b": 2}"#, limits).is_ok());
+    assert_eq!(limited("[1, 2, 3]", limits), Err(ErrorKind::TooManyItems));
+    assert_eq!(
+        limited(r#"{"a": 1, "b": 2, "c": 3}"#, limits),
+        Err(ErrorKind::TooManyItems)
+    );
+    // the limit is per list, not for the whole document
+    assert!(limited("[[1, 2], [3, 4]]", limits).is_ok());
+}
+
+#[test]
+fn no_items_allowed() {
+    let limits = Limits {
+        max_items: 0,
+        ..Limits::default()
+    };
+
+    assert!(limited("[]", limits).is_ok());
+    assert!(limited("{}", limits).is_ok());
+    assert_eq!(limited("[1]", limits), Err(ErrorKind::TooManyItems));
+    assert_eq!(limited(r#"{"a": 1}"#, limits), Err(ErrorKind::TooManyItems));
+
+    let one = Limits {
+        max_items: 1,
+        ..Limits::default()
+    };
+
+    assert!(limited("[1]", one).is_ok());
+    assert_eq!(limited("[1, 2]", one), Err(ErrorKind::TooManyItems));
+}
+
+#[test]
+fn length_limit() {
+    let limits = Limits {
+        max_len: 6,
+        ..Limits::default()
+    };
+
+    assert!(limited("[1, 2]", limits).is_ok());
+    assert_eq!(limited("[1, 23]", limits), Err(ErrorKind::TooLong));
+}
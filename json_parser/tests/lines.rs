@@ -0,0 +1,235 @@
+use json_parser::{parse_json, Dialect, ErrorKind, JsonError, JsonLines, JsonValue, Limits};
+
+use std::io::{self, BufReader, Read};
+
+fn lines(doc: &[u8]) -> Vec<Result<JsonValue, JsonError>> {
+    JsonLines::new(doc).collect()
+}
+
+/// The kind, line, column and offset of an error
+fn located(e: &JsonError) -> (ErrorKind, usize, usize, usize) {
+    (e.kind(), e.line(), e.column(), e.offset())
+}
+
+fn value(doc: &str) -> JsonValue {
+    parse_json(doc).unwrap()
+}
+
+#[test]
+fn one_value_per_line() {
+    let found = lines(b"{\"a\": 1}\n[true, null]\n\"text\"\n42");
+
+    assert_eq!(
+        found,
+        vec![
+            Ok(value(r#"{"a": 1}"#)),
+            Ok(value("[true, null]")),
+            Ok(value("\"text\"")),
+            // the last line doesn't need a line break
+            Ok(value("42")),
+        ]
+    );
+}
+
+#[test]
+fn errors_carry_their_line_and_iteration_goes_on() {
+    let doc = b"[1]\n[2,\n{\"a\" 1}\n[4]\n[5] x\n";
+    let found = lines(doc);
+
+    assert_eq!(found.len(), 5);
+    assert_eq!(found[0], Ok(value("[1]")));
+    assert_eq!(
+        located(found[1].as_ref().unwrap_err()),
+        (ErrorKind::ExpectedValue, 2, 4, "[1]\n[2,".len())
+    );
+    assert_eq!(
+        located(found[2].as_ref().unwrap_err()),
+        (ErrorKind::ExpectedColon, 3, 6, "[1]\n[2,\n{\"a\" ".len())
+    );
+    assert_eq!(found[3], Ok(value("[4]")));
+    assert_eq!(
+        located(found[4].as_ref().unwrap_err()),
+        (ErrorKind::TrailingCharacters, 5, 5, doc.len() - 2)
+    );
+}
+
+#[test]
+fn blank_lines_and_crlf() {
+    let doc = b"\n[1]\r\n   \r\n\t\n\r\n[2]\r\n\n  [3]  \r\n[4\r\n";
+    let found = lines(doc);
+
+    assert_eq!(found.len(), 4);
+    assert_eq!(
+        found[..3],
+        [Ok(value("[1]")), Ok(value("[2]")), Ok(value("[3]"))]
+    );
+
+    // blank lines still count, and the `\r` isn't part of the line
+    let e = found[3].as_ref().unwrap_err();
+    assert_eq!(
+        (e.kind(), e.line(), e.column()),
+        (ErrorKind::UnclosedList, 9, 3)
+    );
+}
+
+#[test]
+fn invalid_utf8_on_one_line() {
+    let found = lines(b"[\"a\"]\n[\"\xc3\xa9\xff\"]\n[\"b\"]\n");
+
+    assert_eq!(found.len(), 3);
+    assert_eq!(found[0], Ok(value(r#"["a"]"#)));
+    // the column counts the characters before the bad byte
+    assert_eq!(
+        located(found[1].as_ref().unwrap_err()),
+        (ErrorKind::InvalidUtf8, 2, 4, "[\"a\"]\n[\"\u{e9}".len())
+    );
+    assert_eq!(found[2], Ok(value(r#"["b"]"#)));
+}
+
+#[test]
+fn dialects_and_limits() {
+    let doc = &b"[1, /* two */ 2,]\n[[[]]]\n"[..];
+
+    let found: Vec<_> = JsonLines::with_dialect(doc, Dialect::jsonc()).collect();
+    assert_eq!(found, vec![Ok(value("[1, 2]")), Ok(value("[[[]]]"))]);
+
+    let limits = Limits {
+        max_depth: 2,
+        ..Limits::default()
+    };
+    let found: Vec<_> = JsonLines::with_limits(doc, Dialect::jsonc(), limits).collect();
+    assert_eq!(found[0], Ok(value("[1, 2]")));
+    assert_eq!(
+        found[1].as_ref().unwrap_err().kind(),
+        ErrorKind::DepthExceeded
+    );
+}
+
+#[test]
+fn long_lines_are_cut_off() {
+    let limits = Limits {
+        max_len: 8,
+        ..Limits::default()
+    };
+    // 8 bytes fit, even with a `\r\n` after them, but 9 don't
+    let doc = b"[1,2,34]\r\n[1,2,345]\n[1]\n[1,2,3456]";
+    let found: Vec<_> = JsonLines::with_limits(&doc[..], Dialect::strict(), limits).collect();
+
+    assert_eq!(found.len(), 4);
+    assert_eq!(found[0], Ok(value("[1,2,34]")));
+    assert_eq!(
+        located(found[1].as_ref().unwrap_err()),
+        (ErrorKind::TooLong, 2, 1, "[1,2,34]\r\n".len())
+    );
+    assert_eq!(found[2], Ok(value("[1]")));
+    assert_eq!(
+        located(found[3].as_ref().unwrap_err()),
+        (
+            ErrorKind::TooLong,
+            4,
+            1,
+            "[1,2,34]\r\n[1,2,345]\n[1]\n".len()
+        )
+    );
+}
+
+#[test]
+fn a_huge_line_is_skipped_without_reading_it_in() {
+    const HUGE: u64 = 64 << 20;
+
+    let limits = Limits {
+        max_len: 1024,
+        ..Limits::default()
+    };
+    let input = io::repeat(b' ').take(HUGE).chain(&b"\n[1]\n"[..]);
+    let mut lines = JsonLines::with_limits(BufReader::new(input), Dialect::strict(), limits);
+
+    assert_eq!(
+        lines.next().unwrap().unwrap_err().kind(),
+        ErrorKind::TooLong
+    );
+    let e = lines.next().unwrap();
+    assert_eq!(e, Ok(value("[1]")));
+    assert!(lines.next().is_none());
+}
+
+/// Gives out `data`, then fails
+struct Failing<'a>(&'a [u8]);
+
+impl Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::other("disconnected"));
+        }
+
+        let len = self.0.len().min(buf.len());
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn io_errors_stop_the_iteration() {
+    let mut lines = JsonLines::new(BufReader::new(Failing(b"[1]\n[2]\n[3")));
+
+    assert_eq!(lines.next(), Some(Ok(value("[1]"))));
+    assert_eq!(lines.next(), Some(Ok(value("[2]"))));
+    // the last line never ends, so it is never parsed
+    assert_eq!(lines.next(), None);
+    assert_eq!(lines.io_error().unwrap().to_string(), "disconnected");
+    assert_eq!(lines.next(), None);
+}
+
+/// A few hundred records, some blank and some broken
+fn records() -> Vec<u8> {
+    let mut doc = Vec::new();
+
+    for i in 0..500 {
+        let line = match i % 7 {
+            0 => Vec::new(),
+            3 => format!("{{\"n\": {}", i).into_bytes(),
+            5 => [&b"[\"\xff"[..], i.to_string().as_bytes(), b"\"]"].concat(),
+            _ => format!("{{\"n\": {}, \"list\": [{}, {}]}}", i, i, i * 2).into_bytes(),
+        };
+        doc.extend_from_slice(&line);
+        doc.extend_from_slice(if i % 2 == 0 { b"\n" } else { b"\r\n" });
+    }
+
+    doc
+}
+
+#[test]
+fn parallel_keeps_the_order_of_the_lines() {
+    let doc = records();
+    let sequential = lines(&doc);
+
+    // every seventh line is blank, starting with the first
+    assert_eq!(sequential.len(), 500 - 72);
+    assert!(sequential.iter().any(Result::is_err));
+
+    for batch_size in [0, 1, 2, 7, 64, 1000] {
+        let parallel: Vec<_> = JsonLines::new(&doc[..]).parallel(batch_size).collect();
+        assert_eq!(parallel, sequential, "batch size {}", batch_size);
+    }
+}
+
+#[test]
+fn parallel_io_errors_stop_the_iteration() {
+    let lines = JsonLines::new(BufReader::new(Failing(b"[1]\n[2]\n[3")));
+    let mut parallel = lines.parallel(4);
+
+    assert_eq!(parallel.next(), Some(Ok(value("[1]"))));
+    assert_eq!(parallel.next(), Some(Ok(value("[2]"))));
+    assert_eq!(parallel.next(), None);
+    assert!(parallel.io_error().is_some());
+}
+
+#[test]
+fn lines_are_read_from_any_buf_read() {
+    // a reader that gives out a byte at a time splits every line across reads
+    let doc = b"[1]\n\n[2]\r\n";
+    let reader = BufReader::with_capacity(1, &doc[..]);
+    let found: Vec<_> = JsonLines::new(reader).collect();
+    assert_eq!(found, vec![Ok(value("[1]")), Ok(value("[2]"))]);
+}
@@ -0,0 +1,62 @@
+use json_parser::{parse_json, JsonPath};
+
+const EBNF: &str = r#"path = "$", { segment } ;
+segment = "..", ( dotted | bracket ) | ".", dotted | bracket ;
+dotted = "*" | name ;
+name = ? name ? ;
+bracket = "[", white_space, ( "*" | filter | slice | index | quoted_name ), white_space, "]" ;
+white_space = ? white_space ? ;
+filter = "?(", white_space, "@", { ".", name }, [ white_space, comparison, white_space, filter_value ], white_space, ")" ;
+comparison = "==" | "!=" | "<=" | ">=" | "<" | ">" ;
+filter_value = "true" | "false" | "null" | number | quoted_name ;
+number = ? number ? ;
+quoted_name = "'", quoted_text, "'" | string ;
+quoted_text = ? quoted_text ? ;
+string = ? string ? ;
+slice = [ index ], white_space, ":", white_space, [ index ], [ white_space, ":", white_space, [ index ] ] ;
+index = [ "-" ], digits ;
+digits = ? digits ? ;
+"#;
+
+#[test]
+fn grammar_as_ebnf() {
+    assert_eq!(JsonPath::grammar().to_ebnf(), EBNF);
+}
+
+#[test]
+fn grammar_as_railroad_diagrams() {
+    let grammar = JsonPath::grammar();
+    let svg = grammar.to_svg();
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    for rule in grammar.rules() {
+        assert!(
+            svg.contains(&format!(">{}</text>", rule.name)),
+            "no diagram for {}",
+            rule.name
+        );
+    }
+    assert_eq!(
+        svg.matches("class=\"title\"").count(),
+        grammar.rules().len()
+    );
+
+    // the comparisons are escaped
+    assert!(svg.contains(">&lt;=</text>"));
+    assert!(svg.contains(">&gt;</text>"));
+}
+
+#[test]
+fn paths_still_parse_after_naming_their_rules() {
+    let doc = parse_json(r#"{"a": [{"b": 1}, {"b": 2}, {"b": 3, "c": "x"}]}"#).unwrap();
+
+    let found = doc.query("$..a[?(@.b >= 2)]['c']").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(doc.query("$.a[-1:]").unwrap().len(), 1);
+    assert_eq!(doc.query("$.a[*].b").unwrap().len(), 3);
+
+    let e = "$.a[?(@.b >> 2)]".parse::<JsonPath>().unwrap_err();
+    assert_eq!(e.column(), 4);
+}
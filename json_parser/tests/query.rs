@@ -0,0 +1,231 @@
+use json_parser::{parse_json, JsonPath, JsonValue, WriteOptions};
+
+const STORE: &str = r#"{
+    "store": {
+        "book": [
+            {"title": "Sayings", "price": 8.95, "category": "reference"},
+            {"title": "Sword", "price": 12.99, "category": "fiction"},
+            {"title": "Moby Dick", "price": 8.99, "category": "fiction", "isbn": "0-553"},
+            {"title": "The Lord", "price": 22.99, "category": "fiction", "isbn": "0-395"}
+        ],
+        "bicycle": {"color": "red", "price": 19.95}
+    },
+    "a/b": 1,
+    "m~n": 2,
+    "": 3,
+    "list": [10, 11, 12, 13, 14, 15]
+}"#;
+
+fn store() -> JsonValue {
+    parse_json(STORE).unwrap()
+}
+
+fn compact(value: &JsonValue) -> String {
+    let options = WriteOptions {
+        sort_keys: true,
+        ..WriteOptions::compact()
+    };
+
+    value.to_string_with(options)
+}
+
+fn pointer(pointer: &str) -> Option<String> {
+    store().pointer(pointer).map(compact)
+}
+
+/// What `path` selects from the store, in the order it was selected
+fn query(path: &str) -> Vec<String> {
+    store()
+        .query(path)
+        .unwrap()
+        .into_iter()
+        .map(compact)
+        .collect()
+}
+
+/// What `path` selects from the store, for paths that go through objects in no set order
+fn query_sorted(path: &str) -> Vec<String> {
+    let mut found = query(path);
+    found.sort();
+    found
+}
+
+#[test]
+fn pointer_lookups() {
+    assert_eq!(pointer("/store/bicycle/color").as_deref(), Some("\"red\""));
+    assert_eq!(pointer("/store/book/0/price").as_deref(), Some("8.95"));
+    assert_eq!(pointer("/list/5").as_deref(), Some("15"));
+
+    // the empty pointer is the whole document, and `/` is the member with an empty name
+    assert_eq!(pointer(""), Some(compact(&store())));
+    assert_eq!(pointer("/").as_deref(), Some("3"));
+
+    assert_eq!(pointer("/store/missing"), None);
+    assert_eq!(pointer("/list/0/deeper"), None);
+    assert_eq!(pointer("store"), None);
+}
+
+#[test]
+fn pointer_escapes() {
+    assert_eq!(pointer("/a~1b").as_deref(), Some("1"));
+    assert_eq!(pointer("/m~0n").as_deref(), Some("2"));
+    // escapes are undone one at a time, so `~01` is a `~` and a `1`, never a `/`
+    let doc = parse_json(r#"{"~1": "tilde one", "/": "slash"}"#).unwrap();
+    assert_eq!(
+        doc.pointer("/~01").map(compact).as_deref(),
+        Some("\"tilde one\"")
+    );
+    assert_eq!(
+        doc.pointer("/~1").map(compact).as_deref(),
+        Some("\"slash\"")
+    );
+    assert_eq!(pointer("/a/b"), None);
+}
+
+#[test]
+fn pointer_indices() {
+    assert_eq!(pointer("/list/0").as_deref(), Some("10"));
+    assert_eq!(pointer("/list/6"), None);
+    // indices are plain decimal numbers
+    for index in ["00", "01", "-1", "+1", " 1", "1.0", "0x1", ""] {
+        assert_eq!(pointer(&format!("/list/{}", index)), None, "{}", index);
+    }
+    // `-` is the item after the last one, which never exists
+    assert_eq!(pointer("/list/-"), None);
+
+    // in an object, the same tokens are just names
+    let doc = parse_json(r#"{"01": "a", "-": "b"}"#).unwrap();
+    assert_eq!(doc.pointer("/01").map(compact).as_deref(), Some("\"a\""));
+    assert_eq!(doc.pointer("/-").map(compact).as_deref(), Some("\"b\""));
+}
+
+#[test]
+fn members_and_indices() {
+    assert_eq!(query("$"), vec![compact(&store())]);
+    assert_eq!(query("$.store.bicycle.color"), vec!["\"red\""]);
+    assert_eq!(query("$['store']['bicycle']['price']"), vec!["19.95"]);
+    assert_eq!(query("$['a/b']"), vec!["1"]);
+
+    assert_eq!(query("$.list[0]"), vec!["10"]);
+    assert_eq!(query("$.list[-1]"), vec!["15"]);
+    assert_eq!(query("$.list[-6]"), vec!["10"]);
+    assert!(query("$.list[6]").is_empty());
+    assert!(query("$.list[-7]").is_empty());
+    assert!(query("$.store.missing").is_empty());
+    // a name doesn't select anything from a list, nor an index from an object
+    assert!(query("$.list.length").is_empty());
+    assert!(query("$.store[0]").is_empty());
+}
+
+#[test]
+fn wildcards() {
+    assert_eq!(
+        query("$.store.book[*].title"),
+        vec!["\"Sayings\"", "\"Sword\"", "\"Moby Dick\"", "\"The Lord\""]
+    );
+    assert_eq!(query("$.list.*").len(), 6);
+    assert_eq!(query_sorted("$.store.bicycle.*"), vec!["\"red\"", "19.95"]);
+    assert!(query("$.list[0].*").is_empty());
+}
+
+#[test]
+fn slices() {
+    let slice = |s: &str| query(&format!("$.list[{}]", s)).join(",");
+
+    assert_eq!(slice("1:3"), "11,12");
+    assert_eq!(slice(":2"), "10,11");
+    assert_eq!(slice("4:"), "14,15");
+    assert_eq!(slice(":"), "10,11,12,13,14,15");
+    assert_eq!(slice("::2"), "10,12,14");
+    assert_eq!(slice("1::2"), "11,13,15");
+
+    // negative bounds count from the end
+    assert_eq!(slice("-2:"), "14,15");
+    assert_eq!(slice(":-4"), "10,11");
+    assert_eq!(slice("-3:-1"), "13,14");
+
+    // a negative step walks backwards, from the end when the start is left out
+    assert_eq!(slice("::-1"), "15,14,13,12,11,10");
+    assert_eq!(slice("4:1:-1"), "14,13,12");
+    assert_eq!(slice("::-2"), "15,13,11");
+    assert_eq!(slice("-1:-4:-2"), "15,13");
+
+    // bounds past either end are clamped, and a step of zero selects nothing
+    assert_eq!(slice("-100:100"), "10,11,12,13,14,15");
+    assert_eq!(slice("3:1"), "");
+    assert_eq!(slice("::0"), "");
+}
+
+#[test]
+fn descendants() {
+    assert_eq!(
+        query_sorted("$..price"),
+        vec!["12.99", "19.95", "22.99", "8.95", "8.99"]
+    );
+    assert_eq!(query("$..book[2].title"), vec!["\"Moby Dick\""]);
+    assert_eq!(query("$..book[-1:].title"), vec!["\"The Lord\""]);
+    assert_eq!(query_sorted("$..isbn"), vec!["\"0-395\"", "\"0-553\""]);
+    // everything below the root: the store, its list of 4 books with 14 members between
+    // them, the bicycle and its 2 members, 3 more members of the root, and the list of 6
+    assert_eq!(query("$..*").len(), 1 + 1 + 4 + 14 + 1 + 2 + 3 + 1 + 6);
+    assert!(query("$..missing").is_empty());
+}
+
+#[test]
+fn filters() {
+    let titles = |path: &str| {
+        let mut found = query(&format!("$.store.book[{}].title", path));
+        found.sort();
+        found
+    };
+
+    assert_eq!(titles("?(@.isbn)"), vec!["\"Moby Dick\"", "\"The Lord\""]);
+    assert_eq!(
+        titles("?(@.price < 10)"),
+        vec!["\"Moby Dick\"", "\"Sayings\""]
+    );
+    assert_eq!(titles("?(@.price <= 8.95)"), vec!["\"Sayings\""]);
+    assert_eq!(titles("?(@.price > 20)"), vec!["\"The Lord\""]);
+    assert_eq!(titles("?(@.price >= 22.99)"), vec!["\"The Lord\""]);
+    assert_eq!(
+        titles("?(@.category == \"reference\")"),
+        vec!["\"Sayings\""]
+    );
+    assert_eq!(
+        titles("?(@.category != \"reference\")"),
+        vec!["\"Moby Dick\"", "\"Sword\"", "\"The Lord\""]
+    );
+    // strings are ordered, but a string never compares with a number
+    assert_eq!(titles("?(@.title < \"N\")"), vec!["\"Moby Dick\""]);
+    assert!(titles("?(@.title < 10)").is_empty());
+
+    // filters look at members of objects too, and can follow a path into the item
+    let doc = parse_json(r#"{"a": {"x": {"y": 1}}, "b": {"x": {"y": 2}}, "c": 3}"#).unwrap();
+    let found = doc.query("$[?(@.x.y == 2)]").unwrap();
+    assert_eq!(
+        found.into_iter().map(compact).collect::<Vec<_>>(),
+        vec![r#"{"x":{"y":2}}"#]
+    );
+
+    assert_eq!(
+        query_sorted("$..[?(@.price > 19)].price"),
+        vec!["19.95", "22.99"]
+    );
+}
+
+#[test]
+fn malformed_paths() {
+    for (path, column) in [
+        ("", 1),
+        ("store", 1),
+        ("$.", 2),
+        ("$[1", 2),
+        ("$['a]", 2),
+        ("$.a[1:2:3:4]", 4),
+        ("$[?(@.a ~ 1)]", 2),
+        ("$.a b", 4),
+    ] {
+        let e = path.parse::<JsonPath>().unwrap_err();
+        assert_eq!(e.column(), column, "{}", path);
+    }
+}
@@ -0,0 +1,127 @@
+use json_parser::{parse_json, ErrorKind, JsonValue, WriteOptions};
+
+use std::collections::HashMap;
+
+/// A xorshift generator, which is plenty for making up test values
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn number(&mut self) -> f64 {
+        match self.below(4) {
+            0 => self.below(1000) as f64 - 500.0,
+            1 => (self.below(2_000_000) as f64 - 1_000_000.0) / 1000.0,
+            // any finite `f64` at all, from subnormals to the largest there is
+            _ => loop {
+                let n = f64::from_bits(self.next());
+                if n.is_finite() {
+                    break n;
+                }
+            },
+        }
+    }
+
+    fn char(&mut self) -> char {
+        const SPECIAL: &[char] = &['"', '\\', '/', '\n', '\r', '\t', '\u{8}', '\u{c}', '\0'];
+
+        match self.below(6) {
+            0 => SPECIAL[self.below(SPECIAL.len() as u64) as usize],
+            1 => std::char::from_u32(self.below(0x20) as u32).unwrap(),
+            2 => loop {
+                if let Some(c) = std::char::from_u32(self.below(0x11_0000) as u32) {
+                    break c;
+                }
+            },
+            _ => (b' ' + self.below(95) as u8) as char,
+        }
+    }
+
+    fn string(&mut self) -> String {
+        (0..self.below(8)).map(|_| self.char()).collect()
+    }
+
+    fn value(&mut self, depth: u32) -> JsonValue {
+        let kinds = if depth == 0 { 4 } else { 6 };
+
+        match self.below(kinds) {
+            0 => JsonValue::Null,
+            1 => JsonValue::Bool(self.below(2) == 0),
+            2 => JsonValue::Number(self.number()),
+            3 => JsonValue::String(self.string()),
+            4 => JsonValue::List((0..self.below(5)).map(|_| self.value(depth - 1)).collect()),
+            _ => JsonValue::Object(
+                (0..self.below(5))
+                    .map(|_| (self.string(), self.value(depth - 1)))
+                    .collect::<HashMap<_, _>>(),
+            ),
+        }
+    }
+}
+
+#[test]
+fn parse_reads_back_what_is_written() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let all_options = [
+        WriteOptions::compact(),
+        WriteOptions::pretty(),
+        WriteOptions {
+            indent: Some(0),
+            sort_keys: true,
+            ascii_only: true,
+        },
+    ];
+
+    for _ in 0..2000 {
+        let value = rng.value(4);
+
+        for &options in &all_options {
+            let text = value.to_string_with(options);
+            assert_eq!(parse_json(&text).as_ref(), Ok(&value), "{}", text);
+
+            if options.ascii_only {
+                assert!(text.is_ascii(), "{}", text);
+            }
+        }
+
+        let mut bytes = Vec::new();
+        value.write_to(&mut bytes, WriteOptions::pretty()).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), format!("{:#}", value));
+    }
+}
+
+#[test]
+fn non_finite_numbers_are_written_as_null() {
+    let list = JsonValue::List(vec![
+        JsonValue::Number(f64::NAN),
+        JsonValue::Number(f64::INFINITY),
+        JsonValue::Number(f64::NEG_INFINITY),
+    ]);
+
+    assert_eq!(list.to_string(), "[null,null,null]");
+}
+
+#[test]
+fn numbers_too_large_for_f64_are_rejected() {
+    for doc in &["1e400", "-1e400", "[123123e100000]", "1.5e+9999"] {
+        let e = parse_json(doc).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::NumberOutOfRange, "{}", doc);
+        assert_eq!(e.offset(), doc.find(|c| c != '[').unwrap(), "{}", doc);
+    }
+
+    // the largest `f64` is fine, and so are numbers too small for one, which round to zero
+    assert_eq!(
+        parse_json("1.7976931348623157e308"),
+        Ok(JsonValue::Number(f64::MAX))
+    );
+    assert_eq!(parse_json("1e-400"), Ok(JsonValue::Number(0.0)));
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "serde")]
+
+use json_parser::de::{from_str, from_str_limited, from_value, from_value_limited, Error};
+use json_parser::{parse_json, JsonValue, Limits};
+
+use serde::Deserialize;
+
+#[derive(Debug, PartialEq, Deserialize)]
+enum Shape {
+    Point,
+    Circle { radius: f64 },
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Drawing<'a> {
+    name: &'a str,
+    shapes: Vec<Shape>,
+}
+
+fn lists(depth: usize) -> String {
+    format!("{}{}", "[".repeat(depth), "]".repeat(depth))
+}
+
+fn limits(max_depth: usize, max_items: usize) -> Limits {
+    Limits {
+        max_depth,
+        max_items,
+        ..Limits::default()
+    }
+}
+
+#[test]
+fn from_str_and_from_value_agree() {
+    let doc = r#"{"name": "dots", "shapes": ["Point", {"Circle": {"radius": 2.5}}]}"#;
+    let expected = Drawing {
+        name: "dots",
+        shapes: vec![Shape::Point, Shape::Circle { radius: 2.5 }],
+    };
+
+    assert_eq!(from_str::<Drawing>(doc).unwrap(), expected);
+    assert_eq!(
+        from_value::<JsonValue>(parse_json(doc).unwrap()).unwrap(),
+        parse_json(doc).unwrap()
+    );
+}
+
+#[test]
+fn from_str_depth_limit() {
+    let max = Limits::default().max_depth;
+
+    assert!(from_str::<JsonValue>(&lists(max)).is_ok());
+
+    let e = from_str::<JsonValue>(&lists(max + 1)).unwrap_err();
+    assert!(e.to_string().contains("nested too deeply"), "{}", e);
+    assert_eq!(e.column(), Some(max + 1));
+
+    assert!(from_str_limited::<JsonValue>(&lists(2), limits(2, usize::MAX)).is_ok());
+    assert!(from_str_limited::<JsonValue>(&lists(3), limits(2, usize::MAX)).is_err());
+    // the object of an enum variant is a level too
+    assert!(
+        from_str_limited::<Vec<Shape>>(r#"[{"Circle": {"radius": 1}}]"#, limits(2, 8)).is_err()
+    );
+    assert!(from_str_limited::<Vec<Shape>>(r#"[{"Circle": {"radius": 1}}]"#, limits(3, 8)).is_ok());
+}
+
+#[test]
+fn from_str_hostile_depth() {
+    let e: Error = from_str::<JsonValue>(&lists(1_000_000)).unwrap_err();
+
+    assert!(e.to_string().contains("nested too deeply"), "{}", e);
+}
+
+#[test]
+fn from_str_item_and_length_limits() {
+    assert!(from_str_limited::<Vec<u8>>("[1, 2]", limits(8, 2)).is_ok());
+    assert!(from_str_limited::<Vec<u8>>("[1, 2, 3]", limits(8, 2)).is_err());
+    assert!(from_str_limited::<Vec<u8>>("[]", limits(8, 0)).is_ok());
+    assert!(from_str_limited::<Vec<u8>>("[1]", limits(8, 0)).is_err());
+    assert!(from_str_limited::<JsonValue>(r#"{"a": 1, "b": 2}"#, limits(8, 1)).is_err());
+
+    let short = Limits {
+        max_len: 5,
+        ..Limits::default()
+    };
+
+    assert!(from_str_limited::<Vec<u8>>("[1,2]", short).is_ok());
+    let e = from_str_limited::<Vec<u8>>("[1, 2]", short).unwrap_err();
+    assert!(e.to_string().contains("too long"), "{}", e);
+}
+
+#[test]
+fn from_value_limits() {
+    let deep = (0..200).fold(JsonValue::Null, |value, _| JsonValue::List(vec![value]));
+
+    let e = from_value::<JsonValue>(deep.clone()).unwrap_err();
+    assert!(e.to_string().contains("nested too deeply"), "{}", e);
+    assert_eq!(
+        from_value_limited::<JsonValue>(deep.clone(), limits(200, 1)),
+        Ok(deep)
+    );
+
+    let list = parse_json("[1, 2, 3]").unwrap();
+    assert!(from_value_limited::<Vec<u8>>(list.clone(), limits(1, 3)).is_ok());
+    assert!(from_value_limited::<Vec<u8>>(list.clone(), limits(1, 2)).is_err());
+    assert!(from_value_limited::<Vec<u8>>(list, limits(0, 3)).is_err());
+}
+
+#[test]
+fn from_str_rejects_numbers_too_large_for_f64() {
+    let e = from_str::<Vec<f64>>("[1, 1e400]").unwrap_err();
+
+    assert!(e.to_string().contains("too large"), "{}", e);
+    assert_eq!(e.column(), Some(5));
+}
+
+#[test]
+fn serialize_round_trip() {
+    let doc = r#"{
+        "name": "a \"quoted\" \\ line\n",
+        "numbers": [0, -0.0, 3, -7, 0.5, 1e20, -1.5e-300, 9007199254740993],
+        "nested": {"empty": {}, "list": [[], [null, true, false]]}
+    }"#;
+    let value = parse_json(doc).unwrap();
+
+    let text = serde_json::to_string(&value).unwrap();
+    assert_eq!(parse_json(&text).unwrap(), value);
+    assert_eq!(from_str::<JsonValue>(&text).unwrap(), value);
+}
+
+#[test]
+fn serialize_numbers() {
+    let number = |n: f64| serde_json::to_value(JsonValue::Number(n)).unwrap();
+
+    // whole numbers come out as integers, as long as an `f64` holds them exactly
+    assert!(number(3.0).is_i64());
+    assert!(number(-7.0).is_i64());
+    assert!(number(0.5).is_f64());
+    assert!(number(1e20).is_f64());
+
+    // and `-0.0` keeps its sign, which an integer can't
+    assert!(number(0.0).is_i64());
+    let zero = number(-0.0).as_f64().unwrap();
+    assert!(number(-0.0).is_f64());
+    assert!(zero == 0.0 && zero.is_sign_negative());
+    assert_eq!(serde_json::to_string(&JsonValue::Number(-0.0)).unwrap(), "-0.0");
+}
@@ -0,0 +1,361 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Field, Fields, Ident, LitStr, Path, Type};
+
+/// What the `#[parse(...)]` attributes on a type, variant or field ask for
+#[derive(Default)]
+struct Options {
+    /// Literals that have to come first, in order
+    tags: Vec<LitStr>,
+    /// A literal that has to come last
+    end: Option<LitStr>,
+    sep_by: Option<LitStr>,
+    with: Option<Path>,
+}
+
+impl Options {
+    fn new(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("parse")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    options.tags.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("end") {
+                    options.end = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("sep_by") {
+                    options.sep_by = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("with") {
+                    options.with = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `tag`, `end`, `sep_by` or `with`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+
+    /// Types and variants can only have literals around them
+    fn only_literals(self, span: &impl Spanned) -> syn::Result<Self> {
+        if self.sep_by.is_some() || self.with.is_some() {
+            let message = "`sep_by` and `with` only go on fields";
+            return Err(syn::Error::new(span.span(), message));
+        }
+
+        Ok(self)
+    }
+}
+
+/// `snake_case` to `CamelCase`, for the names of error variants
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// The `T` of a `Vec<T>`
+fn vec_item(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let last = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+        return None;
+    };
+
+    match args.args.first()? {
+        syn::GenericArgument::Type(item) if last.ident == "Vec" => Some(item),
+        _ => None,
+    }
+}
+
+/// Everything needed to write the parser of one struct or variant
+struct Context<'a> {
+    error: &'a Ident,
+    /// The variants of the error type, which each field adds to
+    variants: &'a mut Vec<TokenStream>,
+    /// Whether anything can fail with `Expected`
+    expected: &'a mut bool,
+}
+
+impl Context<'_> {
+    fn literal(&mut self, literal: &LitStr) -> TokenStream {
+        let error = self.error;
+        *self.expected = true;
+
+        quote!(
+            match input.trim_start().strip_prefix(#literal) {
+                ::std::option::Option::Some(rest) => input = rest,
+                ::std::option::Option::None => {
+                    return ::std::result::Result::Err((input, #error::Expected(#literal)));
+                }
+            }
+        )
+    }
+
+    /// The statements that parse `field` into `binding`, where `variant` names its error
+    fn field(
+        &mut self,
+        field: &Field,
+        binding: &Ident,
+        variant: &Ident,
+    ) -> syn::Result<TokenStream> {
+        let options = Options::new(&field.attrs)?;
+        let error = self.error;
+        let ty = &field.ty;
+
+        let tags: Vec<_> = options.tags.iter().map(|tag| self.literal(tag)).collect();
+        let end = options.end.as_ref().map(|end| self.literal(end));
+
+        let parser = match (&options.with, &options.sep_by) {
+            (Some(with), _) => quote!(#with()),
+            (None, Some(_)) => match vec_item(ty) {
+                Some(item) => quote!(::parser_combinators::parse::parsed::<#item>()),
+                None => return Err(syn::Error::new(ty.span(), "`sep_by` needs a `Vec` field")),
+            },
+            (None, None) => quote!(::parser_combinators::parse::parsed::<#ty>()),
+        };
+
+        let value = match &options.sep_by {
+            // separated lists can't fail, they just end before the first item that isn't there
+            Some(sep_by) => quote!({
+                let mut items = ::std::vec::Vec::new();
+
+                if let (rest, ::std::result::Result::Ok(item)) = ::parser_combinators::Parser::parse(&parser, input.trim_start()) {
+                    input = rest;
+                    items.push(item);
+
+                    while let ::std::option::Option::Some(next) = input.trim_start().strip_prefix(#sep_by) {
+                        match ::parser_combinators::Parser::parse(&parser, next.trim_start()) {
+                            (rest, ::std::result::Result::Ok(item)) => {
+                                input = rest;
+                                items.push(item);
+                            }
+                            (_, ::std::result::Result::Err(_)) => break,
+                        }
+                    }
+                }
+
+                items
+            }),
+            None => {
+                // the errors of `with` parsers can't be named, so they are dropped
+                let (variant_def, pattern, err) = if options.with.is_some() {
+                    (quote!(#variant), quote!(_), quote!(#error::#variant))
+                } else {
+                    let error_ty = quote!(<#ty as ::parser_combinators::parse::Parse>::Error);
+                    (
+                        quote!(#variant(#error_ty)),
+                        quote!(e),
+                        quote!(#error::#variant(e)),
+                    )
+                };
+                self.variants.push(variant_def);
+
+                quote!(
+                    match ::parser_combinators::Parser::parse(&parser, input.trim_start()) {
+                        (rest, ::std::result::Result::Ok(value)) => {
+                            input = rest;
+                            value
+                        }
+                        (_, ::std::result::Result::Err(#pattern)) => {
+                            return ::std::result::Result::Err((input, #err));
+                        }
+                    }
+                )
+            }
+        };
+
+        Ok(quote!(
+            #(#tags)*
+            let #binding = {
+                let parser = #parser;
+                #value
+            };
+            #end
+        ))
+    }
+
+    /// A function named `name` that parses `fields` into `constructor`, with the literals of
+    /// `options` around them, where `prefix` goes in front of the names of error variants
+    fn fields(
+        &mut self,
+        name: &Ident,
+        ty: &Ident,
+        constructor: TokenStream,
+        fields: &Fields,
+        options: &Options,
+        prefix: &str,
+    ) -> syn::Result<TokenStream> {
+        let error = self.error;
+
+        let mut body: Vec<_> = options.tags.iter().map(|tag| self.literal(tag)).collect();
+        let mut bindings = Vec::new();
+
+        for (i, field) in fields.iter().enumerate() {
+            let binding = format_ident!("__{}", i);
+            let variant = match &field.ident {
+                Some(ident) => format_ident!("{}{}", prefix, camel_case(&ident.to_string())),
+                None if prefix.is_empty() => format_ident!("Field{}", i),
+                None if fields.len() == 1 => format_ident!("{}", prefix),
+                None => format_ident!("{}{}", prefix, i),
+            };
+
+            body.push(self.field(field, &binding, &variant)?);
+            bindings.push(binding);
+        }
+
+        body.extend(options.end.iter().map(|end| self.literal(end)));
+
+        let value = match fields {
+            Fields::Named(named) => {
+                let names = named.named.iter().map(|field| &field.ident);
+                quote!(#constructor { #(#names: #bindings),* })
+            }
+            Fields::Unnamed(_) => quote!(#constructor(#(#bindings),*)),
+            Fields::Unit => constructor,
+        };
+
+        Ok(quote!(
+            #[allow(unused_mut)]
+            fn #name(mut input: &str) -> ::std::result::Result<(&str, #ty), (&str, #error)> {
+                #(#body)*
+                ::std::result::Result::Ok((input, #value))
+            }
+        ))
+    }
+}
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        let message = "`Parse` can't be derived for types with generics";
+        return Err(syn::Error::new(input.generics.span(), message));
+    }
+
+    let ty = &input.ident;
+    let vis = &input.vis;
+    let error = format_ident!("Parse{}Error", ty);
+    let options = Options::new(&input.attrs)?.only_literals(ty)?;
+
+    let mut variants = Vec::new();
+    let mut expected = false;
+    let mut context = Context {
+        error: &error,
+        variants: &mut variants,
+        expected: &mut expected,
+    };
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let parse = format_ident!("parse");
+            let function = context.fields(&parse, ty, quote!(#ty), &data.fields, &options, "")?;
+
+            quote!(
+                #function
+
+                match parse(input) {
+                    ::std::result::Result::Ok((rest, value)) => (rest, ::std::result::Result::Ok(value)),
+                    ::std::result::Result::Err((_, e)) => (input, ::std::result::Result::Err(e)),
+                }
+            )
+        }
+        Data::Enum(data) if data.variants.is_empty() => {
+            return Err(syn::Error::new(
+                ty.span(),
+                "`Parse` can't be derived for empty enums",
+            ));
+        }
+        Data::Enum(data) => {
+            let mut functions = Vec::new();
+            let mut names = Vec::new();
+
+            for (i, variant) in data.variants.iter().enumerate() {
+                let name = format_ident!("parse_{}", i);
+
+                // the literals of the enum go around those of each variant
+                let own = Options::new(&variant.attrs)?.only_literals(&variant.ident)?;
+                let variant_options = Options {
+                    tags: options.tags.iter().cloned().chain(own.tags).collect(),
+                    end: own.end.or_else(|| options.end.clone()),
+                    ..Options::default()
+                };
+
+                let ident = &variant.ident;
+                functions.push(context.fields(
+                    &name,
+                    ty,
+                    quote!(#ty::#ident),
+                    &variant.fields,
+                    &variant_options,
+                    &ident.to_string(),
+                )?);
+                names.push(name);
+            }
+
+            // the error is the one of the variant that got the furthest
+            quote!(
+                #(#functions)*
+
+                let mut error: ::std::option::Option<(&str, #error)> = ::std::option::Option::None;
+
+                #(
+                    match #names(input) {
+                        ::std::result::Result::Ok((rest, value)) => {
+                            return (rest, ::std::result::Result::Ok(value));
+                        }
+                        ::std::result::Result::Err((at, e)) => {
+                            if error.as_ref().map_or(true, |(best, _)| at.len() < best.len()) {
+                                error = ::std::option::Option::Some((at, e));
+                            }
+                        }
+                    }
+                )*
+
+                match error {
+                    ::std::option::Option::Some((_, e)) => (input, ::std::result::Result::Err(e)),
+                    ::std::option::Option::None => ::std::unreachable!(),
+                }
+            )
+        }
+        Data::Union(data) => {
+            let message = "`Parse` can't be derived for unions";
+            return Err(syn::Error::new(data.union_token.span(), message));
+        }
+    };
+
+    let expected = if expected {
+        quote!(
+            /// A literal that wasn't found
+            Expected(&'static str),
+        )
+    } else {
+        TokenStream::new()
+    };
+
+    let doc = format!("Why a [`{ty}`] couldn't be parsed");
+
+    Ok(quote!(
+        #[doc = #doc]
+        #[derive(Debug)]
+        #vis enum #error {
+            #expected
+            #(#variants,)*
+        }
+
+        impl ::parser_combinators::parse::Parse for #ty {
+            type Error = #error;
+
+            fn parse_str(input: &str) -> (&str, ::std::result::Result<Self, Self::Error>) {
+                #body
+            }
+        }
+    ))
+}
@@ -0,0 +1,535 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, token, Token};
+use syn::{Attribute, Block, Ident, LitChar, LitStr, Pat, Path, Type, Visibility};
+
+use std::collections::{HashMap, HashSet};
+
+pub struct Grammar {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    name: Ident,
+    output: Option<Type>,
+    expr: Expr,
+}
+
+enum Expr {
+    Literal(LitStr),
+    Char(LitChar),
+    /// `[pattern]`, a char that matches the pattern
+    Class(Pat),
+    /// `.`, any char
+    Any,
+    Rule(Ident),
+    /// A Rust function call that gives back a parser
+    Call(Path, Punctuated<syn::Expr, Token![,]>),
+    ZeroOrMore(Box<Expr>),
+    OneOrMore(Box<Expr>),
+    Optional(Box<Expr>),
+    /// `$e`, the text that `e` matched
+    Capture(Box<Expr>),
+    /// `&e` when true and `!e` when false, which check for `e` without consuming anything
+    Lookahead(Box<Expr>, bool),
+    Sequence(Vec<(Option<Ident>, Expr)>, Option<Block>),
+    Choice(Vec<Expr>),
+}
+
+/// The names of all of the rules, which are needed up front to tell a rule followed by
+/// a group, as in `rule (a b)*`, from a call to a Rust function
+fn rule_names(input: ParseStream) -> syn::Result<HashSet<String>> {
+    let mut names = HashSet::new();
+
+    while !input.is_empty() {
+        input.call(Attribute::parse_outer)?;
+        input.parse::<Visibility>()?;
+        let name = input.parse::<Ident>()?;
+
+        if !names.insert(name.to_string()) {
+            return Err(syn::Error::new(
+                name.span(),
+                "rule is defined more than once",
+            ));
+        }
+
+        // actions are single token trees, so the first `;` outside of them ends the rule
+        while !input.peek(Token![;]) {
+            input.parse::<proc_macro2::TokenTree>()?;
+        }
+        input.parse::<Token![;]>()?;
+    }
+
+    Ok(names)
+}
+
+impl Parse for Grammar {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let names = rule_names(&input.fork())?;
+        let mut rules = Vec::new();
+
+        while !input.is_empty() {
+            rules.push(Rule::parse(input, &names)?);
+        }
+
+        Ok(Self { rules })
+    }
+}
+
+impl Rule {
+    fn parse(input: ParseStream, names: &HashSet<String>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let name = input.parse()?;
+
+        let output = if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        input.parse::<Token![=]>()?;
+        let expr = choice(input, names)?;
+        input.parse::<Token![;]>()?;
+
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            output,
+            expr,
+        })
+    }
+}
+
+fn choice(input: ParseStream, names: &HashSet<String>) -> syn::Result<Expr> {
+    let mut alternatives = vec![sequence(input, names)?];
+
+    while input.peek(Token![/]) {
+        input.parse::<Token![/]>()?;
+        alternatives.push(sequence(input, names)?);
+    }
+
+    Ok(if alternatives.len() == 1 {
+        alternatives.remove(0)
+    } else {
+        Expr::Choice(alternatives)
+    })
+}
+
+fn starts_item(input: ParseStream) -> bool {
+    input.peek(LitStr)
+        || input.peek(LitChar)
+        || input.peek(token::Bracket)
+        || input.peek(token::Paren)
+        || input.peek(Ident)
+        || input.peek(Token![::])
+        || input.peek(Token![.])
+        || input.peek(Token![$])
+        || input.peek(Token![&])
+        || input.peek(Token![!])
+}
+
+fn sequence(input: ParseStream, names: &HashSet<String>) -> syn::Result<Expr> {
+    let mut items = Vec::new();
+
+    while starts_item(input) {
+        let label = if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+            let label = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Some(label)
+        } else {
+            None
+        };
+
+        items.push((label, prefixed(input, names)?));
+    }
+
+    if items.is_empty() {
+        return Err(input.error("expected an expression"));
+    }
+
+    let action = if input.peek(token::Brace) {
+        Some(input.parse()?)
+    } else {
+        None
+    };
+
+    Ok(match (items.len(), &items[0].0, action) {
+        (1, None, None) => items.remove(0).1,
+        (_, _, action) => Expr::Sequence(items, action),
+    })
+}
+
+fn prefixed(input: ParseStream, names: &HashSet<String>) -> syn::Result<Expr> {
+    if input.peek(Token![$]) {
+        input.parse::<Token![$]>()?;
+        Ok(Expr::Capture(Box::new(suffixed(input, names)?)))
+    } else if input.peek(Token![&]) {
+        input.parse::<Token![&]>()?;
+        Ok(Expr::Lookahead(Box::new(suffixed(input, names)?), true))
+    } else if input.peek(Token![!]) {
+        input.parse::<Token![!]>()?;
+        Ok(Expr::Lookahead(Box::new(suffixed(input, names)?), false))
+    } else {
+        suffixed(input, names)
+    }
+}
+
+fn suffixed(input: ParseStream, names: &HashSet<String>) -> syn::Result<Expr> {
+    let mut expr = primary(input, names)?;
+
+    loop {
+        expr = if input.peek(Token![*]) {
+            input.parse::<Token![*]>()?;
+            Expr::ZeroOrMore(Box::new(expr))
+        } else if input.peek(Token![+]) {
+            input.parse::<Token![+]>()?;
+            Expr::OneOrMore(Box::new(expr))
+        } else if input.peek(Token![?]) {
+            input.parse::<Token![?]>()?;
+            Expr::Optional(Box::new(expr))
+        } else {
+            return Ok(expr);
+        };
+    }
+}
+
+fn primary(input: ParseStream, names: &HashSet<String>) -> syn::Result<Expr> {
+    if input.peek(LitStr) {
+        Ok(Expr::Literal(input.parse()?))
+    } else if input.peek(LitChar) {
+        Ok(Expr::Char(input.parse()?))
+    } else if input.peek(Token![.]) {
+        input.parse::<Token![.]>()?;
+        Ok(Expr::Any)
+    } else if input.peek(token::Bracket) {
+        let content;
+        bracketed!(content in input);
+        Ok(Expr::Class(Pat::parse_multi_with_leading_vert(&content)?))
+    } else if input.peek(token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        let expr = choice(&content, names)?;
+
+        if !content.is_empty() {
+            return Err(content.error("expected `/`, `)` or another expression"));
+        }
+
+        Ok(expr)
+    } else if input.peek(Ident) && !input.peek2(Token![::]) {
+        let name = input.parse::<Ident>()?;
+
+        if names.contains(&name.to_string()) {
+            Ok(Expr::Rule(name))
+        } else if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Ok(Expr::Call(
+                name.into(),
+                Punctuated::parse_terminated(&content)?,
+            ))
+        } else {
+            let message = format!("no rule named `{name}`, write `{name}()` to call a function");
+            Err(syn::Error::new(name.span(), message))
+        }
+    } else {
+        let path = input.call(Path::parse_mod_style)?;
+        let content;
+        parenthesized!(content in input);
+        Ok(Expr::Call(path, Punctuated::parse_terminated(&content)?))
+    }
+}
+
+impl Expr {
+    fn rules(&self, found: &mut Vec<Ident>) {
+        match self {
+            Expr::Rule(name) => found.push(name.clone()),
+            Expr::ZeroOrMore(expr)
+            | Expr::OneOrMore(expr)
+            | Expr::Optional(expr)
+            | Expr::Capture(expr)
+            | Expr::Lookahead(expr, _) => expr.rules(found),
+            Expr::Sequence(items, _) => items.iter().for_each(|(_, expr)| expr.rules(found)),
+            Expr::Choice(alternatives) => alternatives.iter().for_each(|expr| expr.rules(found)),
+            Expr::Literal(_) | Expr::Char(_) | Expr::Class(_) | Expr::Any | Expr::Call(..) => (),
+        }
+    }
+
+    /// A parser of `&'input str` whose error is `()`, where `output` is the type that
+    /// the actions of this expression have to give back, if it is known
+    fn expand(&self, output: Option<&Type>, actions: &mut bool) -> TokenStream {
+        let in_place = quote!(::parser_combinators::func::AsParser::as_parser_in_place);
+        let parser_once = quote!(::parser_combinators::ParserOnce::<&'input str>);
+        let drop = quote!(::std::mem::drop);
+
+        match self {
+            Expr::Literal(literal) => quote!(#parser_once::map_err(
+                ::parser_combinators::prelude::util::literal(#literal),
+                #drop,
+            )),
+            Expr::Char(c) => {
+                Expr::Literal(LitStr::new(&c.value().to_string(), c.span())).expand(None, actions)
+            }
+            Expr::Class(pattern) => quote!(#in_place(|input: &mut &'input str| {
+                let mut chars = input.chars();
+                match chars.next() {
+                    ::std::option::Option::Some(c) if ::std::matches!(c, #pattern) => {
+                        *input = chars.as_str();
+                        ::std::result::Result::Ok(c)
+                    }
+                    _ => ::std::result::Result::Err(()),
+                }
+            })),
+            Expr::Any => quote!(#in_place(|input: &mut &'input str| {
+                let mut chars = input.chars();
+                match chars.next() {
+                    ::std::option::Option::Some(c) => {
+                        *input = chars.as_str();
+                        ::std::result::Result::Ok(c)
+                    }
+                    ::std::option::Option::None => ::std::result::Result::Err(()),
+                }
+            })),
+            Expr::Rule(name) => quote!(::parser_combinators::prelude::defer(#name)),
+            // the parser is run from a closure, so that it doesn't have to describe itself,
+            // and the grammar shows it as a rule named after the function
+            Expr::Call(path, args) => {
+                let name = path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+
+                quote!({
+                    let parser = #path(#args);
+                    #parser_once::trace(
+                        #in_place(move |input: &mut &'input str| {
+                            let (rest, out) = ::parser_combinators::Parser::parse(&parser, *input);
+                            *input = rest;
+                            out.map_err(#drop)
+                        }),
+                        #name,
+                    )
+                })
+            }
+            Expr::ZeroOrMore(expr) => {
+                let expr = expr.expand(None, actions);
+                quote!(#parser_once::map_err(
+                    #parser_once::zero_or_more(#expr, ::std::vec::Vec::new),
+                    #drop,
+                ))
+            }
+            Expr::OneOrMore(expr) => {
+                let expr = expr.expand(None, actions);
+                quote!(#parser_once::map_err(
+                    #parser_once::one_or_more(#expr, ::std::vec::Vec::new),
+                    #drop,
+                ))
+            }
+            Expr::Optional(expr) => {
+                let expr = expr.expand(None, actions);
+                quote!(#parser_once::map_both(
+                    #parser_once::optional(#expr),
+                    ::std::result::Result::ok,
+                    #drop,
+                ))
+            }
+            Expr::Capture(expr) => {
+                let expr = expr.expand(None, actions);
+                quote!({
+                    let parser = #expr;
+                    #in_place(move |input: &mut &'input str| {
+                        let start = *input;
+                        match ::parser_combinators::Parser::parse(&parser, start) {
+                            (rest, ::std::result::Result::Ok(_)) => {
+                                *input = rest;
+                                ::std::result::Result::Ok(&start[..start.len() - rest.len()])
+                            }
+                            (_, ::std::result::Result::Err(_)) => ::std::result::Result::Err(()),
+                        }
+                    })
+                })
+            }
+            Expr::Lookahead(expr, positive) => {
+                let expr = expr.expand(None, actions);
+                quote!({
+                    let parser = #expr;
+                    #in_place(move |input: &mut &'input str| {
+                        let (_, out) = ::parser_combinators::Parser::parse(&parser, *input);
+                        if out.is_ok() == #positive {
+                            ::std::result::Result::Ok(())
+                        } else {
+                            ::std::result::Result::Err(())
+                        }
+                    })
+                })
+            }
+            Expr::Sequence(items, action) => {
+                // items without a label are bound to hidden names, so that a sequence
+                // without any labels can give back all of them
+                let names: Vec<_> = (0..items.len())
+                    .map(|i| Ident::new(&format!("__{i}"), proc_macro2::Span::call_site()))
+                    .collect();
+                let labels: Vec<_> = items
+                    .iter()
+                    .filter_map(|(label, _)| label.as_ref())
+                    .collect();
+
+                let mut parsers = items.iter().map(|(_, expr)| expr.expand(None, actions));
+                let mut patterns = items
+                    .iter()
+                    .zip(&names)
+                    .map(|((label, _), name)| match label {
+                        Some(label) => label.to_token_stream(),
+                        None if labels.is_empty() && action.is_none() => name.to_token_stream(),
+                        None => quote!(_),
+                    });
+
+                let mut parser = parsers.next().unwrap_or_default();
+                let mut pattern = patterns.next().unwrap_or_default();
+                for (next, next_pattern) in parsers.zip(patterns) {
+                    parser = quote!(#parser_once::then(#parser, #next));
+                    pattern = quote!((#pattern, #next_pattern));
+                }
+
+                let output = match (action, labels.as_slice()) {
+                    (Some(block), _) => match output {
+                        Some(output) => quote!(-> #output #block),
+                        None => block.to_token_stream(),
+                    },
+                    (None, [label]) => label.to_token_stream(),
+                    (None, []) => quote!((#(#names),*)),
+                    (None, labels) => quote!((#(#labels),*)),
+                };
+
+                *actions = true;
+                quote!(#parser_once::map_err(__action(#parser, |#pattern| #output), #drop))
+            }
+            Expr::Choice(alternatives) => {
+                let mut alternatives = alternatives.iter().map(|expr| expr.expand(output, actions));
+                let first = alternatives.next().unwrap_or_default();
+
+                alternatives.fold(first, |parser, next| {
+                    quote!(#parser_once::map_both(
+                        #parser_once::or(#parser, #next),
+                        ::parser_combinators::prelude::util::Either::into_inner,
+                        #drop,
+                    ))
+                })
+            }
+        }
+    }
+}
+
+impl Grammar {
+    /// The rules that can reach themselves, which need to be boxed since an `impl Trait`
+    /// can't contain itself
+    fn recursive(&self) -> HashSet<String> {
+        let calls: HashMap<_, _> = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let mut found = Vec::new();
+                rule.expr.rules(&mut found);
+                (rule.name.to_string(), found)
+            })
+            .collect();
+
+        let reaches_itself = |start: &String| {
+            let mut seen = HashSet::new();
+            let mut stack: Vec<_> = calls[start].iter().map(Ident::to_string).collect();
+
+            while let Some(name) = stack.pop() {
+                if name == *start {
+                    return true;
+                }
+
+                if seen.insert(name.clone()) {
+                    stack.extend(calls[&name].iter().map(Ident::to_string));
+                }
+            }
+
+            false
+        };
+
+        calls
+            .keys()
+            .filter(|name| reaches_itself(name))
+            .cloned()
+            .collect()
+    }
+
+    pub fn expand(&self) -> syn::Result<TokenStream> {
+        let recursive = self.recursive();
+
+        let rules = self.rules.iter().map(|rule| {
+            let Rule {
+                attrs,
+                vis,
+                name,
+                output,
+                expr,
+            } = rule;
+
+            let mut actions = false;
+            let mut body = expr.expand(output.as_ref(), &mut actions);
+
+            if output.is_none() {
+                body = quote!(::parser_combinators::ParserOnce::<&'input str>::map(#body, ::std::mem::drop));
+            }
+
+            // traced under the name of the rule, which also makes it a rule of its own in the
+            // grammar that `Describe` gives
+            let rule_name = name.to_string();
+            body = quote!(::parser_combinators::ParserOnce::<&'input str>::trace(#body, #rule_name));
+
+            let output = output.as_ref().map_or_else(|| quote!(()), ToTokens::to_token_stream);
+            let parser = quote!(::parser_combinators::Parser<&'input str, Output = #output, Error = ()>);
+            // a trait object can only have one trait that isn't an auto trait
+            let described = quote!(::parser_combinators::introspect::DescribedParser<&'input str, Output = #output, Error = ()>);
+
+            // `map` says nothing about the argument of its closure, so this pins it down
+            // to the output of the parser, which lets actions call methods on their bindings
+            let action = if actions {
+                quote!(
+                    fn __action<'input, P, F, O>(parser: P, f: F) -> ::parser_combinators::map::Map<P, F>
+                    where
+                        P: ::parser_combinators::ParserOnce<&'input str>,
+                        F: Fn(P::Output) -> O,
+                    {
+                        ::parser_combinators::ParserOnce::map(parser, f)
+                    }
+                )
+            } else {
+                TokenStream::new()
+            };
+
+            if recursive.contains(&name.to_string()) {
+                quote!(
+                    #(#attrs)*
+                    #vis fn #name<'input>() -> ::std::boxed::Box<dyn #described + 'input> {
+                        #action
+                        ::std::boxed::Box::new(#body)
+                    }
+                )
+            } else {
+                quote!(
+                    #(#attrs)*
+                    #vis fn #name<'input>() -> impl #parser + ::parser_combinators::introspect::Describe {
+                        #action
+                        #body
+                    }
+                )
+            }
+        });
+
+        Ok(quote!(#(#rules)*))
+    }
+}
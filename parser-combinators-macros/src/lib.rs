@@ -0,0 +1,149 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+
+mod derive;
+mod grammar;
+
+/// Defines parsers of `&str` from PEG-like rules, as in
+///
+/// ```
+/// use parser_combinators::prelude::*;
+///
+/// grammar! {
+///     /// a comma separated list of numbers, like `[1, 2, 3]`
+///     pub list -> Vec<u32> = "[" ws items:(first:number rest:(ws "," ws n:number)*)? ws "]" {
+///         match items {
+///             Some((first, rest)) => std::iter::once(first).chain(rest).collect(),
+///             None => Vec::new(),
+///         }
+///     };
+///
+///     number -> u32 = digits:$(['0'..='9']+) { digits.parse().unwrap() };
+///
+///     ws = [' ' | '\t' | '\n']*;
+/// }
+///
+/// assert_eq!(list().parse("[1, 2,\n3]"), ("", Ok(vec![1, 2, 3])));
+/// assert_eq!(list().parse("[ ]"), ("", Ok(vec![])));
+/// assert_eq!(list().parse("[1, x]"), ("[1, x]", Err(())));
+/// ```
+///
+/// Each rule becomes a function of the same name, with the attributes and visibility of the
+/// rule, that gives back an `impl Parser<&'input str, Output = T, Error = ()> + Describe`,
+/// where `T` comes after the `->` and is `()` when there is none. Rules can refer to each
+/// other in any order, and rules that refer back to themselves are boxed as a
+/// `dyn DescribedParser`, since an `impl Parser` can't contain itself.
+///
+/// Each rule is named with `trace`, so it shows up in a `trace::record` log and is a rule of
+/// its own in the grammar that `introspect::Grammar::new` finds, which can be written out as
+/// EBNF or drawn as a railroad diagram. Rust functions are rules there too, named after their
+/// path, but what their parsers match can't be looked into. Neither can `[pattern]`, `.`, `$e`,
+/// `&e` or `!e`.
+///
+/// The expressions of a rule are
+/// * `"text"` or `'c'`, which match exactly that and give `()`
+/// * `[pattern]`, which matches a `char` that fits the pattern and gives it back
+/// * `.`, which matches any `char`
+/// * `rule`, which matches another rule of the grammar
+/// * `path(args)`, which calls a Rust function for its parser, whose error is dropped
+/// * `(e)`, for grouping
+/// * `e*`, `e+` and `e?`, which give a `Vec` of what `e` gave for the first two, and an
+///   `Option` for the last
+/// * `$e`, which gives the `&'input str` that `e` matched
+/// * `&e` and `!e`, which check whether `e` matches or not without consuming anything
+/// * `name:e`, which binds what `e` gave to `name` inside of the sequence
+/// * `a b c`, which matches each in turn
+/// * `a / b`, which tries `a`, and then `b` if that failed
+///
+/// A sequence can end with an action in braces, which is what the sequence gives back and can
+/// use its bindings. Without an action, a sequence gives back its one binding, or a tuple of
+/// them if there are more. A sequence without any bindings gives back a tuple of everything it
+/// matched, or just what it matched if it is a single expression. A rule without a type drops
+/// whatever its expression gave back.
+///
+/// They expand to `then`, `or`, `zero_or_more`, `one_or_more`, `optional` and `defer`, so they
+/// behave the same way, for instance `e*` never ends if `e` matches without consuming
+/// anything.
+#[proc_macro]
+pub fn grammar(input: TokenStream) -> TokenStream {
+    let grammar = syn::parse_macro_input!(input as grammar::Grammar);
+
+    grammar
+        .expand()
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `Parse` for a struct or an enum, as in
+///
+/// ```
+/// use parser_combinators::prelude::*;
+///
+/// #[derive(Debug, PartialEq, Parse)]
+/// #[parse(tag = "let", end = ";")]
+/// struct Let {
+///     name: String,
+///     #[parse(tag = "=", sep_by = ",")]
+///     values: Vec<Value>,
+/// }
+///
+/// #[derive(Debug, PartialEq, Parse)]
+/// enum Value {
+///     Number(i64),
+///     #[parse(tag = "(", end = ")")]
+///     Tuple(#[parse(sep_by = ",")] Vec<Value>),
+///     Text(#[parse(with = quoted)] String),
+/// }
+///
+/// grammar! {
+///     quoted -> String = "'" text:$((!"'" .)*) "'" { text.to_string() };
+/// }
+///
+/// let (rest, parsed) = Let::parse_str("let x = 1, (2, 'three'), 'four';");
+/// let expected = Let {
+///     name: "x".to_string(),
+///     values: vec![
+///         Value::Number(1),
+///         Value::Tuple(vec![Value::Number(2), Value::Text("three".to_string())]),
+///         Value::Text("four".to_string()),
+///     ],
+/// };
+/// assert_eq!((rest, parsed.unwrap()), ("", expected));
+///
+/// let (rest, parsed) = Let::parse_str("let = 1;");
+/// assert_eq!(rest, "let = 1;");
+/// assert!(matches!(parsed, Err(ParseLetError::Name(_))));
+/// ```
+///
+/// A struct parses its fields in order, and an enum tries each of its variants in order,
+/// taking the first one that parses. Each field is parsed with the `Parse` implementation
+/// of its type, after skipping any whitespace.
+///
+/// The `#[parse(...)]` attribute takes
+/// * `tag = "text"`, for text that has to come first, which can be given more than once
+/// * `end = "text"`, for text that has to come last
+/// * `sep_by = "text"`, on a `Vec` field, which parses any number of items with the text
+///   between them
+/// * `with = path`, on a field, which parses it with the parser that `path()` gives back
+///
+/// Tags on an enum come before those of each of its variants. Like fields, tags skip any
+/// whitespace in front of them.
+///
+/// Along with the implementation, this defines an error enum named `Parse{Type}Error`, with the
+/// same visibility as the type. It has an `Expected` variant for the tags that weren't found,
+/// and a variant for each field that holds the error of its type, except for `sep_by` fields,
+/// which can't fail. These are named after the field, with the variant in front for an enum,
+/// such as `Let::name` being `Name` and `Value::Number` being `Number`. The errors of `with`
+/// parsers can't be named, so their variants don't hold anything. When every variant of an enum
+/// fails, the error is the one of the variant that got the furthest.
+///
+/// Types with generics aren't supported.
+#[proc_macro_derive(Parse, attributes(parse))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+
+    derive::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
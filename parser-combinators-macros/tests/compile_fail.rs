@@ -0,0 +1,5 @@
+/// Checks that mistakes in macro input are reported as errors that point at the mistake
+#[test]
+fn compile_fail() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,135 @@
+use parser_combinators::parse::ExpectedIdent;
+use parser_combinators::prelude::*;
+
+use std::num::ParseIntError;
+
+#[derive(Debug, PartialEq, Parse)]
+#[parse(tag = "fn", end = ";")]
+struct Function {
+    name: String,
+    #[parse(tag = "(", end = ")", sep_by = ",")]
+    args: Vec<Expr>,
+    #[parse(tag = "->")]
+    result: Option<Kind>,
+}
+
+#[derive(Debug, PartialEq, Parse)]
+enum Kind {
+    #[parse(tag = "int")]
+    Int,
+    #[parse(tag = "list")]
+    List(#[parse(tag = "<", end = ">")] Box<Kind>),
+}
+
+/// Every variant starts with the tag of the enum
+#[derive(Debug, PartialEq, Parse)]
+#[parse(tag = "=")]
+enum Expr {
+    Number(i64),
+    #[parse(tag = "(", end = ")")]
+    Pair(i64, #[parse(tag = ",")] i64),
+    #[parse(tag = "-")]
+    Neg {
+        value: Box<Expr>,
+    },
+    Word(#[parse(with = word)] String),
+}
+
+#[derive(Debug, PartialEq, Parse)]
+struct Point(i64, #[parse(tag = ",")] i64);
+
+grammar! {
+    word -> String = w:$(['a'..='z']+) { w.to_string() };
+}
+
+fn parse<T: Parse>(input: &str) -> (&str, Result<T, T::Error>) {
+    T::parse_str(input)
+}
+
+#[test]
+fn structs_parse_fields_in_order() {
+    let (rest, function) = parse::<Function>("fn f(=1, =-=(2, 3), =x) -> list<list<int>>; rest");
+
+    assert_eq!(rest, " rest");
+    assert_eq!(
+        function.unwrap(),
+        Function {
+            name: "f".to_string(),
+            args: vec![
+                Expr::Number(1),
+                Expr::Neg {
+                    value: Box::new(Expr::Pair(2, 3)),
+                },
+                Expr::Word("x".to_string()),
+            ],
+            result: Some(Kind::List(Box::new(Kind::List(Box::new(Kind::Int))))),
+        }
+    );
+
+    // tags have to be there even when the field is optional
+    let (_, function) = parse::<Function>("fn g() ->;");
+    let function = function.unwrap();
+    assert_eq!((function.args, function.result), (vec![], None));
+    assert!(parse::<Function>("fn g();").1.is_err());
+    assert_eq!(parse::<Point>(" 1 , -2 ").1.unwrap(), Point(1, -2));
+}
+
+#[test]
+fn error_variants_are_named_after_fields() {
+    assert!(matches!(
+        parse::<Function>("fn (=1);"),
+        ("fn (=1);", Err(ParseFunctionError::Name(ExpectedIdent)))
+    ));
+    assert!(matches!(
+        parse::<Function>("fn f(=1"),
+        ("fn f(=1", Err(ParseFunctionError::Expected(")")))
+    ));
+    // an optional field can't fail, so a bad one is left for the end tag to trip over
+    assert!(matches!(
+        parse::<Function>("fn f() -> float;"),
+        (_, Err(ParseFunctionError::Expected(";")))
+    ));
+    assert!(matches!(
+        parse::<Point>("1,"),
+        ("1,", Err(ParsePointError::Field1(ParseIntError { .. })))
+    ));
+}
+
+#[test]
+fn enums_take_the_first_variant_that_parses() {
+    assert_eq!(parse::<Expr>("= 5").1.unwrap(), Expr::Number(5));
+    assert_eq!(parse::<Expr>("=(1, 2)").1.unwrap(), Expr::Pair(1, 2));
+    assert_eq!(
+        parse::<Expr>("=abc").1.unwrap(),
+        Expr::Word("abc".to_string())
+    );
+    // the tag of the enum comes before those of the variant
+    assert!(parse::<Expr>("-= 5").1.is_err());
+}
+
+#[test]
+fn enums_report_the_variant_that_got_the_furthest() {
+    // `Pair` got past `(` and its first number
+    assert!(matches!(
+        parse::<Expr>("=(1 2)"),
+        ("=(1 2)", Err(ParseExprError::Expected(",")))
+    ));
+    assert!(matches!(
+        parse::<Expr>("=(1, x)"),
+        ("=(1, x)", Err(ParseExprError::Pair1(_)))
+    ));
+    // `Neg` got past `-`, and failed to parse its value, which is another `Expr`
+    assert!(matches!(
+        parse::<Expr>("=-="),
+        ("=-=", Err(ParseExprError::NegValue(_)))
+    ));
+    // nothing got past the tag of the enum
+    assert!(matches!(
+        parse::<Expr>("5"),
+        ("5", Err(ParseExprError::Expected("=")))
+    ));
+    assert!(matches!(
+        parse::<Kind>("list<x>"),
+        ("list<x>", Err(ParseKindError::List(_)))
+    ));
+}
@@ -0,0 +1,135 @@
+use parser_combinators::introspect::{Grammar, Node};
+use parser_combinators::prelude::*;
+
+grammar! {
+    /// `+` and `-` over `*` and `/` over parentheses, which recurse back to `sum`
+    sum -> i64 = first:product rest:(ws op:['+' | '-'] ws p:product)* {
+        rest.into_iter().fold(first, |n, (op, p)| if op == '+' { n + p } else { n - p })
+    };
+
+    product -> i64 = first:factor rest:(ws op:['*' | '/'] ws f:factor)* {
+        rest.into_iter().fold(first, |n, (op, f)| if op == '*' { n * f } else { n / f })
+    };
+
+    factor -> i64 = "(" ws n:sum ws ")" { n } / number;
+
+    number -> i64 = digits:$('-'? ['0'..='9']+) { digits.parse().unwrap() };
+
+    ws = [' ']*;
+
+    /// a rule that only refers to itself
+    nesting -> usize = "[" n:nesting "]" { n + 1 } / "" { 0 };
+
+    keyword -> &'input str = word:$(['a'..='z']+) !['a'..='z' | '0'..='9'] { word };
+
+    quoted -> String = '"' chars:(!'"' c:.)* '"' { chars.into_iter().collect() };
+
+    /// a Rust function, whose error is dropped
+    answer = literal("42");
+}
+
+fn literal(text: &'static str) -> impl for<'a> Parser<&'a str, Output = (), Error = ()> {
+    parser_combinators::prelude::util::literal(text).map_err(drop)
+}
+
+#[test]
+fn rules_recurse_through_each_other() {
+    assert_eq!(sum().parse("1 + 2 * 3"), ("", Ok(7)));
+    assert_eq!(sum().parse("(1 + 2) * 3"), ("", Ok(9)));
+    assert_eq!(sum().parse("2 * (3 - (4 - 10)) / 3"), ("", Ok(6)));
+    assert_eq!(sum().parse("-4 - -4"), ("", Ok(0)));
+}
+
+#[test]
+fn rule_recurses_into_itself() {
+    assert_eq!(nesting().parse("[[[]]]"), ("", Ok(3)));
+    assert_eq!(nesting().parse(""), ("", Ok(0)));
+    // the innermost `[` can't be closed, so it is left for whatever comes next
+    assert_eq!(nesting().parse("[[]"), ("[[]", Ok(0)));
+}
+
+#[test]
+fn rules_stop_where_they_fail() {
+    // a sequence that fails gives back the input it was given
+    assert_eq!(factor().parse("(1 + 2"), ("(1 + 2", Err(())));
+    assert_eq!(sum().parse("x"), ("x", Err(())));
+    // repetition stops at the first item that doesn't parse, and leaves it in the input
+    assert_eq!(sum().parse("1 + 2 +"), (" +", Ok(3)));
+    assert_eq!(sum().parse("1 + 2 + x"), (" + x", Ok(3)));
+}
+
+#[test]
+fn lookahead_and_capture() {
+    assert_eq!(keyword().parse("let x"), (" x", Ok("let")));
+    assert_eq!(keyword().parse("let2"), ("let2", Err(())));
+    assert_eq!(quoted().parse(r#""a b" c"#), (" c", Ok("a b".to_string())));
+    assert_eq!(quoted().parse(r#""a b"#), (r#""a b"#, Err(())));
+}
+
+#[test]
+fn rust_functions() {
+    assert_eq!(answer().parse("42!"), ("!", Ok(())));
+    assert_eq!(answer().parse("41"), ("41", Err(())));
+}
+
+#[test]
+fn rules_describe_their_grammar() {
+    let expected = concat!(
+        "sum = product, { ws, ? custom parser ?, ws, product } ;\n",
+        "product = factor, { ws, ? custom parser ?, ws, factor } ;\n",
+        "factor = \"(\", ws, sum, ws, \")\" | number ;\n",
+        "ws = { ? custom parser ? } ;\n",
+        "number = ? number ? ;\n",
+    );
+
+    assert_eq!(Grammar::new(&sum()).to_ebnf(), expected);
+    // a rule that only the others refer to starts a grammar of its own
+    assert_eq!(
+        Grammar::new(&ws()).to_ebnf(),
+        "ws = { ? custom parser ? } ;\n"
+    );
+}
+
+#[test]
+fn recursive_rules_refer_back_to_themselves() {
+    let grammar = Grammar::new(&nesting());
+
+    assert_eq!(grammar.rules().len(), 1);
+    assert_eq!(
+        grammar.rules()[0].node,
+        Node::Choice(vec![
+            Node::Sequence(vec![
+                Node::Literal("[".to_string()),
+                Node::Rule("nesting"),
+                Node::Literal("]".to_string()),
+            ]),
+            Node::Literal(String::new()),
+        ])
+    );
+    assert_eq!(
+        grammar.to_ebnf(),
+        "nesting = \"[\", nesting, \"]\" | \"\" ;\n"
+    );
+}
+
+#[test]
+fn rust_functions_are_rules_named_after_them() {
+    assert_eq!(
+        Grammar::new(&answer()).to_ebnf(),
+        "answer = literal ;\nliteral = ? literal ? ;\n"
+    );
+}
+
+#[test]
+fn rules_are_drawn_as_railroad_diagrams() {
+    let svg = Grammar::new(&sum()).to_svg();
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    for rule in ["sum", "product", "factor", "ws", "number"] {
+        let title = format!(">{}</text>", rule);
+        assert!(svg.contains(&title), "no diagram for {}", rule);
+    }
+    // text is escaped
+    assert!(Grammar::new(&quoted()).to_svg().contains("&quot;"));
+}
@@ -0,0 +1,6 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+enum Never {}
+
+fn main() {}
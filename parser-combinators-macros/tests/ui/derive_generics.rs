@@ -0,0 +1,8 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+struct Wrapper<T> {
+    value: T,
+}
+
+fn main() {}
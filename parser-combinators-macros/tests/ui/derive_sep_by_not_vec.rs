@@ -0,0 +1,9 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+struct List {
+    #[parse(sep_by = ",")]
+    items: Option<i64>,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+union Number {
+    int: i64,
+    float: f64,
+}
+
+fn main() {}
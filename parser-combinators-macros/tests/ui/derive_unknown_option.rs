@@ -0,0 +1,9 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+struct Call {
+    #[parse(prefix = "(")]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use parser_combinators::prelude::*;
+
+#[derive(Parse)]
+#[parse(with = name)]
+struct Name(String);
+
+fn main() {}
@@ -0,0 +1,8 @@
+use parser_combinators::prelude::*;
+
+grammar! {
+    a = "a";
+    a = "b";
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+use parser_combinators::prelude::*;
+
+grammar! {
+    pair = ("a" "b" ; "c");
+}
+
+fn main() {}
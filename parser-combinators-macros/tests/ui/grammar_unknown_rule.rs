@@ -0,0 +1,7 @@
+use parser_combinators::prelude::*;
+
+grammar! {
+    list = "[" item* "]";
+}
+
+fn main() {}
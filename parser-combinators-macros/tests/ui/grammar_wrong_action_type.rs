@@ -0,0 +1,7 @@
+use parser_combinators::prelude::*;
+
+grammar! {
+    number -> u32 = digits:$(['0'..='9']+) { digits };
+}
+
+fn main() {}
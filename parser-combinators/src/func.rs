@@ -1,5 +1,6 @@
 use super::*;
 
+#[allow(clippy::wrong_self_convention)]
 pub trait AsParser: Sized {
     #[inline]
     fn defer_once<Input>(self) -> DeferOnce<Self>
@@ -84,7 +85,7 @@ pub struct Func<F: ?Sized>(F);
 
 impl<Input, F, Output, Error> ParserOnce<Input> for FuncOnce<F>
 where
-    F: ?Sized + FnOnce(Input) -> (Input, Result<Output, Error>),
+    F: FnOnce(Input) -> (Input, Result<Output, Error>),
 {
     type Output = Output;
     type Error = Error;
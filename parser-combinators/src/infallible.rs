@@ -32,6 +32,8 @@ impl<T: IntoInfallible, U: IntoInfallible> IntoInfallible for Either<T, U> {
     }
 }
 
+// the other side can't be made, so it can't be reached
+#[allow(unreachable_code)]
 pub fn unwrap_left<L, N: IntoInfallible>(e: Either<L, N>) -> L {
     match e {
         Either::Left(l) => l,
@@ -39,6 +41,7 @@ pub fn unwrap_left<L, N: IntoInfallible>(e: Either<L, N>) -> L {
     }
 }
 
+#[allow(unreachable_code)]
 pub fn unwrap_right<R, N: IntoInfallible>(e: Either<N, R>) -> R {
     match e {
         Either::Left(l) => from_infallible(l.into_infallible()),
@@ -0,0 +1,426 @@
+//! Describes the structure of parsers as a grammar, which can be written out as EBNF or drawn
+//! as a railroad diagram
+//!
+//! Parsers that are named with `trace` become rules of the grammar, and the other parsers
+//! are written out where they are used. Parsers that run closures, such as those made with
+//! `as_parser`, can't be looked into, so they are described as special sequences, which show
+//! the name of the rule they are in if they make up all of it.
+//!
+//! A recursive parser has to be named somewhere along its recursion, so that the grammar can
+//! refer back to it. Otherwise, `defer` stops looking into the parsers that it builds after
+//! `MAX_DEFER_DEPTH` levels. Boxed parsers can only be described if their trait object is of
+//! a trait that has both `Parser` and `Describe` as supertraits, such as `DescribedParser`.
+
+use super::*;
+
+use std::any::type_name;
+use std::ops::{Bound, RangeBounds};
+
+use func::{
+    Defer, DeferMut, DeferOnce, Func, FuncInPlace, FuncMut, FuncMutInPlace, FuncOnce,
+    FuncOnceInPlace,
+};
+use parse::Parsed;
+use trace::Traced;
+
+mod railroad;
+
+/// How many unnamed `defer`s are looked into, one inside of the other
+pub const MAX_DEFER_DEPTH: usize = 32;
+
+/// A parser that can describe what it matches
+pub trait Describe {
+    /// Describes this parser, adding the rules of the named parsers inside of it to `grammar`
+    fn describe(&self, grammar: &mut Grammar) -> Node;
+}
+
+/// A parser that can describe itself, for trait objects such as
+/// `Box<dyn DescribedParser<&str, Output = T, Error = E>>`
+pub trait DescribedParser<Input>: Parser<Input> + Describe {}
+
+impl<Input, P: Parser<Input> + Describe> DescribedParser<Input> for P {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Node {
+    /// Matches without consuming anything
+    Empty,
+    Literal(String),
+    /// Refers to the rule of this name
+    Rule(&'static str),
+    /// Matches something that can't be described, such as a closure, with a name if it has one
+    Special(Option<&'static str>),
+    Sequence(Vec<Node>),
+    Choice(Vec<Node>),
+    Optional(Box<Node>),
+    /// Matches `node` at least `min` times, and at most `max` times if there is a `max`
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+    },
+}
+
+impl Node {
+    /// The nodes in order, with nested sequences flattened
+    fn sequence(nodes: impl IntoIterator<Item = Node>) -> Self {
+        let mut items = Vec::new();
+
+        for node in nodes {
+            match node {
+                Node::Empty => (),
+                Node::Sequence(inner) => items.extend(inner),
+                node => items.push(node),
+            }
+        }
+
+        match items.len() {
+            0 => Node::Empty,
+            1 => items.pop().unwrap(),
+            _ => Node::Sequence(items),
+        }
+    }
+
+    /// The nodes as alternatives, with nested choices flattened
+    fn choice(nodes: impl IntoIterator<Item = Node>) -> Self {
+        let mut items = Vec::new();
+
+        for node in nodes {
+            match node {
+                Node::Choice(inner) => items.extend(inner),
+                node => items.push(node),
+            }
+        }
+
+        match items.len() {
+            1 => items.pop().unwrap(),
+            _ => Node::Choice(items),
+        }
+    }
+
+    fn repeat(node: Node, min: usize, max: Option<usize>) -> Self {
+        Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub name: &'static str,
+    pub node: Node,
+}
+
+/// The rules of a grammar, in the order that they were found in
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Grammar {
+    rules: Vec<Rule>,
+    defer_depth: usize,
+}
+
+impl Grammar {
+    /// The grammar of `parser`, whose first rule is `parser` itself, named `grammar` if it
+    /// doesn't have a name
+    pub fn new(parser: &(impl Describe + ?Sized)) -> Self {
+        let mut grammar = Self::default();
+
+        match parser.describe(&mut grammar) {
+            Node::Rule(_) => (),
+            node => grammar.rules.insert(
+                0,
+                Rule {
+                    name: "grammar",
+                    node,
+                },
+            ),
+        }
+
+        grammar
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Refers to the rule `name`, which is described by `describe` the first time that it
+    /// comes up, so names have to be unique
+    pub fn rule(&mut self, name: &'static str, describe: impl FnOnce(&mut Self) -> Node) -> Node {
+        if self.rules.iter().all(|rule| rule.name != name) {
+            let index = self.rules.len();
+
+            // added before describing it, so that it can refer to itself
+            self.rules.push(Rule {
+                name,
+                node: Node::Empty,
+            });
+
+            self.rules[index].node = match describe(self) {
+                Node::Special(None) => Node::Special(Some(name)),
+                node => node,
+            };
+        }
+
+        Node::Rule(name)
+    }
+
+    /// The grammar in ISO EBNF, one rule per line
+    pub fn to_ebnf(&self) -> String {
+        let mut out = String::new();
+
+        for rule in &self.rules {
+            out.push_str(rule.name);
+            out.push_str(" = ");
+            out.push_str(&ebnf(&rule.node, Precedence::Choice));
+            out.push_str(" ;\n");
+        }
+
+        out
+    }
+
+    /// The grammar as an SVG image, with a railroad diagram of each rule under its name
+    pub fn to_svg(&self) -> String {
+        railroad::draw_rules(&self.rules)
+    }
+}
+
+/// What an EBNF expression is written inside of, which decides when it needs parentheses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Choice,
+    Sequence,
+    Factor,
+}
+
+fn ebnf(node: &Node, precedence: Precedence) -> String {
+    let join = |parts: Vec<String>, separator: &str, node_precedence: Precedence| {
+        if parts.len() > 1 && precedence > node_precedence {
+            format!("( {} )", parts.join(separator))
+        } else {
+            parts.join(separator)
+        }
+    };
+
+    match node {
+        Node::Empty => String::new(),
+        Node::Literal(text) if text.contains('"') => format!("'{}'", text),
+        Node::Literal(text) => format!("\"{}\"", text),
+        Node::Rule(name) => name.to_string(),
+        Node::Special(name) => format!("? {} ?", name.unwrap_or("custom parser")),
+        Node::Sequence(nodes) => join(
+            nodes
+                .iter()
+                .map(|node| ebnf(node, Precedence::Sequence))
+                .collect(),
+            ", ",
+            Precedence::Sequence,
+        ),
+        Node::Choice(nodes) => join(
+            nodes
+                .iter()
+                .map(|node| ebnf(node, Precedence::Choice))
+                .collect(),
+            " | ",
+            Precedence::Choice,
+        ),
+        Node::Optional(node) => format!("[ {} ]", ebnf(node, Precedence::Choice)),
+        // `min` of them, and then either any number more or up to `max - min` more
+        Node::Repeat { node, min, max } => {
+            let mut parts = Vec::new();
+
+            match min {
+                0 => (),
+                1 => parts.push(ebnf(node, Precedence::Factor)),
+                min => parts.push(format!("{} * {}", min, ebnf(node, Precedence::Factor))),
+            }
+
+            match max.map(|max| max.saturating_sub(*min)) {
+                None => parts.push(format!("{{ {} }}", ebnf(node, Precedence::Choice))),
+                Some(0) => (),
+                Some(1) => parts.push(format!("[ {} ]", ebnf(node, Precedence::Choice))),
+                Some(more) => {
+                    parts.push(format!("{} * [ {} ]", more, ebnf(node, Precedence::Choice)))
+                }
+            }
+
+            join(parts, ", ", Precedence::Sequence)
+        }
+    }
+}
+
+impl Describe for Accept {
+    fn describe(&self, _: &mut Grammar) -> Node {
+        Node::Empty
+    }
+}
+
+impl Describe for Reject {
+    fn describe(&self, _: &mut Grammar) -> Node {
+        Node::Special(Some("nothing"))
+    }
+}
+
+impl Describe for Literal {
+    fn describe(&self, _: &mut Grammar) -> Node {
+        Node::Literal(self.0.to_string())
+    }
+}
+
+impl<T> Describe for Parsed<T> {
+    fn describe(&self, _: &mut Grammar) -> Node {
+        Node::Special(Some(type_name::<T>()))
+    }
+}
+
+impl<P: Describe> Describe for Traced<P> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        grammar.rule(self.1, |grammar| self.0.describe(grammar))
+    }
+}
+
+impl<P: Describe, Q: Describe> Describe for Then<P, Q> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        let first = self.0.describe(grammar);
+        Node::sequence(vec![first, self.1.describe(grammar)])
+    }
+}
+
+impl<P: Describe, Q: Describe> Describe for Or<P, Q> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        let first = self.0.describe(grammar);
+        Node::choice(vec![first, self.1.describe(grammar)])
+    }
+}
+
+/// What comes after `P` depends on what `P` gave back, so it can't be described
+impl<P: Describe, F> Describe for AndThen<P, F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::sequence(vec![self.0.describe(grammar), Node::Special(None)])
+    }
+}
+
+impl<P: Describe, F> Describe for OrElse<P, F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::choice(vec![self.0.describe(grammar), Node::Special(None)])
+    }
+}
+
+impl<P: Describe> Describe for Optional<P> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::Optional(Box::new(self.0.describe(grammar)))
+    }
+}
+
+impl<P: Describe, F> Describe for ZeroOrMore<P, F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::repeat(self.0.describe(grammar), 0, None)
+    }
+}
+
+impl<P: Describe, F> Describe for OneOrMore<P, F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::repeat((self.0).0.describe(grammar), 1, None)
+    }
+}
+
+impl<P: Describe, F, R: RangeBounds<usize>> Describe for Repeat<P, F, R> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        let min = match self.2.start_bound() {
+            Bound::Unbounded => 0,
+            Bound::Included(&x) => x,
+            Bound::Excluded(&x) => x.saturating_add(1),
+        };
+
+        let max = match self.2.end_bound() {
+            Bound::Unbounded => None,
+            Bound::Included(&x) => Some(x),
+            Bound::Excluded(&x) => Some(x.saturating_sub(1)),
+        };
+
+        Node::repeat(self.0.describe(grammar), min, max)
+    }
+}
+
+impl<P: Describe, F> Describe for AtMost<P, F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        Node::repeat(self.0.describe(grammar), 0, Some(self.2))
+    }
+}
+
+/// Parsers that only change what another parser gives back, or when it runs, match the same
+/// things as it does
+macro_rules! impl_describe_inner {
+    ($($type:ident<P $(, $param:ident)*>)*) => {$(
+        impl<P: Describe $(, $param)*> Describe for $type<P $(, $param)*> {
+            fn describe(&self, grammar: &mut Grammar) -> Node {
+                self.0.describe(grammar)
+            }
+        }
+    )*};
+}
+
+impl_describe_inner! {
+    Map<P, F> MapErr<P, F> MapBoth<P, F, G>
+    FlatMap<P, F> FlatMapErr<P, F> FlatMapBoth<P, F, G>
+    Filter<P, F> FilterInput<P, F>
+    Inspect<P, F> InspectInput<P, F>
+    DepthLimit<P> MaxInputLen<P>
+}
+
+macro_rules! impl_describe_special {
+    ($($type:ident)*) => {$(
+        impl<F: ?Sized> Describe for $type<F> {
+            fn describe(&self, _: &mut Grammar) -> Node {
+                Node::Special(None)
+            }
+        }
+    )*};
+}
+
+impl_describe_special! {
+    FuncOnce FuncMut Func FuncOnceInPlace FuncMutInPlace FuncInPlace DeferOnce DeferMut
+}
+
+impl<F: Fn() -> P, P: Describe> Describe for Defer<F> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        if grammar.defer_depth >= MAX_DEFER_DEPTH {
+            return Node::Special(Some("too deeply nested"));
+        }
+
+        grammar.defer_depth += 1;
+        let node = (self.0)().describe(grammar);
+        grammar.defer_depth -= 1;
+
+        node
+    }
+}
+
+impl<P: ?Sized + Describe> Describe for Box<P> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        P::describe(self, grammar)
+    }
+}
+
+impl<P: ?Sized + Describe> Describe for Rc<P> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        P::describe(self, grammar)
+    }
+}
+
+impl<P: ?Sized + Describe> Describe for Arc<P> {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        P::describe(self, grammar)
+    }
+}
+
+impl<P: ?Sized + Describe> Describe for &P {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        P::describe(self, grammar)
+    }
+}
+
+impl<P: ?Sized + Describe> Describe for &mut P {
+    fn describe(&self, grammar: &mut Grammar) -> Node {
+        P::describe(self, grammar)
+    }
+}
@@ -0,0 +1,371 @@
+//! Draws rules as railroad diagrams, where everything that a rule matches is a path along the
+//! lines from its left end to its right end
+
+use super::{Node, Rule};
+
+use std::fmt::Write;
+
+const CHAR_WIDTH: i64 = 8;
+const BOX_HEIGHT: i64 = 22;
+/// The horizontal space between the items of a sequence
+const GAP: i64 = 10;
+/// The vertical space between the alternatives of a choice
+const V_GAP: i64 = 10;
+/// The radius of the curves, where the lines of a choice or a loop turn
+const RADIUS: i64 = 10;
+const MARGIN: i64 = 20;
+/// The space for the name above each diagram
+const TITLE: i64 = 24;
+
+const STYLE: &str = "path { fill: none; stroke: black; stroke-width: 1.5 } \
+    rect { fill: #ffc; stroke: black; stroke-width: 1.5 } \
+    rect.rule { fill: #cdf } \
+    rect.special { fill: #eee; stroke-dasharray: 4 2 } \
+    text { font: 14px monospace; text-anchor: middle } \
+    text.title { font-weight: bold; text-anchor: start } \
+    text.label { font-size: 11px }";
+
+/// How much room a node takes, around the line that goes through it
+#[derive(Debug, Clone, Copy)]
+struct Size {
+    width: i64,
+    /// Above the line
+    up: i64,
+    /// Below the line
+    down: i64,
+}
+
+fn text_width(text: &str) -> i64 {
+    text.chars().count() as i64 * CHAR_WIDTH + 2 * GAP
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// What the label of a loop that goes around at least once says, if it needs one
+fn repeat_label(min: usize, max: Option<usize>) -> Option<String> {
+    match (min, max) {
+        (1, None) => None,
+        (min, None) => Some(format!("{}..", min)),
+        (min, Some(max)) => Some(format!("{}..={}", min, max)),
+    }
+}
+
+/// The text of a node that is drawn as a box, with the class of the box
+fn boxed(node: &Node) -> Option<(String, &'static str)> {
+    match node {
+        Node::Literal(text) => Some((text.clone(), "literal")),
+        Node::Rule(name) => Some((name.to_string(), "rule")),
+        Node::Special(name) => Some((name.unwrap_or("...").to_string(), "special")),
+        _ => None,
+    }
+}
+
+fn choice_size(nodes: &[Node]) -> Size {
+    let sizes: Vec<_> = nodes.iter().map(size).collect();
+    let width = sizes.iter().map(|size| size.width).max().unwrap_or(0);
+
+    Size {
+        width: width + 4 * RADIUS,
+        up: sizes.first().map_or(0, |size| size.up),
+        down: branch_offsets(&sizes).last().copied().unwrap_or(0)
+            + sizes.last().map_or(0, |size| size.down),
+    }
+}
+
+/// How far below the line of a choice the line of each of its alternatives is
+fn branch_offsets(sizes: &[Size]) -> Vec<i64> {
+    let mut offsets = Vec::with_capacity(sizes.len());
+    let mut offset = 0;
+
+    for (i, size) in sizes.iter().enumerate() {
+        if i != 0 {
+            // there has to be room for the curves that go down to the alternative
+            offset = (offset + sizes[i - 1].down + V_GAP + size.up).max(offset + 2 * RADIUS);
+        }
+
+        offsets.push(offset);
+    }
+
+    offsets
+}
+
+/// How far below the line the line back of a loop is
+fn loop_offset(size: Size) -> i64 {
+    (size.down + V_GAP).max(2 * RADIUS)
+}
+
+fn size(node: &Node) -> Size {
+    if let Some((text, _)) = boxed(node) {
+        return Size {
+            width: text_width(&text),
+            up: BOX_HEIGHT / 2,
+            down: BOX_HEIGHT / 2,
+        };
+    }
+
+    match node {
+        Node::Sequence(nodes) => {
+            let sizes: Vec<_> = nodes.iter().map(size).collect();
+
+            Size {
+                width: sizes.iter().map(|size| size.width).sum::<i64>()
+                    + GAP * (sizes.len() as i64 - 1).max(0),
+                up: sizes.iter().map(|size| size.up).max().unwrap_or(0),
+                down: sizes.iter().map(|size| size.down).max().unwrap_or(0),
+            }
+        }
+        Node::Choice(nodes) => choice_size(nodes),
+        Node::Optional(node) => choice_size(&[Node::Empty, (**node).clone()]),
+        Node::Repeat { node, min: 0, max } => choice_size(&[
+            Node::Empty,
+            Node::Repeat {
+                node: node.clone(),
+                min: 1,
+                max: *max,
+            },
+        ]),
+        Node::Repeat { node, min, max } => {
+            let inner = size(node);
+            let label = repeat_label(*min, *max).map_or(0, |_| 14);
+
+            Size {
+                width: inner.width + 4 * RADIUS,
+                up: inner.up,
+                down: loop_offset(inner) + label,
+            }
+        }
+        _ => Size {
+            width: 0,
+            up: 0,
+            down: 0,
+        },
+    }
+}
+
+/// Draws `node` with its line starting at `(x, y)`
+fn draw(node: &Node, x: i64, y: i64, out: &mut String) {
+    let line = |out: &mut String, from: i64, to: i64, y: i64| {
+        if from != to {
+            writeln!(out, r#"<path d="M{} {} H{}"/>"#, from, y, to).unwrap();
+        }
+    };
+
+    if let Some((text, class)) = boxed(node) {
+        let width = text_width(&text);
+        let rounded = if class == "literal" {
+            BOX_HEIGHT / 2
+        } else {
+            0
+        };
+
+        writeln!(
+            out,
+            r#"<rect class="{}" x="{}" y="{}" width="{}" height="{}" rx="{}"/>"#,
+            class,
+            x,
+            y - BOX_HEIGHT / 2,
+            width,
+            BOX_HEIGHT,
+            rounded
+        )
+        .unwrap();
+        writeln!(
+            out,
+            r#"<text x="{}" y="{}">{}</text>"#,
+            x + width / 2,
+            y + 5,
+            escape(&text)
+        )
+        .unwrap();
+        return;
+    }
+
+    match node {
+        Node::Sequence(nodes) => {
+            let mut x = x;
+
+            for (i, node) in nodes.iter().enumerate() {
+                if i != 0 {
+                    line(out, x, x + GAP, y);
+                    x += GAP;
+                }
+
+                draw(node, x, y, out);
+                x += size(node).width;
+            }
+        }
+        Node::Choice(nodes) => draw_choice(nodes, x, y, out),
+        Node::Optional(node) => draw_choice(&[Node::Empty, (**node).clone()], x, y, out),
+        // the path that skips the loop is what makes it start at 0
+        Node::Repeat { node, min: 0, max } => {
+            let repeat = Node::Repeat {
+                node: node.clone(),
+                min: 1,
+                max: *max,
+            };
+
+            draw_choice(&[Node::Empty, repeat], x, y, out);
+        }
+        Node::Repeat { node, min, max } => {
+            let inner = size(node);
+            let right = x + 3 * RADIUS + inner.width;
+            let back = y + loop_offset(inner);
+
+            line(out, x, x + 2 * RADIUS, y);
+            draw(node, x + 2 * RADIUS, y, out);
+            line(
+                out,
+                x + 2 * RADIUS + inner.width,
+                x + 4 * RADIUS + inner.width,
+                y,
+            );
+
+            writeln!(
+                out,
+                r#"<path d="M{} {} a{r} {r} 0 0 1 {r} {r} V{} a{r} {r} 0 0 1 -{r} {r} H{} a{r} {r} 0 0 1 -{r} -{r} V{} a{r} {r} 0 0 1 {r} -{r}"/>"#,
+                right - RADIUS,
+                y,
+                back - RADIUS,
+                x + 2 * RADIUS,
+                y + RADIUS,
+                r = RADIUS
+            )
+            .unwrap();
+
+            if let Some(label) = repeat_label(*min, *max) {
+                writeln!(
+                    out,
+                    r#"<text class="label" x="{}" y="{}">{}</text>"#,
+                    x + 2 * RADIUS + inner.width / 2,
+                    back + 14,
+                    label
+                )
+                .unwrap();
+            }
+        }
+        _ => (),
+    }
+}
+
+fn draw_choice(nodes: &[Node], x: i64, y: i64, out: &mut String) {
+    let sizes: Vec<_> = nodes.iter().map(size).collect();
+    let offsets = branch_offsets(&sizes);
+    let width = sizes.iter().map(|size| size.width).max().unwrap_or(0);
+    let right = x + 2 * RADIUS + width;
+
+    for ((node, size), offset) in nodes.iter().zip(&sizes).zip(offsets) {
+        let branch_y = y + offset;
+
+        if offset == 0 {
+            writeln!(out, r#"<path d="M{} {} H{}"/>"#, x, y, x + 2 * RADIUS).unwrap();
+        } else {
+            writeln!(
+                out,
+                r#"<path d="M{} {} a{r} {r} 0 0 1 {r} {r} V{} a{r} {r} 0 0 0 {r} {r}"/>"#,
+                x,
+                y,
+                branch_y - RADIUS,
+                r = RADIUS
+            )
+            .unwrap();
+        }
+
+        draw(node, x + 2 * RADIUS, branch_y, out);
+
+        if x + 2 * RADIUS + size.width != right {
+            writeln!(
+                out,
+                r#"<path d="M{} {} H{}"/>"#,
+                x + 2 * RADIUS + size.width,
+                branch_y,
+                right
+            )
+            .unwrap();
+        }
+
+        if offset == 0 {
+            writeln!(
+                out,
+                r#"<path d="M{} {} H{}"/>"#,
+                right,
+                y,
+                right + 2 * RADIUS
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                out,
+                r#"<path d="M{} {} a{r} {r} 0 0 0 {r} -{r} V{} a{r} {r} 0 0 1 {r} -{r}"/>"#,
+                right,
+                branch_y,
+                y + RADIUS,
+                r = RADIUS
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// An SVG image with the diagram of each rule under its name, one under the other
+pub fn draw_rules(rules: &[Rule]) -> String {
+    let mut body = String::new();
+    let mut y = MARGIN;
+    let mut width = 0;
+
+    for rule in rules {
+        let size = size(&rule.node);
+        let line_y = y + TITLE + size.up;
+        // the ends of the diagram are short bars, with a bit of line on either side of it
+        let start = MARGIN + GAP;
+        let end = start + GAP + size.width + GAP;
+
+        writeln!(
+            body,
+            r#"<text class="title" x="{}" y="{}">{}</text>"#,
+            MARGIN,
+            y + 14,
+            escape(rule.name)
+        )
+        .unwrap();
+        writeln!(
+            body,
+            r#"<path d="M{} {} v10 M{} {} H{}"/>"#,
+            MARGIN,
+            line_y - 5,
+            MARGIN,
+            line_y,
+            start + GAP
+        )
+        .unwrap();
+        draw(&rule.node, start + GAP, line_y, &mut body);
+        writeln!(
+            body,
+            r#"<path d="M{} {} H{} M{} {} v10"/>"#,
+            start + GAP + size.width,
+            line_y,
+            end + GAP,
+            end + GAP,
+            line_y - 5
+        )
+        .unwrap();
+
+        width = width.max(end + GAP + MARGIN);
+        y = line_y + size.down + MARGIN;
+    }
+
+    format!(
+        concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            "\n<style>{}</style>\n{}</svg>\n"
+        ),
+        STYLE,
+        body,
+        w = width,
+        h = y
+    )
+}
@@ -1,5 +1,6 @@
 #![forbid(unsafe_code)]
-#![feature(specialization, unsized_locals)]
+#![allow(incomplete_features)]
+#![feature(specialization)]
 
 use std::rc::Rc;
 use std::sync::Arc;
@@ -23,25 +24,36 @@ pub mod filter;
 pub mod flat_map;
 pub mod func;
 pub mod inspect;
+pub mod introspect;
+pub mod limit;
 pub mod map;
+pub mod parse;
 pub mod repeat;
 pub mod then;
+pub mod trace;
 
 use and_then::*;
 use filter::*;
 use flat_map::*;
 use inspect::*;
+use limit::*;
 use map::*;
 use repeat::*;
 use then::*;
+use trace::Traced;
 
 pub mod prelude {
     pub use crate::func::AsParser as _;
     pub use crate::{Parser, ParserMut, ParserOnce};
 
+    pub use crate::alt;
     pub use crate::reject;
+    pub use crate::seq;
     pub use crate::unimplemented_parser;
 
+    pub use crate::parse::{parsed, Parse};
+    pub use parser_combinators_macros::{grammar, Parse};
+
     pub fn defer_once<P, Input>(p: P) -> crate::func::DeferOnce<P>
     where
         crate::func::DeferOnce<P>: ParserOnce<Input>,
@@ -65,6 +77,7 @@ pub mod prelude {
 
     pub mod util {
         pub use crate::infallible::*;
+        pub use either::Either;
 
         pub fn ignore() {}
 
@@ -78,6 +91,15 @@ pub mod prelude {
         pub fn snd<T, U>((_, u): (T, U)) -> U {
             u
         }
+
+        /// The text that `literal` expected but didn't find
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct LiteralError(pub &'static str);
+
+        /// Matches exactly `expected`, which is what string literals in `seq!` expand to
+        pub fn literal(expected: &'static str) -> crate::Literal {
+            crate::Literal(expected)
+        }
     }
 
     #[macro_export]
@@ -85,6 +107,106 @@ pub mod prelude {
         ($($type:tt)*) => { <$crate::Reject as ParserCombinators<$($type)*>>::map($crate::Reject, $crate::prelude::util::IntoInfallible::into_infallible) };
     }
 
+    /// Parses each item in turn, and gives back what the expression after `=>` builds out
+    /// of the named items, as in
+    ///
+    /// ```
+    /// # use parser_combinators::prelude::*;
+    /// # grammar! {
+    /// #     identifier -> &'input str = $(['a'..='z']+);
+    /// #     ws = [' ']*;
+    /// #     quoted -> &'input str = '"' text:$((!'"' .)*) '"' { text };
+    /// # }
+    /// let assignment =
+    ///     seq!(name = identifier(), _ = ws(), "=", _ = ws(), value = quoted() => (name, value));
+    ///
+    /// assert_eq!(assignment.parse(r#"x = "1";"#), (";", Ok(("x", "1"))));
+    /// assert!(assignment.parse(r#"x "1""#).1.is_err());
+    /// ```
+    ///
+    /// An item is `name = parser`, `_ = parser` for one whose output isn't needed, or a string
+    /// literal, which is matched with `util::literal`. Without `=>`, this gives back the named
+    /// items, in a tuple if there is more than one. This expands to `then`, so the error is
+    /// the same nesting of `Either` that chaining `then` would give.
+    #[macro_export]
+    macro_rules! seq {
+        (@then [] $p:expr) => { $p };
+        (@then [$parser:expr] $p:expr) => { $crate::ParserOnce::then($parser, $p) };
+        (@seq [$parser:expr] [$pat:tt] [$($names:ident)*]) => {
+            $crate::ParserOnce::map($parser, |$pat| ($($names),*))
+        };
+        (@seq [$parser:expr] [$pat:tt] [$($names:ident)*] => $body:expr) => {
+            $crate::ParserOnce::map($parser, move |$pat| $body)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] _ = $p:expr $(, $($rest:tt)*)?) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $p)] [($($pat,)? _)] [$($names)*] $($($rest)*)?)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] _ = $p:expr => $body:expr) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $p)] [($($pat,)? _)] [$($names)*] => $body)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] $name:ident = $p:expr $(, $($rest:tt)*)?) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $p)] [($($pat,)? $name)] [$($names)* $name] $($($rest)*)?)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] $name:ident = $p:expr => $body:expr) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $p)] [($($pat,)? $name)] [$($names)* $name] => $body)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] $lit:literal $(, $($rest:tt)*)?) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $crate::prelude::util::literal($lit))] [($($pat,)? _)] [$($names)*] $($($rest)*)?)
+        };
+        (@seq [$($parser:expr)?] [$($pat:tt)?] [$($names:ident)*] $lit:literal => $body:expr) => {
+            $crate::seq!(@seq [$crate::seq!(@then [$($parser)?] $crate::prelude::util::literal($lit))] [($($pat,)? _)] [$($names)*] => $body)
+        };
+        (@seq $($rest:tt)*) => {
+            ::std::compile_error!("expected `name = parser`, `_ = parser` or a string literal")
+        };
+        ($($items:tt)*) => { $crate::seq!(@seq [] [] [] $($items)*) };
+    }
+
+    /// Tries each parser in turn, and gives back what the function after its `=>` makes of
+    /// its output, as in
+    ///
+    /// ```
+    /// # use parser_combinators::prelude::*;
+    /// # #[derive(Debug, PartialEq)]
+    /// # enum Value<'a> {
+    /// #     Number(u32),
+    /// #     String(&'a str),
+    /// #     Name(&'a str),
+    /// # }
+    /// # grammar! {
+    /// #     number -> u32 = digits:$(['0'..='9']+) { digits.parse().unwrap() };
+    /// #     quoted -> &'input str = '"' text:$((!'"' .)*) '"' { text };
+    /// #     ident -> &'input str = $(['a'..='z']+);
+    /// # }
+    /// let value = alt!(number() => Value::Number, quoted() => Value::String, ident() => Value::Name);
+    ///
+    /// assert_eq!(value.parse("12"), ("", Ok(Value::Number(12))));
+    /// assert_eq!(value.parse(r#""12""#), ("", Ok(Value::String("12"))));
+    /// assert_eq!(value.parse("x"), ("", Ok(Value::Name("x"))));
+    /// assert!(value.parse("?").1.is_err());
+    /// ```
+    ///
+    /// so every branch gives back the same type. A branch without `=>` gives back the output of
+    /// its parser as it is. This expands to `or`, so the error is the same nesting of tuples
+    /// that chaining `or` would give.
+    #[macro_export]
+    macro_rules! alt {
+        (@alt [$parser:expr]) => { $parser };
+        (@alt [$parser:expr] $p:expr $(=> $f:expr)? $(, $($rest:tt)*)?) => {
+            $crate::alt!(@alt [
+                $crate::ParserOnce::map(
+                    $crate::ParserOnce::or($parser, $crate::alt!(@branch $p $(=> $f)?)),
+                    $crate::prelude::util::Either::into_inner,
+                )
+            ] $($($rest)*)?)
+        };
+        (@branch $p:expr) => { $p };
+        (@branch $p:expr => $f:expr) => { $crate::ParserOnce::map($p, $f) };
+        ($p:expr $(=> $f:expr)? $(, $($rest:tt)*)?) => {
+            $crate::alt!(@alt [$crate::alt!(@branch $p $(=> $f)?)] $($($rest)*)?)
+        };
+    }
+
     #[macro_export]
     macro_rules! unimplemented_parser {
         ($($type:tt)*) => {{
@@ -104,6 +226,10 @@ type ParseResult<Input, P: ParserMut<Input>> = (Input, Result<P::Output, P::Erro
 pub struct Accept;
 pub struct Reject;
 
+/// Matches exactly the text it holds, see `util::literal`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Literal(pub(crate) &'static str);
+
 pub trait Restore {
     type SavePoint: Copy;
 
@@ -290,6 +416,42 @@ pub trait ParserOnce<Input> {
     {
         Repeat(self, f, r)
     }
+
+    /// Like `zero_or_more`, but fails with `TooMany` instead of collecting more than `max` items
+    #[inline]
+    fn at_most<F>(self, max: usize, f: F) -> AtMost<Self, F>
+    where
+        Self: Sized,
+    {
+        AtMost(self, f, max)
+    }
+
+    /// Fails with `DepthExceeded` without running this parser, if `depth` is past its limit
+    #[inline]
+    fn depth_limit(self, depth: Depth) -> DepthLimit<Self>
+    where
+        Self: Sized,
+    {
+        DepthLimit(self, depth)
+    }
+
+    /// Names this parser in the log that `trace::record` makes
+    #[inline]
+    fn trace(self, name: &'static str) -> Traced<Self>
+    where
+        Self: Sized,
+    {
+        Traced(self, name)
+    }
+
+    /// Fails with `InputTooLong` without running this parser, if there is more than `max` input
+    #[inline]
+    fn max_input_len(self, max: usize) -> MaxInputLen<Self>
+    where
+        Self: Sized,
+    {
+        MaxInputLen(self, max)
+    }
 }
 
 impl<Input> ParserOnce<Input> for Accept {
@@ -344,6 +506,37 @@ impl<Input> Parser<Input> for Reject {
     }
 }
 
+impl<'a> ParserOnce<&'a str> for Literal {
+    type Output = ();
+    type Error = prelude::util::LiteralError;
+
+    #[inline]
+    fn parse_once(self, input: &'a str) -> ParseResult<&'a str, Self> {
+        self.parse(input)
+    }
+
+    fn parse_box(self: Box<Self>, input: &'a str) -> ParseResult<&'a str, Self> {
+        self.parse(input)
+    }
+}
+
+impl<'a> ParserMut<&'a str> for Literal {
+    #[inline]
+    fn parse_mut(&mut self, input: &'a str) -> ParseResult<&'a str, Self> {
+        self.parse(input)
+    }
+}
+
+impl<'a> Parser<&'a str> for Literal {
+    #[inline]
+    fn parse(&self, input: &'a str) -> ParseResult<&'a str, Self> {
+        match input.strip_prefix(self.0) {
+            Some(rest) => (rest, Ok(())),
+            None => (input, Err(prelude::util::LiteralError(self.0))),
+        }
+    }
+}
+
 impl<Input, P: ?Sized + ParserOnce<Input>> ParserOnce<Input> for Box<P> {
     type Output = P::Output;
     type Error = P::Error;
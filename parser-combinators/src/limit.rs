@@ -0,0 +1,169 @@
+//! Guards for parsing untrusted input, so that a hostile document can't overflow the stack
+//! by nesting deeply, or use up memory by being huge
+
+use super::*;
+
+/// How deeply nested a recursive grammar currently is, along with how deep it may go
+///
+/// Parsers are built lazily by `defer` as the input nests, so a `Depth` is handed
+/// down through the functions that build them, with `deeper` called on every level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Depth {
+    current: usize,
+    max: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DepthExceeded;
+
+impl Depth {
+    /// The outermost level of a grammar that may nest `max` levels deep
+    pub fn limit(max: usize) -> Self {
+        Self { current: 0, max }
+    }
+
+    pub fn current(self) -> usize {
+        self.current
+    }
+
+    pub fn max(self) -> usize {
+        self.max
+    }
+
+    /// The depth one level further in
+    pub fn deeper(self) -> Self {
+        Self {
+            current: self.current.saturating_add(1),
+            ..self
+        }
+    }
+
+    pub fn is_exceeded(self) -> bool {
+        self.current > self.max
+    }
+}
+
+/// Fails without running its parser once `Depth` is past its limit, and otherwise runs it on
+/// a stack with room for at least one more level
+///
+/// A level of a grammar built from combinators can take tens of KiB of stack in a debug build,
+/// so the default 2 MiB stacks of threads would only fit a few dozen levels. Rather than
+/// sizing limits for whichever thread a parse happens to run on, each level that gets close
+/// to the end of the stack carries on in a newly allocated segment. The depth limit is what
+/// bounds how many of those there can be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DepthLimit<P>(pub(crate) P, pub(crate) Depth);
+
+/// How much stack has to be left for a level to run on the current segment
+const RED_ZONE: usize = 256 << 10;
+
+/// How large each newly allocated segment of stack is
+const STACK_SEGMENT: usize = 4 << 20;
+
+impl<Input, P: ParserOnce<Input>> ParserOnce<Input> for DepthLimit<P> {
+    type Output = P::Output;
+    type Error = Either<DepthExceeded, P::Error>;
+
+    #[inline]
+    fn parse_once(self, input: Input) -> ParseResult<Input, Self> {
+        if self.1.is_exceeded() {
+            return (input, Err(Either::Left(DepthExceeded)));
+        }
+
+        let (input, out) =
+            stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.0.parse_once(input));
+        (input, out.map_err(Either::Right))
+    }
+
+    impl_parse_box! { Input }
+}
+
+impl<Input, P: ParserMut<Input>> ParserMut<Input> for DepthLimit<P> {
+    #[inline]
+    fn parse_mut(&mut self, input: Input) -> ParseResult<Input, Self> {
+        if self.1.is_exceeded() {
+            return (input, Err(Either::Left(DepthExceeded)));
+        }
+
+        let (input, out) = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.0.parse_mut(input));
+        (input, out.map_err(Either::Right))
+    }
+}
+
+impl<Input, P: Parser<Input>> Parser<Input> for DepthLimit<P> {
+    #[inline]
+    fn parse(&self, input: Input) -> ParseResult<Input, Self> {
+        if self.1.is_exceeded() {
+            return (input, Err(Either::Left(DepthExceeded)));
+        }
+
+        let (input, out) = stacker::maybe_grow(RED_ZONE, STACK_SEGMENT, || self.0.parse(input));
+        (input, out.map_err(Either::Right))
+    }
+}
+
+/// Inputs that know how much is left of them
+pub trait InputLen {
+    fn input_len(&self) -> usize;
+}
+
+impl InputLen for &str {
+    #[inline]
+    fn input_len(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> InputLen for &[T] {
+    #[inline]
+    fn input_len(&self) -> usize {
+        self.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InputTooLong;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MaxInputLen<P>(pub(crate) P, pub(crate) usize);
+
+impl<Input: InputLen, P: ParserOnce<Input>> ParserOnce<Input> for MaxInputLen<P> {
+    type Output = P::Output;
+    type Error = Either<InputTooLong, P::Error>;
+
+    #[inline]
+    fn parse_once(self, input: Input) -> ParseResult<Input, Self> {
+        if input.input_len() > self.1 {
+            return (input, Err(Either::Left(InputTooLong)));
+        }
+
+        let (input, out) = self.0.parse_once(input);
+        (input, out.map_err(Either::Right))
+    }
+
+    impl_parse_box! { Input }
+}
+
+impl<Input: InputLen, P: ParserMut<Input>> ParserMut<Input> for MaxInputLen<P> {
+    #[inline]
+    fn parse_mut(&mut self, input: Input) -> ParseResult<Input, Self> {
+        if input.input_len() > self.1 {
+            return (input, Err(Either::Left(InputTooLong)));
+        }
+
+        let (input, out) = self.0.parse_mut(input);
+        (input, out.map_err(Either::Right))
+    }
+}
+
+impl<Input: InputLen, P: Parser<Input>> Parser<Input> for MaxInputLen<P> {
+    #[inline]
+    fn parse(&self, input: Input) -> ParseResult<Input, Self> {
+        if input.input_len() > self.1 {
+            return (input, Err(Either::Left(InputTooLong)));
+        }
+
+        let (input, out) = self.0.parse(input);
+        (input, out.map_err(Either::Right))
+    }
+}
@@ -0,0 +1,182 @@
+use super::*;
+
+use std::marker::PhantomData;
+use std::num::{ParseFloatError, ParseIntError};
+use std::str::ParseBoolError;
+
+/// A type that can parse itself from the start of a `&str`, which is what `#[derive(Parse)]`
+/// implements
+///
+/// The implementations for tokens skip any whitespace in front of them, so that a type made
+/// up of them can be parsed from text that has whitespace between its tokens. On failure,
+/// the input that is given back should be the input that was given in.
+pub trait Parse: Sized {
+    type Error;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>);
+}
+
+/// The parser of a type that implements `Parse`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Parsed<T>(PhantomData<fn() -> T>);
+
+pub fn parsed<T: Parse>() -> Parsed<T> {
+    Parsed(PhantomData)
+}
+
+impl<'a, T: Parse> ParserOnce<&'a str> for Parsed<T> {
+    type Output = T;
+    type Error = T::Error;
+
+    #[inline]
+    fn parse_once(self, input: &'a str) -> ParseResult<&'a str, Self> {
+        T::parse_str(input)
+    }
+
+    fn parse_box(self: Box<Self>, input: &'a str) -> ParseResult<&'a str, Self> {
+        T::parse_str(input)
+    }
+}
+
+impl<'a, T: Parse> ParserMut<&'a str> for Parsed<T> {
+    #[inline]
+    fn parse_mut(&mut self, input: &'a str) -> ParseResult<&'a str, Self> {
+        T::parse_str(input)
+    }
+}
+
+impl<'a, T: Parse> Parser<&'a str> for Parsed<T> {
+    #[inline]
+    fn parse(&self, input: &'a str) -> ParseResult<&'a str, Self> {
+        T::parse_str(input)
+    }
+}
+
+/// The length of the token at the start of `input` whose chars all fit `f`
+fn token_len(input: &str, mut f: impl FnMut(usize, char) -> bool) -> usize {
+    input
+        .char_indices()
+        .find(|&(i, c)| !f(i, c))
+        .map_or(input.len(), |(i, _)| i)
+}
+
+/// Parses the token at the start of `input` with `FromStr`, keeping the input as it was
+/// if that fails
+fn parse_token<T: std::str::FromStr>(
+    input: &str,
+    f: impl Fn(usize, char) -> bool,
+) -> (&str, Result<T, T::Err>) {
+    let trimmed = input.trim_start();
+    let len = token_len(trimmed, f);
+
+    match trimmed[..len].parse() {
+        Ok(value) => (&trimmed[len..], Ok(value)),
+        Err(e) => (input, Err(e)),
+    }
+}
+
+macro_rules! impl_parse_int {
+    ($($int:ty)*) => {$(
+        /// A decimal number, with a sign in front for signed types
+        impl Parse for $int {
+            type Error = ParseIntError;
+
+            fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+                parse_token(input, |i, c| c.is_ascii_digit() || (i == 0 && (c == '-' || c == '+')))
+            }
+        }
+    )*};
+}
+
+impl_parse_int! { i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize }
+
+macro_rules! impl_parse_float {
+    ($($float:ty)*) => {$(
+        /// A decimal number, which may have a sign, a fraction and an exponent
+        impl Parse for $float {
+            type Error = ParseFloatError;
+
+            fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+                let trimmed = input.trim_start();
+                let mut prev = None;
+                let len = token_len(trimmed, |i, c| {
+                    let fits = match c {
+                        '0'..='9' | '.' | 'e' | 'E' => true,
+                        '-' | '+' => i == 0 || matches!(prev, Some('e') | Some('E')),
+                        _ => false,
+                    };
+                    prev = Some(c);
+                    fits
+                });
+
+                match trimmed[..len].parse() {
+                    Ok(value) => (&trimmed[len..], Ok(value)),
+                    Err(e) => (input, Err(e)),
+                }
+            }
+        }
+    )*};
+}
+
+impl_parse_float! { f32 f64 }
+
+/// `true` or `false`
+impl Parse for bool {
+    type Error = ParseBoolError;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+        parse_token(input, |_, c| c.is_alphanumeric() || c == '_')
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedIdent;
+
+/// An identifier, which starts with a letter or `_` and goes on with letters, digits and `_`
+impl Parse for String {
+    type Error = ExpectedIdent;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+        let trimmed = input.trim_start();
+        let len = token_len(trimmed, |i, c| {
+            c.is_alphabetic() || c == '_' || (i != 0 && c.is_numeric())
+        });
+
+        if len == 0 {
+            (input, Err(ExpectedIdent))
+        } else {
+            (&trimmed[len..], Ok(trimmed[..len].to_string()))
+        }
+    }
+}
+
+/// The same as `T`, which lets recursive types be parsed
+///
+/// The error is boxed too, since the error of a recursive type holds itself as well
+impl<T: Parse> Parse for Box<T> {
+    type Error = Box<T::Error>;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+        let (input, out) = T::parse_str(input);
+        (input, out.map(Box::new).map_err(Box::new))
+    }
+}
+
+/// `T` if it is there, and `None` if it isn't
+impl<T: Parse> Parse for Option<T> {
+    type Error = Infallible;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+        let (input, out) = T::parse_str(input);
+        (input, Ok(out.ok()))
+    }
+}
+
+/// As many `T` as there are in a row
+impl<T: Parse> Parse for Vec<T> {
+    type Error = Infallible;
+
+    fn parse_str(input: &str) -> (&str, Result<Self, Self::Error>) {
+        parsed::<T>().zero_or_more(Vec::new).parse(input)
+    }
+}
@@ -230,7 +230,7 @@ where
         };
 
         let max = match self.2.end_bound() {
-            Bound::Unbounded => usize::max_value(),
+            Bound::Unbounded => usize::MAX,
             Bound::Included(&x) => x + 1,
             Bound::Excluded(&x) => x,
         };
@@ -283,7 +283,7 @@ where
         };
 
         let max = match self.2.end_bound() {
-            Bound::Unbounded => usize::max_value(),
+            Bound::Unbounded => usize::MAX,
             Bound::Included(&x) => x + 1,
             Bound::Excluded(&x) => x,
         };
@@ -334,7 +334,7 @@ where
         };
 
         let max = match self.2.end_bound() {
-            Bound::Unbounded => usize::max_value(),
+            Bound::Unbounded => usize::MAX,
             Bound::Included(&x) => x + 1,
             Bound::Excluded(&x) => x,
         };
@@ -364,3 +364,99 @@ where
         (input, Ok(c))
     }
 }
+
+/// Collects items like `ZeroOrMore`, but gives up once it finds more than a set number of them
+///
+/// Neither of the other repetitions can be made to do this. `ZeroOrMore` can't fail, which
+/// grammars rely on by unwrapping its `Infallible` error, and `Repeat` with an upper bound
+/// stops at the bound and leaves the rest of the items in the input, which is what a grammar
+/// like `x{2,3} x*` means. This fails instead, so that an oversized collection can't go
+/// unnoticed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AtMost<P, F>(pub(crate) P, pub(crate) F, pub(crate) usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TooMany;
+
+impl<Input: Restore, P, F, C> ParserOnce<Input> for AtMost<P, F>
+where
+    P: ParserMut<Input>,
+    F: FnOnce() -> C,
+    C: Collection<P::Output>,
+{
+    type Output = C;
+    type Error = TooMany;
+
+    #[inline]
+    fn parse_once(mut self, mut input: Input) -> ParseResult<Input, Self> {
+        let mut c = (self.1)();
+        let save = input.save();
+
+        for count in 0.. {
+            let (next, out) = self.0.parse_mut(input);
+            input = next;
+
+            match out {
+                Ok(_) if count == self.2 => return (input.restore(save), Err(TooMany)),
+                Ok(x) => c.put(x),
+                Err(_) => break,
+            }
+        }
+
+        (input, Ok(c))
+    }
+
+    impl_parse_box! { Input }
+}
+
+impl<Input: Restore, P, F, C> ParserMut<Input> for AtMost<P, F>
+where
+    P: ParserMut<Input>,
+    F: FnMut() -> C,
+    C: Collection<P::Output>,
+{
+    #[inline]
+    fn parse_mut(&mut self, mut input: Input) -> ParseResult<Input, Self> {
+        let mut c = (self.1)();
+        let save = input.save();
+
+        for count in 0.. {
+            let (next, out) = self.0.parse_mut(input);
+            input = next;
+
+            match out {
+                Ok(_) if count == self.2 => return (input.restore(save), Err(TooMany)),
+                Ok(x) => c.put(x),
+                Err(_) => break,
+            }
+        }
+
+        (input, Ok(c))
+    }
+}
+
+impl<Input: Restore, P, F, C> Parser<Input> for AtMost<P, F>
+where
+    P: Parser<Input>,
+    F: Fn() -> C,
+    C: Collection<P::Output>,
+{
+    #[inline]
+    fn parse(&self, mut input: Input) -> ParseResult<Input, Self> {
+        let mut c = (self.1)();
+        let save = input.save();
+
+        for count in 0.. {
+            let (next, out) = self.0.parse(input);
+            input = next;
+
+            match out {
+                Ok(_) if count == self.2 => return (input.restore(save), Err(TooMany)),
+                Ok(x) => c.put(x),
+                Err(_) => break,
+            }
+        }
+
+        (input, Ok(c))
+    }
+}
@@ -83,6 +83,78 @@ where
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Or<P, Q>(pub(crate) P, pub(crate) Q);
 
+/// Tries `first`, and then `second` if that failed, which is what `Or` does when it can't
+/// try both at once
+#[inline]
+fn or_once<Input: Restore, P, Q>(first: P, second: Q, input: Input) -> ParseResult<Input, Or<P, Q>>
+where
+    P: ParserOnce<Input>,
+    Q: ParserOnce<Input>,
+{
+    let save = input.save();
+    let (input, out_0) = first.parse_once(input);
+
+    match out_0 {
+        Ok(x) => (input, Ok(Either::Left(x))),
+        Err(out_0) => {
+            let (input, out_1) = second.parse_once(input.restore(save));
+
+            match out_1 {
+                Ok(x) => (input, Ok(Either::Right(x))),
+                Err(out_1) => (input.restore(save), Err((out_0, out_1))),
+            }
+        }
+    }
+}
+
+#[inline]
+fn or_mut<Input: Restore, P, Q>(
+    first: &mut P,
+    second: &mut Q,
+    input: Input,
+) -> ParseResult<Input, Or<P, Q>>
+where
+    P: ParserMut<Input>,
+    Q: ParserMut<Input>,
+{
+    let save = input.save();
+    let (input, out_0) = first.parse_mut(input);
+
+    match out_0 {
+        Ok(x) => (input, Ok(Either::Left(x))),
+        Err(out_0) => {
+            let (input, out_1) = second.parse_mut(input.restore(save));
+
+            match out_1 {
+                Ok(x) => (input, Ok(Either::Right(x))),
+                Err(out_1) => (input.restore(save), Err((out_0, out_1))),
+            }
+        }
+    }
+}
+
+#[inline]
+fn or<Input: Restore, P, Q>(first: &P, second: &Q, input: Input) -> ParseResult<Input, Or<P, Q>>
+where
+    P: Parser<Input>,
+    Q: Parser<Input>,
+{
+    let save = input.save();
+    let (input, out_0) = first.parse(input);
+
+    match out_0 {
+        Ok(x) => (input, Ok(Either::Left(x))),
+        Err(out_0) => {
+            let (input, out_1) = second.parse(input.restore(save));
+
+            match out_1 {
+                Ok(x) => (input, Ok(Either::Right(x))),
+                Err(out_1) => (input.restore(save), Err((out_0, out_1))),
+            }
+        }
+    }
+}
+
 impl<Input: Restore, P, Q> ParserOnce<Input> for Or<P, Q>
 where
     P: ParserOnce<Input>,
@@ -93,20 +165,7 @@ where
 
     #[inline]
     default fn parse_once(self, input: Input) -> ParseResult<Input, Self> {
-        let save = input.save();
-        let (input, out_0) = self.0.parse_once(input);
-
-        match out_0 {
-            Ok(x) => (input, Ok(Either::Left(x))),
-            Err(out_0) => {
-                let (input, out_1) = self.1.parse_once(input.restore(save));
-
-                match out_1 {
-                    Ok(x) => (input, Ok(Either::Right(x))),
-                    Err(out_1) => (input.restore(save), Err((out_0, out_1))),
-                }
-            }
-        }
+        or_once(self.0, self.1, input)
     }
 
     impl_parse_box! { Input }
@@ -119,20 +178,7 @@ where
 {
     #[inline]
     default fn parse_mut(&mut self, input: Input) -> ParseResult<Input, Self> {
-        let save = input.save();
-        let (input, out_0) = self.0.parse_mut(input);
-
-        match out_0 {
-            Ok(x) => (input, Ok(Either::Left(x))),
-            Err(out_0) => {
-                let (input, out_1) = self.1.parse_mut(input.restore(save));
-
-                match out_1 {
-                    Ok(x) => (input, Ok(Either::Right(x))),
-                    Err(out_1) => (input.restore(save), Err((out_0, out_1))),
-                }
-            }
-        }
+        or_mut(&mut self.0, &mut self.1, input)
     }
 }
 
@@ -143,20 +189,7 @@ where
 {
     #[inline]
     default fn parse(&self, input: Input) -> ParseResult<Input, Self> {
-        let save = input.save();
-        let (input, out_0) = self.0.parse(input);
-
-        match out_0 {
-            Ok(x) => (input, Ok(Either::Left(x))),
-            Err(out_0) => {
-                let (input, out_1) = self.1.parse(input.restore(save));
-
-                match out_1 {
-                    Ok(x) => (input, Ok(Either::Right(x))),
-                    Err(out_1) => (input.restore(save), Err((out_0, out_1))),
-                }
-            }
-        }
+        or(&self.0, &self.1, input)
     }
 }
 
@@ -172,6 +205,10 @@ where
 {
     #[inline]
     fn parse_once(self, input: Input) -> ParseResult<Input, Self> {
+        if trace::recording() {
+            return or_once(self.0, self.1, input);
+        }
+
         let (save, input_0, input_1) = (input.save(), input.clone(), input);
         let Or(first, second) = self;
 
@@ -198,6 +235,10 @@ where
 {
     #[inline]
     fn parse_mut(&mut self, input: Input) -> ParseResult<Input, Self> {
+        if trace::recording() {
+            return or_mut(&mut self.0, &mut self.1, input);
+        }
+
         let (save, input_0, input_1) = (input.save(), input.clone(), input);
         let Or(first, second) = self;
 
@@ -224,6 +265,10 @@ where
 {
     #[inline]
     fn parse(&self, input: Input) -> ParseResult<Input, Self> {
+        if trace::recording() {
+            return or(&self.0, &self.1, input);
+        }
+
         let (save, input_0, input_1) = (input.save(), input.clone(), input);
         let Or(first, second) = self;
 
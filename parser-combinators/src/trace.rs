@@ -0,0 +1,276 @@
+//! Records which named parsers ran, where, and how far they got, to find out why a grammar
+//! failed
+//!
+//! Parsers are named with `trace`, and while `record` runs, each of them adds an `Event` when
+//! it starts and when it ends. Without the `trace` feature, `trace` doesn't do anything and
+//! `record` always gives back an empty `Trace`.
+
+use super::*;
+
+use std::fmt;
+
+/// A parser that adds events to the trace being recorded, if there is one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Traced<P>(pub(crate) P, pub(crate) &'static str);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EventKind {
+    /// The parser started
+    Enter,
+    /// The parser failed after the parsers inside of it had matched `discarded` more input,
+    /// which was given back
+    Backtrack { discarded: usize },
+    /// The parser ended
+    Exit { consumed: usize, success: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Event {
+    pub name: &'static str,
+    /// How many traced parsers this one is inside of
+    pub depth: usize,
+    /// Where the parser started, from the start of the input given to `record`
+    pub offset: usize,
+    pub kind: EventKind,
+}
+
+/// The events of one call to `record`, which displays as an indented log
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Trace {
+    pub events: Vec<Event>,
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for event in &self.events {
+            let indent = event.depth * 2;
+            let (name, offset) = (event.name, event.offset);
+
+            match event.kind {
+                EventKind::Enter => writeln!(f, "{:indent$}> {} at {}", "", name, offset)?,
+                EventKind::Backtrack { discarded } => writeln!(
+                    f,
+                    "{:indent$}! {} at {} gave back {}",
+                    "", name, offset, discarded
+                )?,
+                EventKind::Exit {
+                    consumed,
+                    success: true,
+                } => writeln!(
+                    f,
+                    "{:indent$}< {} at {} matched {}",
+                    "", name, offset, consumed
+                )?,
+                EventKind::Exit { success: false, .. } => {
+                    writeln!(f, "{:indent$}< {} at {} failed", "", name, offset)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs `f` on `input`, recording the events of the traced parsers that it runs on this thread
+///
+/// While recording, `or` tries its branches one after the other on this thread, instead of at
+/// the same time, so that all of them are recorded in order. Calls to `record` can't be
+/// nested, the inner one gives back an empty `Trace`.
+pub fn record<Input: InputLen, R>(input: Input, f: impl FnOnce(Input) -> R) -> (R, Trace) {
+    recorder::record(input, f)
+}
+
+/// Whether a trace is being recorded on this thread
+pub(crate) fn recording() -> bool {
+    recorder::recording()
+}
+
+#[cfg(feature = "trace")]
+mod recorder {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    struct Frame {
+        name: &'static str,
+        offset: usize,
+        /// The furthest that this parser or any inside of it got
+        furthest: usize,
+    }
+
+    struct Recorder {
+        len: usize,
+        stack: Vec<Frame>,
+        events: Vec<Event>,
+    }
+
+    thread_local! {
+        static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+    }
+
+    pub fn recording() -> bool {
+        RECORDER.with(|recorder| recorder.borrow().is_some())
+    }
+
+    pub fn record<Input: InputLen, R>(input: Input, f: impl FnOnce(Input) -> R) -> (R, Trace) {
+        let len = input.input_len();
+        let started = RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            let started = recorder.is_none();
+
+            if started {
+                *recorder = Some(Recorder {
+                    len,
+                    stack: Vec::new(),
+                    events: Vec::new(),
+                });
+            }
+
+            started
+        });
+
+        // `then_some` would make a `Stop` either way, and dropping it would end the recording
+        let stop = started.then(|| Stop);
+        let out = f(input);
+
+        let events = match stop {
+            Some(stop) => stop
+                .take()
+                .map_or_else(Vec::new, |recorder| recorder.events),
+            None => Vec::new(),
+        };
+
+        (out, Trace { events })
+    }
+
+    /// Ends the recording that `record` started, which happens when it is dropped if the
+    /// parser panics, so that the thread doesn't go on recording into a trace nobody has
+    struct Stop;
+
+    impl Stop {
+        fn take(self) -> Option<Recorder> {
+            RECORDER.with(|recorder| recorder.borrow_mut().take())
+        }
+    }
+
+    impl Drop for Stop {
+        fn drop(&mut self) {
+            RECORDER.with(|recorder| recorder.borrow_mut().take());
+        }
+    }
+
+    pub fn enter(name: &'static str, remaining: usize) {
+        RECORDER.with(|recorder| {
+            if let Some(recorder) = recorder.borrow_mut().as_mut() {
+                let offset = recorder.len.saturating_sub(remaining);
+
+                recorder.events.push(Event {
+                    name,
+                    depth: recorder.stack.len(),
+                    offset,
+                    kind: EventKind::Enter,
+                });
+                recorder.stack.push(Frame {
+                    name,
+                    offset,
+                    furthest: offset,
+                });
+            }
+        })
+    }
+
+    pub fn exit(remaining: usize, success: bool) {
+        RECORDER.with(|recorder| {
+            if let Some(recorder) = recorder.borrow_mut().as_mut() {
+                let frame = match recorder.stack.pop() {
+                    Some(frame) => frame,
+                    None => return,
+                };
+
+                let end = recorder.len.saturating_sub(remaining);
+                let furthest = frame.furthest.max(end);
+                let depth = recorder.stack.len();
+
+                if !success && furthest > end {
+                    recorder.events.push(Event {
+                        name: frame.name,
+                        depth,
+                        offset: frame.offset,
+                        kind: EventKind::Backtrack {
+                            discarded: furthest - end,
+                        },
+                    });
+                }
+
+                recorder.events.push(Event {
+                    name: frame.name,
+                    depth,
+                    offset: frame.offset,
+                    kind: EventKind::Exit {
+                        consumed: end.saturating_sub(frame.offset),
+                        success,
+                    },
+                });
+
+                if let Some(parent) = recorder.stack.last_mut() {
+                    parent.furthest = parent.furthest.max(furthest);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+mod recorder {
+    use super::*;
+
+    pub fn record<Input: InputLen, R>(input: Input, f: impl FnOnce(Input) -> R) -> (R, Trace) {
+        (f(input), Trace::default())
+    }
+
+    #[inline(always)]
+    pub fn recording() -> bool {
+        false
+    }
+
+    #[inline(always)]
+    pub fn enter(_: &'static str, _: usize) {}
+
+    #[inline(always)]
+    pub fn exit(_: usize, _: bool) {}
+}
+
+impl<Input: InputLen, P: ParserOnce<Input>> ParserOnce<Input> for Traced<P> {
+    type Output = P::Output;
+    type Error = P::Error;
+
+    #[inline]
+    fn parse_once(self, input: Input) -> ParseResult<Input, Self> {
+        recorder::enter(self.1, input.input_len());
+        let (input, out) = self.0.parse_once(input);
+        recorder::exit(input.input_len(), out.is_ok());
+        (input, out)
+    }
+
+    impl_parse_box! { Input }
+}
+
+impl<Input: InputLen, P: ParserMut<Input>> ParserMut<Input> for Traced<P> {
+    #[inline]
+    fn parse_mut(&mut self, input: Input) -> ParseResult<Input, Self> {
+        recorder::enter(self.1, input.input_len());
+        let (input, out) = self.0.parse_mut(input);
+        recorder::exit(input.input_len(), out.is_ok());
+        (input, out)
+    }
+}
+
+impl<Input: InputLen, P: Parser<Input>> Parser<Input> for Traced<P> {
+    #[inline]
+    fn parse(&self, input: Input) -> ParseResult<Input, Self> {
+        recorder::enter(self.1, input.input_len());
+        let (input, out) = self.0.parse(input);
+        recorder::exit(input.input_len(), out.is_ok());
+        (input, out)
+    }
+}
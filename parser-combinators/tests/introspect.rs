@@ -0,0 +1,157 @@
+use parser_combinators::introspect::{DescribedParser, Grammar, Node};
+use parser_combinators::prelude::util::{count, literal};
+use parser_combinators::prelude::*;
+
+fn ebnf(parser: &impl parser_combinators::introspect::Describe) -> String {
+    Grammar::new(parser).to_ebnf()
+}
+
+/// `(` and `)` around any number of themselves, named so that it can refer to itself
+fn nested<'a>() -> Box<dyn DescribedParser<&'a str, Output = (), Error = ()> + 'a> {
+    Box::new(
+        literal("(")
+            .then(defer(nested).optional())
+            .then(literal(")"))
+            .map_both(drop, drop)
+            .trace("nested"),
+    )
+}
+
+/// The same as `nested`, without the name
+fn unnamed<'a>() -> Box<dyn DescribedParser<&'a str, Output = (), Error = ()> + 'a> {
+    Box::new(
+        literal("(")
+            .then(defer(unnamed).optional())
+            .then(literal(")"))
+            .map_both(drop, drop),
+    )
+}
+
+#[test]
+fn combinators_are_written_as_ebnf() {
+    let parser = literal("a")
+        .then(literal("b").or(literal("c")).optional())
+        .then(literal("d").zero_or_more(count));
+    assert_eq!(
+        ebnf(&parser),
+        "grammar = \"a\", [ \"b\" | \"c\" ], { \"d\" } ;\n"
+    );
+
+    // choices inside of sequences need parentheses, and nested ones are flattened
+    let parser = literal("a")
+        .or(literal("b"))
+        .or(literal("c"))
+        .then(literal("d"));
+    assert_eq!(
+        ebnf(&parser),
+        "grammar = ( \"a\" | \"b\" | \"c\" ), \"d\" ;\n"
+    );
+
+    assert_eq!(ebnf(&literal("say \"hi\"")), "grammar = 'say \"hi\"' ;\n");
+}
+
+#[test]
+fn repetition_is_written_with_its_bounds() {
+    let x = || literal("x");
+
+    assert_eq!(
+        ebnf(&x().one_or_more(count)),
+        "grammar = \"x\", { \"x\" } ;\n"
+    );
+    assert_eq!(
+        ebnf(&x().repeat(2..=4, count)),
+        "grammar = 2 * \"x\", 2 * [ \"x\" ] ;\n"
+    );
+    assert_eq!(
+        ebnf(&x().repeat(3.., count)),
+        "grammar = 3 * \"x\", { \"x\" } ;\n"
+    );
+    assert_eq!(ebnf(&x().repeat(..2, count)), "grammar = [ \"x\" ] ;\n");
+    assert_eq!(ebnf(&x().repeat(1..=1, count)), "grammar = \"x\" ;\n");
+    assert_eq!(ebnf(&x().at_most(3, count)), "grammar = 3 * [ \"x\" ] ;\n");
+}
+
+#[test]
+fn closures_are_special_sequences() {
+    // parsers that only change the output match the same as the parser inside of them
+    let filtered = literal("a").map(|()| 1).filter(|&n: &i32| n > 0);
+    assert_eq!(ebnf(&filtered), "grammar = \"a\" ;\n");
+
+    let chained = literal("a").and_then(|()| literal("b"));
+    assert_eq!(ebnf(&chained), "grammar = \"a\", ? custom parser ? ;\n");
+
+    // a closure that makes up all of a named parser is shown with the name
+    let digit = (|s: &mut &str| {
+        s.strip_prefix(|c: char| c.is_ascii_digit())
+            .map(|rest| *s = rest)
+            .ok_or(())
+    })
+    .as_parser_in_place()
+    .trace("digit");
+    assert_eq!(
+        ebnf(&literal("#").then(digit)),
+        "grammar = \"#\", digit ;\ndigit = ? digit ? ;\n"
+    );
+}
+
+#[test]
+fn named_parsers_are_rules() {
+    let grammar = Grammar::new(&nested());
+
+    assert_eq!(grammar.rules().len(), 1);
+    assert_eq!(grammar.rules()[0].name, "nested");
+    assert_eq!(
+        grammar.rules()[0].node,
+        Node::Sequence(vec![
+            Node::Literal("(".to_string()),
+            Node::Optional(Box::new(Node::Rule("nested"))),
+            Node::Literal(")".to_string()),
+        ])
+    );
+    assert_eq!(grammar.to_ebnf(), "nested = \"(\", [ nested ], \")\" ;\n");
+
+    // each rule is only described the first time that it comes up
+    let twice = literal("<").then(nested()).then(nested()).trace("pair");
+    assert_eq!(
+        ebnf(&twice),
+        "pair = \"<\", nested, nested ;\nnested = \"(\", [ nested ], \")\" ;\n"
+    );
+}
+
+#[test]
+fn unnamed_recursion_stops_at_the_depth_limit() {
+    let ebnf = ebnf(&unnamed());
+
+    // the outermost one, and then one for each `defer` that was looked into
+    assert_eq!(
+        ebnf.matches("\"(\"").count(),
+        1 + parser_combinators::introspect::MAX_DEFER_DEPTH
+    );
+    assert!(ebnf.contains("? too deeply nested ?"));
+    assert_eq!(nested().parse("(())"), ("", Ok(())));
+}
+
+#[test]
+fn rules_are_drawn_as_railroad_diagrams() {
+    let parser = literal("<")
+        .then(nested())
+        .then(literal("x").repeat(2..=4, count))
+        .then(literal("a").and_then(|()| literal("b")))
+        .trace("example");
+    let svg = Grammar::new(&parser).to_svg();
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    // a diagram for each rule, under its name
+    assert!(svg.contains(">example</text>"));
+    assert!(svg.contains(">nested</text>"));
+
+    assert!(svg.contains("<rect class=\"literal\""));
+    assert!(svg.contains("<rect class=\"rule\""));
+    assert!(svg.contains("<rect class=\"special\""));
+    assert!(svg.contains(">2..=4</text>"));
+    // text is escaped
+    assert!(svg.contains(">&lt;</text>"));
+    assert!(!svg.contains("><</text>"));
+}
@@ -0,0 +1,87 @@
+use parser_combinators::limit::{Depth, DepthExceeded};
+use parser_combinators::prelude::util::{literal, Either};
+use parser_combinators::prelude::*;
+use parser_combinators::repeat::TooMany;
+
+type Nested = Box<dyn for<'a> Parser<&'a str, Output = usize, Error = ()> + Send + Sync>;
+
+/// Balanced parentheses, giving how deeply they nest
+fn parens(depth: Depth) -> Nested {
+    Box::new(defer(move || {
+        literal("(")
+            .then(parens(depth.deeper()).optional())
+            .then(literal(")"))
+            .map(|((_, inner), _): (((), Result<usize, ()>), ())| inner.map_or(1, |n| n + 1))
+            .depth_limit(depth)
+            .map_err(drop)
+    }))
+}
+
+fn nested(depth: usize) -> String {
+    format!("{}{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+/// Parses `doc` as parentheses nested at most `max` deep
+fn parse_parens(doc: &str, max: usize) -> Result<usize, ()> {
+    let (rest, out) = parens(Depth::limit(max).deeper()).parse(doc);
+    out.and_then(|n| if rest.is_empty() { Ok(n) } else { Err(()) })
+}
+
+#[test]
+fn depth() {
+    let depth = Depth::limit(2);
+
+    assert_eq!((depth.current(), depth.max()), (0, 2));
+    assert!(!depth.deeper().deeper().is_exceeded());
+    assert!(depth.deeper().deeper().deeper().is_exceeded());
+}
+
+#[test]
+fn depth_limit() {
+    assert_eq!(parse_parens(&nested(3), 3), Ok(3));
+    assert_eq!(parse_parens(&nested(4), 3), Err(()));
+    assert_eq!(parse_parens("()", 0), Err(()));
+
+    // the parser isn't run at all once the limit is passed
+    let (rest, out) = literal("x")
+        .depth_limit(Depth::limit(0).deeper())
+        .parse("x");
+    assert_eq!(rest, "x");
+    assert!(matches!(out, Err(Either::Left(DepthExceeded))));
+}
+
+#[test]
+fn deep_nesting_fits_on_a_small_stack() {
+    const DEPTH: usize = 10_000;
+
+    // far deeper than a single stack of this size could hold, as every level that gets close
+    // to the end of the stack carries on in a new segment
+    let found = std::thread::Builder::new()
+        .stack_size(512 << 10)
+        .spawn(|| parse_parens(&nested(DEPTH), DEPTH))
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(found, Ok(DEPTH));
+}
+
+#[test]
+fn at_most() {
+    let xs = || literal("x").at_most(2, Vec::new);
+
+    assert_eq!(xs().parse("y"), ("y", Ok(vec![])));
+    assert_eq!(xs().parse("xxy"), ("y", Ok(vec![(); 2])));
+    // one more than allowed fails, leaving the input as it was
+    assert_eq!(xs().parse("xxxy"), ("xxxy", Err(TooMany)));
+    assert_eq!(
+        literal("x").at_most(0, Vec::new).parse("x"),
+        ("x", Err(TooMany))
+    );
+
+    // where `zero_or_more` takes as many as there are
+    assert_eq!(
+        literal("x").zero_or_more(Vec::new).parse("xxxy"),
+        ("y", Ok(vec![(); 3]))
+    );
+}
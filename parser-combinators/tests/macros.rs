@@ -0,0 +1,79 @@
+use parser_combinators::prelude::util::{literal, Either, LiteralError};
+use parser_combinators::prelude::*;
+
+#[test]
+fn seq_gives_back_the_named_items() {
+    let one = seq!("(", n = parsed::<u32>(), ")");
+    assert_eq!(one.parse("(1)"), ("", Ok(1)));
+
+    let two = seq!(a = parsed::<u32>(), _ = literal(","), b = parsed::<u32>());
+    assert_eq!(two.parse("1, 2 3"), (" 3", Ok((1, 2))));
+
+    let none = seq!("a", _ = parsed::<u32>(), "b");
+    assert_eq!(none.parse("a 1b"), ("", Ok(())));
+}
+
+#[test]
+fn seq_builds_its_result_with_the_names_in_scope() {
+    let sum = seq!(a = parsed::<u32>(), "+", b = parsed::<u32>() => a + b);
+    assert_eq!(sum.parse("1+ 2"), ("", Ok(3)));
+
+    // the body can end on any kind of item
+    let last_literal = seq!(a = parsed::<u32>(), ";" => a * 2);
+    assert_eq!(last_literal.parse("4;"), ("", Ok(8)));
+    let last_ignored = seq!(a = parsed::<u32>(), _ = literal(";") => a * 3);
+    assert_eq!(last_ignored.parse("4;"), ("", Ok(12)));
+}
+
+#[test]
+fn seq_errors_nest_like_then() {
+    let pair = seq!("(", a = parsed::<u32>(), ",", b = parsed::<u32>(), ")");
+
+    assert!(matches!(
+        pair.parse("[1, 2)").1,
+        Err(Either::Left(Either::Left(Either::Left(Either::Left(
+            LiteralError("(")
+        )))))
+    ));
+    assert!(matches!(
+        pair.parse("(1; 2)").1,
+        Err(Either::Left(Either::Left(Either::Right(LiteralError(",")))))
+    ));
+    assert!(matches!(
+        pair.parse("(1, 2]").1,
+        Err(Either::Right(LiteralError(")")))
+    ));
+}
+
+#[test]
+fn alt_takes_the_first_branch_that_parses() {
+    let bit = alt!(literal("0") => |()| false, literal("1") => |()| true);
+    assert_eq!(bit.parse("10"), ("0", Ok(true)));
+    assert_eq!(bit.parse("01"), ("1", Ok(false)));
+
+    // a branch without `=>` gives back its output as it is
+    let number = alt!(parsed::<u32>(), literal("none") => |()| 0_u32);
+    assert_eq!(number.parse("12"), ("", Ok(12_u32)));
+    assert_eq!(number.parse("none"), ("", Ok(0_u32)));
+
+    // a branch that fails part way gives its input back to the next one
+    let longest = alt!(
+        seq!(a = parsed::<u32>(), "." , b = parsed::<u32>() => a * 10 + b),
+        parsed::<u32>()
+    );
+    assert_eq!(longest.parse("1.5"), ("", Ok(15_u32)));
+    assert_eq!(longest.parse("1,5"), (",5", Ok(1_u32)));
+}
+
+#[test]
+fn alt_errors_nest_like_or() {
+    let abc = alt!(literal("a"), literal("b"), literal("c"));
+
+    assert!(matches!(
+        abc.parse("d"),
+        (
+            "d",
+            Err(((LiteralError("a"), LiteralError("b")), LiteralError("c")))
+        )
+    ));
+}
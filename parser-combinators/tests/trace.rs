@@ -0,0 +1,123 @@
+use parser_combinators::prelude::util::literal;
+use parser_combinators::prelude::*;
+use parser_combinators::trace::{record, Trace};
+#[cfg(feature = "trace")]
+use parser_combinators::trace::{Event, EventKind};
+
+#[cfg(feature = "trace")]
+fn event(name: &'static str, depth: usize, offset: usize, kind: EventKind) -> Event {
+    Event {
+        name,
+        depth,
+        offset,
+        kind,
+    }
+}
+
+#[cfg(feature = "trace")]
+fn enter(name: &'static str, depth: usize, offset: usize) -> Event {
+    event(name, depth, offset, EventKind::Enter)
+}
+
+#[cfg(feature = "trace")]
+fn exit(name: &'static str, depth: usize, offset: usize, consumed: usize, success: bool) -> Event {
+    event(name, depth, offset, EventKind::Exit { consumed, success })
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn nested_parsers_are_recorded_in_order() {
+    let pair = literal("a")
+        .trace("a")
+        .then(literal("b").trace("b"))
+        .trace("pair");
+    let ((rest, out), trace) = record("xab", |input| pair.parse(&input[1..]));
+
+    assert_eq!((rest, out.is_ok()), ("", true));
+    // offsets count from the input given to `record`
+    assert_eq!(
+        trace.events,
+        [
+            enter("pair", 0, 1),
+            enter("a", 1, 1),
+            exit("a", 1, 1, 1, true),
+            enter("b", 1, 2),
+            exit("b", 1, 2, 1, true),
+            exit("pair", 0, 1, 2, true),
+        ]
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn backtracking_is_recorded() {
+    let pair = literal("a")
+        .trace("a")
+        .then(literal("b").trace("b"))
+        .trace("pair");
+    let either = pair.or(literal("a").trace("single"));
+    let ((rest, out), trace) = record("ac", |input| either.parse(input));
+
+    assert_eq!((rest, out.is_ok()), ("c", true));
+    // both branches are recorded, one after the other
+    assert_eq!(
+        trace.events,
+        [
+            enter("pair", 0, 0),
+            enter("a", 1, 0),
+            exit("a", 1, 0, 1, true),
+            enter("b", 1, 1),
+            exit("b", 1, 1, 0, false),
+            event("pair", 0, 0, EventKind::Backtrack { discarded: 1 }),
+            exit("pair", 0, 0, 0, false),
+            enter("single", 0, 0),
+            exit("single", 0, 0, 1, true),
+        ]
+    );
+    assert_eq!(
+        trace.to_string(),
+        "> pair at 0\n  > a at 0\n  < a at 0 matched 1\n  > b at 1\n  < b at 1 failed\n\
+         ! pair at 0 gave back 1\n< pair at 0 failed\n> single at 0\n< single at 0 matched 1\n"
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn only_the_outer_record_gets_the_events() {
+    let a = literal("a").trace("a");
+    let ((_, inner), outer) = record("a", |input| record(input, |input| a.parse(input)));
+
+    assert_eq!(inner, Trace::default());
+    assert_eq!(outer.events.len(), 2);
+
+    // nothing is recorded outside of `record`
+    assert_eq!(record("", |_| ()).1, Trace::default());
+}
+
+#[cfg(not(feature = "trace"))]
+#[test]
+fn nothing_is_recorded_without_the_feature() {
+    let a = literal("a").trace("a");
+    let ((rest, out), trace) = record("ab", |input| a.parse(input));
+
+    assert_eq!((rest, out.is_ok()), ("b", true));
+    assert_eq!(trace, Trace::default());
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn a_panic_ends_the_recording() {
+    let a = literal("a").trace("a");
+    let panicked = std::panic::catch_unwind(|| {
+        record("a", |input| {
+            let _ = a.parse(input);
+            panic!("in the parser");
+        })
+    });
+
+    assert!(panicked.is_err());
+    // a new recording starts from nothing, rather than being taken for a nested one
+    let ((_, out), trace) = record("a", |input| a.parse(input));
+    assert!(out.is_ok());
+    assert_eq!(trace.events, [enter("a", 0, 0), exit("a", 0, 0, 1, true)]);
+}
@@ -0,0 +1,5 @@
+use parser_combinators::prelude::*;
+
+fn main() {
+    let _ = seq!();
+}
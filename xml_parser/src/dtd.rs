@@ -0,0 +1,828 @@
+use super::{eat_white_space, is_name_char, is_white_space, keyword, literal, match_literal};
+use super::{name, token};
+use super::{Document, Element, QName};
+
+use parser_combinators::limit::Depth;
+use parser_combinators::prelude::*;
+
+use either::Either;
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt;
+
+/// How deeply groups may be nested in a content model
+const MAX_NESTING: usize = 32;
+
+/// The element and attribute declarations of a DTD
+///
+/// Names are compared as they are written, prefixes and all, since DTDs don't know
+/// about namespaces. Entity and notation declarations are skipped, as are parameter
+/// entities, so a DTD that relies on them to declare its elements won't be complete.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Dtd {
+    elements: HashMap<String, ContentSpec>,
+    attributes: HashMap<String, Vec<AttributeDef>>,
+}
+
+/// A declaration of a DTD that couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DtdError {
+    declaration: String,
+}
+
+impl DtdError {
+    pub fn declaration(&self) -> &str {
+        &self.declaration
+    }
+}
+
+impl fmt::Display for DtdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid declaration `{}`", self.declaration)
+    }
+}
+
+impl Error for DtdError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ContentSpec {
+    Empty,
+    Any,
+    /// `(#PCDATA | a | b)*`, with the names of the elements that can be mixed in with text
+    Mixed(Vec<String>),
+    Children(Particle),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Particle {
+    kind: ParticleKind,
+    repeat: Repeat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ParticleKind {
+    Name(String),
+    Sequence(Vec<Particle>),
+    Choice(Vec<Particle>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    Once,
+    Optional,
+    ZeroOrMore,
+    OneOrMore,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttributeDef {
+    name: String,
+    kind: AttributeKind,
+    default: DefaultDecl,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AttributeKind {
+    CData,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    NmToken,
+    NmTokens,
+    /// A choice of names, either given as they are or as a `NOTATION`
+    Enumeration(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DefaultDecl {
+    Required,
+    Implied,
+    Fixed(String),
+    Default(String),
+}
+
+fn repeat() -> impl for<'a> Parser<&'a str, Output = Repeat, Error = Infallible> + Copy {
+    match_literal("?")
+        .map(|()| Repeat::Optional)
+        .or(match_literal("*").map(|()| Repeat::ZeroOrMore))
+        .map(Either::into_inner)
+        .or(match_literal("+").map(|()| Repeat::OneOrMore))
+        .map(Either::into_inner)
+        .optional()
+        .map(|repeat: Result<Repeat, _>| repeat.unwrap_or(Repeat::Once))
+}
+
+/// `,` for a sequence and `|` for a choice
+fn separator() -> impl for<'a> Parser<&'a str, Output = bool, Error = ()> {
+    token(",")
+        .map(|()| true)
+        .or(token("|").map(|()| false))
+        .map_both(Either::into_inner, drop)
+}
+
+/// A group of particles in parentheses, which are either all separated by `,` or all by `|`
+fn group(depth: Depth) -> impl for<'a> Parser<&'a str, Output = ParticleKind, Error = ()> {
+    token("(")
+        .then(particle(depth.deeper()))
+        .map(util::snd)
+        .then(
+            separator()
+                .then(particle(depth.deeper()))
+                .zero_or_more(Vec::new),
+        )
+        .map_err(drop)
+        .then(token(")"))
+        .map_both(util::fst, drop)
+        .flat_map(|(first, rest): (Particle, Vec<(bool, Particle)>)| {
+            let sequence = rest.first().is_none_or(|&(sequence, _)| sequence);
+            if rest.iter().any(|&(separator, _)| separator != sequence) {
+                return Err(());
+            }
+
+            let mut particles = vec![first];
+            particles.extend(rest.into_iter().map(util::snd));
+
+            Ok(if sequence {
+                ParticleKind::Sequence(particles)
+            } else {
+                ParticleKind::Choice(particles)
+            })
+        })
+}
+
+/// The type is spelled out for the same reason as `ElementParser`, since particles
+/// hold groups which hold particles
+type ParticleParser = Box<dyn for<'a> Parser<&'a str, Output = Particle, Error = ()> + Send + Sync>;
+
+/// `depth` counts the groups that this particle is inside of
+fn particle(depth: Depth) -> ParticleParser {
+    Box::new(
+        defer(move || {
+            name()
+                .map(ParticleKind::Name)
+                .or(group(depth))
+                .map_both(Either::into_inner, drop)
+                .then(repeat())
+                .map_both(
+                    |(kind, repeat): (ParticleKind, Repeat)| Particle { kind, repeat },
+                    util::unwrap_left,
+                )
+        })
+        .depth_limit(depth)
+        .map_err(drop),
+    )
+}
+
+/// `(#PCDATA)`, or `(#PCDATA | a | b)*`
+fn mixed() -> impl for<'a> Parser<&'a str, Output = ContentSpec, Error = ()> {
+    token("(")
+        .then(token("#PCDATA"))
+        .map_err(Either::into_inner)
+        .then(
+            token("|")
+                .then(name())
+                .map(util::snd)
+                .zero_or_more(Vec::new),
+        )
+        .map_both(util::snd, util::unwrap_left)
+        .then(token(")"))
+        .map_both(util::fst, Either::into_inner)
+        .then(match_literal("*").optional())
+        .map_both(
+            |(names, star): (Vec<String>, Result<(), _>)| (names, star.is_ok()),
+            util::unwrap_left,
+        )
+        // only `(#PCDATA)` can go without the `*`
+        .flat_map(|(names, star): (Vec<String>, bool)| {
+            if names.is_empty() || star {
+                Ok(ContentSpec::Mixed(names))
+            } else {
+                Err(())
+            }
+        })
+}
+
+fn content_spec() -> impl for<'a> Parser<&'a str, Output = ContentSpec, Error = ()> {
+    keyword("EMPTY")
+        .map(|()| ContentSpec::Empty)
+        .or(keyword("ANY").map(|()| ContentSpec::Any))
+        .map(Either::into_inner)
+        .or(mixed())
+        .map(Either::into_inner)
+        .or(eat_white_space()
+            .then(particle(Depth::limit(MAX_NESTING)))
+            .map_both(util::snd, util::unwrap_right)
+            .filter(|particle: &Particle| !matches!(particle.kind, ParticleKind::Name(_)))
+            .map_both(ContentSpec::Children, drop))
+        .map_both(Either::into_inner, drop)
+}
+
+fn element_decl() -> impl for<'a> Parser<&'a str, Output = (String, ContentSpec), Error = ()> {
+    match_literal("<!ELEMENT")
+        .map_err(drop)
+        .then(name())
+        .map_both(util::snd, Either::into_inner)
+        .then(content_spec())
+        .map_err(Either::into_inner)
+        .then(token(">"))
+        .map_both(util::fst, Either::into_inner)
+}
+
+/// A name token, which is made of name characters without the rules of where they can go
+fn name_token() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    (|input: &mut &str| {
+        let rest = input.trim_start_matches(is_white_space);
+        let len = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(());
+        }
+
+        let token = rest[..len].to_string();
+        *input = &rest[len..];
+        Ok(token)
+    })
+    .as_parser_in_place()
+}
+
+/// `(a | b | c)`
+fn enumeration() -> impl for<'a> Parser<&'a str, Output = Vec<String>, Error = ()> {
+    token("(")
+        .then(name_token())
+        .map(util::snd)
+        .then(
+            token("|")
+                .then(name_token())
+                .map(util::snd)
+                .zero_or_more(Vec::new),
+        )
+        .map_err(drop)
+        .then(token(")"))
+        .map_both(
+            |((first, mut rest), ()): ((String, Vec<String>), ())| {
+                rest.insert(0, first);
+                rest
+            },
+            drop,
+        )
+}
+
+fn attribute_kind() -> impl for<'a> Parser<&'a str, Output = AttributeKind, Error = ()> {
+    let kind = |word, kind: AttributeKind| keyword(word).map(move |()| kind.clone());
+
+    kind("CDATA", AttributeKind::CData)
+        .or(kind("ID", AttributeKind::Id))
+        .map(Either::into_inner)
+        .or(kind("IDREF", AttributeKind::IdRef))
+        .map(Either::into_inner)
+        .or(kind("IDREFS", AttributeKind::IdRefs))
+        .map(Either::into_inner)
+        .or(kind("ENTITY", AttributeKind::Entity))
+        .map(Either::into_inner)
+        .or(kind("ENTITIES", AttributeKind::Entities))
+        .map(Either::into_inner)
+        .or(kind("NMTOKEN", AttributeKind::NmToken))
+        .map(Either::into_inner)
+        .or(kind("NMTOKENS", AttributeKind::NmTokens))
+        .map(Either::into_inner)
+        .map_err(drop)
+        .or(keyword("NOTATION")
+            .optional()
+            .then(enumeration())
+            .map(util::snd))
+        .map_both(
+            |kind: Either<AttributeKind, Vec<String>>| match kind {
+                Either::Left(kind) => kind,
+                Either::Right(names) => AttributeKind::Enumeration(names),
+            },
+            drop,
+        )
+}
+
+fn default_decl() -> impl for<'a> Parser<&'a str, Output = DefaultDecl, Error = ()> {
+    token("#REQUIRED")
+        .map(|()| DefaultDecl::Required)
+        .or(token("#IMPLIED").map(|()| DefaultDecl::Implied))
+        .map(Either::into_inner)
+        .or(token("#FIXED")
+            .then(literal())
+            .map_both(|((), value)| DefaultDecl::Fixed(value), Either::into_inner))
+        .map(Either::into_inner)
+        .or(literal().map(DefaultDecl::Default))
+        .map_both(Either::into_inner, drop)
+}
+
+fn attribute_def() -> impl for<'a> Parser<&'a str, Output = AttributeDef, Error = ()> {
+    name()
+        .then(attribute_kind())
+        .map_err(Either::into_inner)
+        .then(default_decl())
+        .map_both(
+            |((name, kind), default)| AttributeDef {
+                name,
+                kind,
+                default,
+            },
+            Either::into_inner,
+        )
+}
+
+fn attlist_decl() -> impl for<'a> Parser<&'a str, Output = (String, Vec<AttributeDef>), Error = ()>
+{
+    match_literal("<!ATTLIST")
+        .map_err(drop)
+        .then(name())
+        .map_both(util::snd, Either::into_inner)
+        .then(attribute_def().zero_or_more(Vec::new))
+        .map_err(util::unwrap_left)
+        .then(token(">"))
+        .map_both(util::fst, Either::into_inner)
+}
+
+/// The length of the markup declaration at the start of `input`, which ends at the first
+/// `>` that isn't quoted
+fn markup_len(input: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+impl std::str::FromStr for Dtd {
+    type Err = DtdError;
+
+    /// Parses the declarations of a DTD, such as the internal subset of a DOCTYPE
+    fn from_str(subset: &str) -> Result<Self, DtdError> {
+        let mut dtd = Dtd::default();
+        let mut rest = subset.trim_start_matches(is_white_space);
+
+        while !rest.is_empty() {
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->").map(|end| end + "-->".len())
+            } else if rest.starts_with("<?") {
+                rest.find("?>").map(|end| end + "?>".len())
+            } else if rest.starts_with('%') {
+                // parameter entity references are skipped, along with what they'd declare
+                rest.find(';').map(|end| end + 1)
+            } else {
+                markup_len(rest)
+            };
+
+            let error = || DtdError {
+                declaration: rest[..end.unwrap_or(rest.len())].to_string(),
+            };
+            let declaration = &rest[..end.ok_or_else(error)?];
+
+            if declaration.starts_with("<!ELEMENT") {
+                match element_decl().parse(declaration) {
+                    ("", Ok((name, spec))) => {
+                        dtd.elements.insert(name, spec);
+                    }
+                    _ => return Err(error()),
+                }
+            } else if declaration.starts_with("<!ATTLIST") {
+                match attlist_decl().parse(declaration) {
+                    ("", Ok((name, defs))) => dtd.attributes.entry(name).or_default().extend(defs),
+                    _ => return Err(error()),
+                }
+            } else if !(declaration.starts_with("<!ENTITY")
+                || declaration.starts_with("<!NOTATION")
+                || declaration.starts_with("<!--")
+                || declaration.starts_with("<?")
+                || declaration.starts_with('%'))
+            {
+                return Err(error());
+            }
+
+            rest = rest[declaration.len()..].trim_start_matches(is_white_space);
+        }
+
+        Ok(dtd)
+    }
+}
+
+impl fmt::Display for ContentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentSpec::Empty => f.write_str("EMPTY"),
+            ContentSpec::Any => f.write_str("ANY"),
+            ContentSpec::Mixed(names) if names.is_empty() => f.write_str("(#PCDATA)"),
+            ContentSpec::Mixed(names) => write!(f, "(#PCDATA | {})*", names.join(" | ")),
+            ContentSpec::Children(particle) => write!(f, "{particle}"),
+        }
+    }
+}
+
+impl fmt::Display for Particle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let group = |f: &mut fmt::Formatter<'_>, particles: &[Particle], separator| {
+            f.write_str("(")?;
+            for (i, particle) in particles.iter().enumerate() {
+                if i != 0 {
+                    f.write_str(separator)?;
+                }
+                write!(f, "{particle}")?;
+            }
+            f.write_str(")")
+        };
+
+        match &self.kind {
+            ParticleKind::Name(name) => f.write_str(name)?,
+            ParticleKind::Sequence(particles) => group(f, particles, ", ")?,
+            ParticleKind::Choice(particles) => group(f, particles, " | ")?,
+        }
+
+        f.write_str(match self.repeat {
+            Repeat::Once => "",
+            Repeat::Optional => "?",
+            Repeat::ZeroOrMore => "*",
+            Repeat::OneOrMore => "+",
+        })
+    }
+}
+
+impl Particle {
+    /// Every position in `names` that matching this particle from `start` can end at
+    fn ends(&self, names: &[&str], start: usize) -> BTreeSet<usize> {
+        let once = |from: usize| match &self.kind {
+            ParticleKind::Name(name) => names
+                .get(from)
+                .filter(|&found| found == name)
+                .map(|_| from + 1)
+                .into_iter()
+                .collect(),
+            ParticleKind::Sequence(particles) => {
+                particles
+                    .iter()
+                    .fold(BTreeSet::from([from]), |ends, particle| {
+                        ends.into_iter()
+                            .flat_map(|end| particle.ends(names, end))
+                            .collect()
+                    })
+            }
+            ParticleKind::Choice(particles) => particles
+                .iter()
+                .flat_map(|particle| particle.ends(names, from))
+                .collect(),
+        };
+
+        match self.repeat {
+            Repeat::Once => once(start),
+            Repeat::Optional => {
+                let mut ends = once(start);
+                ends.insert(start);
+                ends
+            }
+            Repeat::ZeroOrMore | Repeat::OneOrMore => {
+                let mut ends = BTreeSet::new();
+                if self.repeat == Repeat::ZeroOrMore {
+                    ends.insert(start);
+                }
+
+                // keep matching from every new end, until there are no more new ones
+                let mut frontier = once(start);
+                while !frontier.is_empty() {
+                    let new: Vec<_> = frontier
+                        .into_iter()
+                        .filter(|&end| ends.insert(end))
+                        .collect();
+                    frontier = new.into_iter().flat_map(once).collect();
+                }
+
+                ends
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The root element isn't the one named by the DOCTYPE
+    WrongRoot(String),
+    UndeclaredElement,
+    /// The content doesn't match the content model, written as it would be in the DTD
+    InvalidContent(String),
+    UndeclaredAttribute(String),
+    MissingAttribute(String),
+    InvalidAttribute {
+        name: String,
+        value: String,
+    },
+    /// A `#FIXED` attribute with another value
+    FixedAttribute {
+        name: String,
+        expected: String,
+    },
+    DuplicateId(String),
+    /// An IDREF that isn't the ID of any element
+    UnknownId(String),
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationErrorKind::WrongRoot(expected) => {
+                write!(f, "the DOCTYPE expects `{expected}` as the root element")
+            }
+            ValidationErrorKind::UndeclaredElement => f.write_str("element is not declared"),
+            ValidationErrorKind::InvalidContent(model) => {
+                write!(f, "content doesn't match `{model}`")
+            }
+            ValidationErrorKind::UndeclaredAttribute(name) => {
+                write!(f, "attribute `{name}` is not declared")
+            }
+            ValidationErrorKind::MissingAttribute(name) => {
+                write!(f, "required attribute `{name}` is missing")
+            }
+            ValidationErrorKind::InvalidAttribute { name, value } => {
+                write!(f, "`{value}` isn't a valid value for attribute `{name}`")
+            }
+            ValidationErrorKind::FixedAttribute { name, expected } => {
+                write!(f, "attribute `{name}` has to be `{expected}`")
+            }
+            ValidationErrorKind::DuplicateId(id) => write!(f, "ID `{id}` is used more than once"),
+            ValidationErrorKind::UnknownId(id) => write!(f, "no element has the ID `{id}`"),
+        }
+    }
+}
+
+/// A place where a document doesn't follow its DTD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    path: String,
+    kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    /// The path of the offending element, such as `/list/item[2]`, which can be used
+    /// as an `XPath` query
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn kind(&self) -> &ValidationErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.kind)
+    }
+}
+
+impl Error for ValidationError {}
+
+fn is_name(value: &str) -> bool {
+    matches!(name().parse(value), ("", Ok(_)))
+}
+
+fn is_name_token(value: &str) -> bool {
+    matches!(name_token().parse(value), ("", Ok(_)))
+}
+
+struct Validator<'a> {
+    dtd: &'a Dtd,
+    ids: HashSet<&'a str>,
+    /// The IDREFs along with the paths of their elements, which are checked at the end,
+    /// since they can refer to elements further on
+    references: Vec<(String, &'a str)>,
+    errors: Vec<ValidationError>,
+}
+
+impl<'a> Validator<'a> {
+    fn error(&mut self, path: &str, kind: ValidationErrorKind) {
+        self.errors.push(ValidationError {
+            path: path.to_string(),
+            kind,
+        });
+    }
+
+    fn element(&mut self, element: &'a Element, path: &str) {
+        let Element::Node {
+            name,
+            attributes,
+            children,
+        } = element
+        else {
+            return;
+        };
+
+        let name = name.qualified();
+        match self.dtd.elements.get(&name) {
+            Some(spec) => self.content(spec, children, path),
+            None => self.error(path, ValidationErrorKind::UndeclaredElement),
+        }
+        self.attributes(&name, attributes, path);
+
+        let mut seen = HashMap::new();
+        for child in children {
+            if let Element::Node { name, .. } = child {
+                let name = name.qualified();
+                let count = children
+                    .iter()
+                    .filter(|other| matches!(other, Element::Node { name: other, .. } if other.qualified() == name))
+                    .count();
+
+                let index = seen.entry(name.clone()).or_insert(0);
+                *index += 1;
+
+                let path = if count == 1 {
+                    format!("{path}/{name}")
+                } else {
+                    format!("{path}/{name}[{index}]")
+                };
+                self.element(child, &path);
+            }
+        }
+    }
+
+    fn content(&mut self, spec: &ContentSpec, children: &[Element], path: &str) {
+        let names: Vec<_> = children
+            .iter()
+            .filter_map(|child| match child {
+                Element::Node { name, .. } => Some(name.qualified()),
+                _ => None,
+            })
+            .collect();
+        let has_text = children
+            .iter()
+            .any(|child| matches!(child, Element::Text(text) if !text.chars().all(is_white_space)));
+
+        let valid = match spec {
+            ContentSpec::Empty => children.is_empty(),
+            ContentSpec::Any => true,
+            ContentSpec::Mixed(allowed) => names.iter().all(|name| allowed.contains(name)),
+            ContentSpec::Children(particle) => {
+                let names: Vec<_> = names.iter().map(String::as_str).collect();
+                !has_text && particle.ends(&names, 0).contains(&names.len())
+            }
+        };
+
+        if !valid {
+            self.error(path, ValidationErrorKind::InvalidContent(spec.to_string()));
+        }
+    }
+
+    fn attributes(&mut self, element: &str, attributes: &'a [(QName, String)], path: &str) {
+        let defs = self
+            .dtd
+            .attributes
+            .get(element)
+            .map_or(&[][..], Vec::as_slice);
+
+        for (name, value) in attributes {
+            let name = name.qualified();
+            let Some(def) = defs.iter().find(|def| def.name == name) else {
+                self.error(path, ValidationErrorKind::UndeclaredAttribute(name));
+                continue;
+            };
+
+            let tokens = || {
+                value
+                    .split(is_white_space)
+                    .filter(|token| !token.is_empty())
+            };
+            let valid = match &def.kind {
+                AttributeKind::CData => true,
+                AttributeKind::Id => {
+                    if !self.ids.insert(value) {
+                        self.error(path, ValidationErrorKind::DuplicateId(value.clone()));
+                    }
+                    is_name(value)
+                }
+                AttributeKind::IdRef => {
+                    self.references.push((path.to_string(), value));
+                    is_name(value)
+                }
+                AttributeKind::IdRefs => {
+                    for id in tokens() {
+                        self.references.push((path.to_string(), id));
+                    }
+                    tokens().next().is_some() && tokens().all(is_name)
+                }
+                AttributeKind::Entity => is_name(value),
+                AttributeKind::Entities => tokens().next().is_some() && tokens().all(is_name),
+                AttributeKind::NmToken => is_name_token(value),
+                AttributeKind::NmTokens => tokens().next().is_some() && tokens().all(is_name_token),
+                AttributeKind::Enumeration(names) => names.contains(value),
+            };
+
+            if !valid {
+                let value = value.clone();
+                self.error(path, ValidationErrorKind::InvalidAttribute { name, value });
+            } else if let DefaultDecl::Fixed(expected) = &def.default {
+                if value != expected {
+                    let expected = expected.clone();
+                    self.error(path, ValidationErrorKind::FixedAttribute { name, expected });
+                }
+            }
+        }
+
+        for def in defs {
+            let present = attributes
+                .iter()
+                .any(|(name, _)| name.qualified() == def.name);
+
+            if def.default == DefaultDecl::Required && !present {
+                self.error(
+                    path,
+                    ValidationErrorKind::MissingAttribute(def.name.clone()),
+                );
+            }
+        }
+    }
+}
+
+impl Dtd {
+    /// Checks that the document follows the DTD, giving back every place where it doesn't
+    pub fn validate(&self, document: &Document) -> Vec<ValidationError> {
+        let mut validator = Validator {
+            dtd: self,
+            ids: HashSet::new(),
+            references: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        if let Element::Node { name, .. } = &document.root {
+            let path = format!("/{}", name.qualified());
+
+            if let Some(doctype) = &document.doctype {
+                if doctype.name != name.qualified() {
+                    let expected = doctype.name.clone();
+                    validator.error(&path, ValidationErrorKind::WrongRoot(expected));
+                }
+            }
+
+            validator.element(&document.root, &path);
+        }
+
+        let Validator {
+            ids,
+            references,
+            mut errors,
+            ..
+        } = validator;
+
+        for (path, id) in references {
+            if !ids.contains(id) {
+                errors.push(ValidationError {
+                    path,
+                    kind: ValidationErrorKind::UnknownId(id.to_string()),
+                });
+            }
+        }
+
+        errors
+    }
+
+    /// Adds the attributes that the DTD gives a default or fixed value, and that aren't
+    /// already there, to `element` and everything inside of it
+    pub fn apply_defaults(&self, element: &mut Element) {
+        let Element::Node {
+            name,
+            attributes,
+            children,
+        } = element
+        else {
+            return;
+        };
+
+        for def in self.attributes.get(&name.qualified()).into_iter().flatten() {
+            let value = match &def.default {
+                DefaultDecl::Fixed(value) | DefaultDecl::Default(value) => value,
+                DefaultDecl::Required | DefaultDecl::Implied => continue,
+            };
+
+            if !attributes
+                .iter()
+                .any(|(name, _)| name.qualified() == def.name)
+            {
+                attributes.push((QName::new(def.name.clone()), value.clone()));
+            }
+        }
+
+        for child in children {
+            self.apply_defaults(child);
+        }
+    }
+}
+
+impl Document {
+    /// The DTD declared in the internal subset of the DOCTYPE, if there is one
+    pub fn dtd(&self) -> Option<Result<Dtd, DtdError>> {
+        let subset = self.doctype.as_ref()?.internal_subset.as_ref()?;
+        Some(subset.parse())
+    }
+}
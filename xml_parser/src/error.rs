@@ -0,0 +1,153 @@
+use super::{EntityErrorKind, NamespaceError};
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidDeclaration,
+    InvalidDoctype,
+    ExpectedElement,
+    InvalidName,
+    /// A tag that isn't ended by `>` or `/>`, which is also where a malformed
+    /// attribute is found
+    UnclosedTag,
+    /// The element with this name is never closed
+    UnclosedElement(String),
+    /// A closing tag that doesn't match the element with this name
+    WrongCloseTag(String),
+    InvalidProcessingInstruction,
+    UnterminatedCData,
+    Entity(EntityErrorKind),
+    DuplicateAttribute(String),
+    Namespace(NamespaceError),
+    DepthExceeded,
+    TooManyItems,
+    TooLong,
+    TrailingCharacters,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::InvalidDeclaration => f.write_str("invalid XML declaration"),
+            ErrorKind::InvalidDoctype => f.write_str("invalid DOCTYPE"),
+            ErrorKind::ExpectedElement => f.write_str("expected an element"),
+            ErrorKind::InvalidName => f.write_str("invalid name"),
+            ErrorKind::UnclosedTag => f.write_str("expected an attribute, or the end of the tag"),
+            ErrorKind::UnclosedElement(name) => write!(f, "`<{name}>` is never closed"),
+            ErrorKind::WrongCloseTag(name) => write!(f, "expected `</{name}>`"),
+            ErrorKind::InvalidProcessingInstruction => {
+                f.write_str("invalid processing instruction")
+            }
+            ErrorKind::UnterminatedCData => f.write_str("unterminated CDATA section"),
+            ErrorKind::Entity(e) => write!(f, "{e}"),
+            ErrorKind::DuplicateAttribute(name) => write!(f, "attribute `{name}` is repeated"),
+            ErrorKind::Namespace(e) => write!(f, "{e}"),
+            ErrorKind::DepthExceeded => f.write_str("elements are nested too deeply"),
+            ErrorKind::TooManyItems => f.write_str("too many attributes or children in an element"),
+            ErrorKind::TooLong => f.write_str("the document is too long"),
+            ErrorKind::TrailingCharacters => {
+                f.write_str("unexpected characters after the root element")
+            }
+        }
+    }
+}
+
+impl fmt::Display for EntityErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntityErrorKind::Malformed => f.write_str("reference isn't closed by `;`"),
+            EntityErrorKind::Unknown(name) => write!(f, "unknown entity `&{name};`"),
+            EntityErrorKind::InvalidChar(name) => {
+                write!(f, "`&{name};` isn't a character that XML allows")
+            }
+            EntityErrorKind::LessThan => f.write_str("`<` must be written as `&lt;` in attributes"),
+        }
+    }
+}
+
+impl fmt::Display for NamespaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NamespaceError::InvalidName(name) => write!(f, "`{name}` isn't a valid qualified name"),
+            NamespaceError::UndeclaredPrefix(prefix) => {
+                write!(f, "namespace prefix `{prefix}` is not declared")
+            }
+            NamespaceError::EmptyDeclaration(prefix) => {
+                write!(f, "namespace prefix `{prefix}` can't be declared empty")
+            }
+            NamespaceError::DuplicateAttribute(name) => {
+                write!(f, "attribute `{name}` is repeated in the same namespace")
+            }
+        }
+    }
+}
+
+/// Finds the byte offset, line and column of the point in `doc` where `remaining` bytes are left
+fn position(doc: &str, remaining: usize) -> (usize, usize, usize) {
+    let offset = doc.len() - remaining;
+    let before = &doc[..offset];
+
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+        offset,
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// An error in an XML document, along with where it was found
+///
+/// Lines and columns both start at 1, and columns are counted in `char`s
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlError {
+    kind: ErrorKind,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl XmlError {
+    /// `remaining` is the length of the input that was left when the error was found
+    pub(crate) fn new(doc: &str, remaining: usize, kind: ErrorKind) -> Self {
+        let (offset, line, column) = position(doc, remaining);
+
+        Self {
+            kind,
+            offset,
+            line,
+            column,
+        }
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The byte offset of the error in the document
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {} column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl Error for XmlError {}
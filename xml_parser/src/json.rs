@@ -0,0 +1,231 @@
+use super::{identifier, is_white_space, Element, NamespaceError, Namespaces, QName};
+
+use parser_combinators::prelude::*;
+
+use json_parser::JsonValue;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Why a `JsonValue` doesn't describe an element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromJsonError {
+    /// The value isn't an object with a single member, named after the element
+    NotAnElement,
+    InvalidName(String),
+    /// An `@attribute` or `#text` member that is an array or an object
+    NotText(String),
+    /// An array that isn't the value of a member named after its elements
+    NestedArray,
+    Namespace(NamespaceError),
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromJsonError::NotAnElement => {
+                f.write_str("expected an object with a single member for the element")
+            }
+            FromJsonError::InvalidName(name) => write!(f, "`{name}` isn't a valid XML name"),
+            FromJsonError::NotText(key) => write!(f, "`{key}` has to be a string, number or bool"),
+            FromJsonError::NestedArray => f.write_str("arrays can't be nested"),
+            FromJsonError::Namespace(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for FromJsonError {}
+
+impl Element {
+    /// Converts the element to JSON, after the convention of tools like xml2js, where
+    /// a node becomes an object that holds its content under its qualified name,
+    /// as in `{"name": content}`
+    ///
+    /// The content of a node is
+    /// * `null` if it has no attributes or children
+    /// * a string if it only holds text
+    /// * an object otherwise, with each attribute under `@name`, the text under `#text`,
+    ///   and each child node under its own name, as an array if the name is repeated
+    ///
+    /// So `<a href="/">home</a>` is `{"a": {"@href": "/", "#text": "home"}}`, and
+    /// `<ul><li>1</li><li>2</li></ul>` is `{"ul": {"li": ["1", "2"]}}`.
+    ///
+    /// Text becomes a string, while comments and processing instructions become `null`.
+    ///
+    /// Namespace declarations are kept as `@xmlns` attributes, so prefixes and namespaces
+    /// survive the round trip. Other things don't, as JSON has no place for them:
+    /// * comments and processing instructions are left out
+    /// * the order of child nodes with different names is lost, since objects are unordered,
+    ///   and converting back puts them in the order of their names
+    /// * so is the order of attributes, which also come back ordered by name
+    /// * text that is mixed in between child nodes is joined into one `#text`, which
+    ///   comes back before the children, and whitespace between child nodes is dropped
+    /// * a name that is used once is never an array, so a JSON array with a single item
+    ///   comes back without the array
+    /// * numbers and bools become text, which comes back as strings
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Element::Node { name, .. } => {
+                let mut object = HashMap::new();
+                object.insert(name.qualified(), node_content(self));
+                JsonValue::Object(object)
+            }
+            Element::Text(text) => JsonValue::String(text.clone()),
+            Element::Comment(_) | Element::ProcessingInstruction { .. } => JsonValue::Null,
+        }
+    }
+
+    /// Converts an object with a single member back to a node, as the inverse of `to_json`,
+    /// resolving the namespaces of its names from the `@xmlns` attributes
+    pub fn from_json(value: &JsonValue) -> Result<Self, FromJsonError> {
+        let mut element = match value {
+            JsonValue::Object(object) if object.len() == 1 => {
+                let (name, content) = object.iter().next().ok_or(FromJsonError::NotAnElement)?;
+                node(name, content)?
+            }
+            _ => return Err(FromJsonError::NotAnElement),
+        };
+
+        element
+            .resolve_namespaces(&Namespaces::default())
+            .map_err(FromJsonError::Namespace)?;
+
+        Ok(element)
+    }
+}
+
+fn node_content(element: &Element) -> JsonValue {
+    let Element::Node {
+        attributes,
+        children,
+        ..
+    } = element
+    else {
+        return element.to_json();
+    };
+
+    let nodes = children
+        .iter()
+        .filter(|child| matches!(child, Element::Node { .. }));
+    let text: String = children
+        .iter()
+        .filter_map(|child| match child {
+            Element::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    // whitespace between child nodes is only there to lay them out
+    let text = if nodes.clone().next().is_some() && text.chars().all(is_white_space) {
+        String::new()
+    } else {
+        text
+    };
+
+    if attributes.is_empty() && nodes.clone().next().is_none() {
+        return if text.is_empty() {
+            JsonValue::Null
+        } else {
+            JsonValue::String(text)
+        };
+    }
+
+    let mut object = HashMap::new();
+    for (name, value) in attributes {
+        object.insert(
+            format!("@{}", name.qualified()),
+            JsonValue::String(value.clone()),
+        );
+    }
+
+    if !text.is_empty() {
+        object.insert("#text".to_string(), JsonValue::String(text));
+    }
+
+    for node in nodes {
+        let name = match node {
+            Element::Node { name, .. } => name.qualified(),
+            _ => continue,
+        };
+
+        let content = node_content(node);
+        match object.get_mut(&name) {
+            Some(JsonValue::List(list)) => list.push(content),
+            Some(single) => {
+                let first = std::mem::replace(single, JsonValue::Null);
+                *single = JsonValue::List(vec![first, content]);
+            }
+            None => {
+                object.insert(name, content);
+            }
+        }
+    }
+
+    JsonValue::Object(object)
+}
+
+fn name(name: &str) -> Result<QName, FromJsonError> {
+    match identifier().parse(name) {
+        ("", Ok(name)) => Ok(QName::new(name)),
+        _ => Err(FromJsonError::InvalidName(name.to_string())),
+    }
+}
+
+/// The text of a string, number or bool, where `key` is the member it is found under
+fn text(key: &str, value: &JsonValue) -> Result<String, FromJsonError> {
+    match value {
+        JsonValue::String(text) => Ok(text.clone()),
+        JsonValue::Number(_) | JsonValue::Bool(_) => Ok(value.to_string()),
+        JsonValue::Null => Ok(String::new()),
+        JsonValue::List(_) | JsonValue::Object(_) => Err(FromJsonError::NotText(key.to_string())),
+    }
+}
+
+fn node(tag: &str, content: &JsonValue) -> Result<Element, FromJsonError> {
+    let tag = name(tag)?;
+    let mut attributes = Vec::new();
+    let mut children = Vec::new();
+
+    match content {
+        JsonValue::Null => (),
+        JsonValue::List(_) => return Err(FromJsonError::NestedArray),
+        JsonValue::Object(object) => {
+            // objects are unordered, so the members are sorted to get the same element
+            // out of the same object every time
+            let mut members: Vec<_> = object.iter().collect();
+            members.sort_by_key(|(key, _)| key.as_str());
+
+            if let Some(value) = object.get("#text") {
+                children.push(Element::Text(text("#text", value)?));
+            }
+
+            for (key, value) in members {
+                if key == "#text" {
+                    continue;
+                }
+
+                if let Some(attribute) = key.strip_prefix('@') {
+                    attributes.push((name(attribute)?, text(key, value)?));
+                    continue;
+                }
+
+                match value {
+                    JsonValue::List(items) => {
+                        for item in items {
+                            children.push(node(key, item)?);
+                        }
+                    }
+                    value => children.push(node(key, value)?),
+                }
+            }
+        }
+        value => children.push(Element::Text(text(&tag.qualified(), value)?)),
+    }
+
+    Ok(Element::Node {
+        name: tag,
+        attributes,
+        children,
+    })
+}
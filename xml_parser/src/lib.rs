@@ -0,0 +1,1546 @@
+#![deny(clippy::pedantic)]
+#![deny(clippy::all)]
+#![allow(non_camel_case_types)]
+// these ask for attributes and doc sections on nearly every public function
+#![allow(clippy::must_use_candidate, clippy::missing_errors_doc)]
+
+use parser_combinators::prelude::*;
+
+use parser_combinators::filter::FilterError;
+use parser_combinators::limit::{Depth, DepthExceeded};
+use parser_combinators::repeat::collections::Collection;
+use parser_combinators::repeat::{FoundZero, TooMany};
+
+use either::Either;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+
+mod dtd;
+mod error;
+mod json;
+mod reader;
+mod writer;
+mod xpath;
+
+pub use dtd::{Dtd, DtdError, ValidationError, ValidationErrorKind};
+pub use error::{ErrorKind, XmlError};
+pub use json::FromJsonError;
+pub use reader::{ReadError, SyntaxError, XmlEvent, XmlReader};
+pub use writer::{Formatted, WriteOptions};
+pub use xpath::{Node, Value, XPath, XPathError};
+
+#[derive(Debug)]
+struct LiteralError;
+fn match_literal(
+    expected: &'static str,
+) -> impl for<'r> Parser<&'r str, Output = (), Error = LiteralError> + Copy {
+    (move |input: &mut &str| {
+        if input.starts_with(expected) {
+            *input = &input[expected.len()..];
+            Ok(())
+        } else {
+            Err(LiteralError)
+        }
+    })
+    .as_parser_in_place()
+}
+
+/// Pairs the error of `p` with the amount of input that was left when it failed,
+/// which is enough to find the error in the document later on
+fn located<P, Output, Error>(
+    p: P,
+) -> impl for<'a> Parser<&'a str, Output = Output, Error = (usize, Error)>
+where
+    P: for<'a> Parser<&'a str, Output = Output, Error = Error>,
+{
+    (move |s: &mut &str| {
+        let remaining = s.len();
+        let (rest, out) = p.parse(*s);
+        *s = rest;
+        out.map_err(|e| (remaining, e))
+    })
+    .as_parser_in_place()
+}
+
+#[derive(Debug)]
+pub struct EmptyInput;
+fn any_char() -> impl for<'a> Parser<&'a str, Output = char, Error = EmptyInput> + Copy {
+    (|input: &mut &str| {
+        let c = input.chars().next().ok_or(EmptyInput)?;
+        *input = &input[c.len_utf8()..];
+        Ok(c)
+    })
+    .as_parser_in_place()
+}
+
+/// `NameStartChar` from the XML 1.0 spec
+fn is_name_start_char(c: char) -> bool {
+    matches!(c,
+        ':' | 'A'..='Z' | '_' | 'a'..='z'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}'
+        | '\u{370}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+/// `NameChar` from the XML 1.0 spec
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c)
+        || matches!(c,
+            '-' | '.' | '0'..='9' | '\u{B7}'
+            | '\u{300}'..='\u{36F}'
+            | '\u{203F}'..='\u{2040}'
+        )
+}
+
+#[derive(Debug)]
+pub struct InvalidIdent;
+fn identifier() -> impl for<'a> Parser<&'a str, Output = String, Error = InvalidIdent> + Copy {
+    (|input: &mut &str| {
+        let mut matched = String::new();
+        let mut chars = input.chars();
+
+        match chars.next() {
+            Some(next) if is_name_start_char(next) => matched.push(next),
+            _ => return Err(InvalidIdent),
+        }
+
+        for next in chars {
+            if is_name_char(next) {
+                matched.push(next);
+            } else {
+                break;
+            }
+        }
+
+        let next_index = matched.len();
+        *input = &input[next_index..];
+        Ok(matched)
+    })
+    .as_parser_in_place()
+}
+
+#[derive(Debug)]
+pub enum MissingQuote {
+    First,
+    Second,
+}
+
+/// Entities that can be referenced on top of the five predefined ones, such as the ones
+/// declared in the internal subset of a DTD
+///
+/// Replacement text is put in as is, without expanding the references inside of it,
+/// so that a small document can't blow up into a huge one
+#[derive(Debug, Default, Clone)]
+pub struct Entities(Arc<HashMap<String, String>>);
+
+impl Entities {
+    pub fn declare(&mut self, name: String, value: String) {
+        Arc::make_mut(&mut self.0).insert(name, value);
+    }
+
+    /// Declares the general entities with a literal value in the internal subset of a DTD,
+    /// skipping parameter entities and external entities
+    fn declare_internal(&mut self, subset: &str) {
+        let declaration = whitespace_char()
+            .one_or_more(util::count)
+            .then(identifier())
+            .map_both(util::snd, drop)
+            .then(whitespace_char().one_or_more(util::count))
+            .map_both(util::fst, drop)
+            .then(literal_string())
+            .map_err(drop)
+            .then(eat_white_space())
+            .map_both(util::fst, util::unwrap_left)
+            .then(match_literal(">"))
+            .map_both(util::fst, drop);
+
+        let mut rest = subset;
+        while let Some(start) = rest.find("<!ENTITY") {
+            rest = &rest[start + "<!ENTITY".len()..];
+
+            if let (after, Ok((name, value))) = declaration.parse(rest) {
+                self.declare(name, value);
+                rest = after;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityErrorKind {
+    /// The reference isn't closed by a `;`
+    Malformed,
+    Unknown(String),
+    /// The character reference isn't a number, or not a character that XML allows
+    InvalidChar(String),
+    /// A `<` in an attribute value, where it has to be written as `&lt;`
+    LessThan,
+}
+
+#[derive(Debug, Clone)]
+struct EntityError {
+    kind: EntityErrorKind,
+    /// How much of the document is left, counting from the `&` of the reference,
+    /// or from the `<` in an attribute value
+    position: usize,
+}
+
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | ' '..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// Decodes the reference at the start of `input` onto the end of `text`
+///
+/// With `normalize`, whitespace in the replacement text of an entity becomes spaces,
+/// as it does in attribute values. Character references are always kept as they are.
+fn reference(
+    input: &mut &str,
+    entities: &Entities,
+    normalize: bool,
+    text: &mut String,
+) -> Result<(), EntityError> {
+    let position = input.len();
+    let error = |kind| EntityError { kind, position };
+
+    let body = &input[1..];
+    let len = body
+        .find(|c: char| !(is_name_char(c) || c == '#'))
+        .unwrap_or(body.len());
+
+    if len == 0 || !body[len..].starts_with(';') {
+        *input = body;
+        return Err(error(EntityErrorKind::Malformed));
+    }
+
+    let name = &body[..len];
+    *input = &body[len + 1..];
+
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix('x') {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => number.parse().ok(),
+        };
+
+        match code
+            .and_then(std::char::from_u32)
+            .filter(|&c| is_xml_char(c))
+        {
+            Some(c) => text.push(c),
+            None => return Err(error(EntityErrorKind::InvalidChar(name.to_string()))),
+        }
+
+        return Ok(());
+    }
+
+    match name {
+        "lt" => text.push('<'),
+        "gt" => text.push('>'),
+        "amp" => text.push('&'),
+        "quot" => text.push('"'),
+        "apos" => text.push('\''),
+        _ => match entities.0.get(name) {
+            Some(value) if normalize => text.extend(value.chars().map(normalize_whitespace)),
+            Some(value) => text.push_str(value),
+            None => return Err(error(EntityErrorKind::Unknown(name.to_string()))),
+        },
+    }
+
+    Ok(())
+}
+
+fn normalize_whitespace(c: char) -> char {
+    match c {
+        '\t' | '\n' | '\r' => ' ',
+        c => c,
+    }
+}
+
+/// Reads character data up to `end` or the end of `input`, decoding the references in it
+///
+/// A bad reference doesn't stop the character data, so that the parsers around it
+/// carry on and the error can be reported from where the data started
+///
+/// With `normalize`, the data is an attribute value: each tab and line break becomes a space,
+/// as the spec asks, with `\r\n` counting as a single line break, and a `<` is an error
+fn decode_until(
+    input: &mut &str,
+    end: char,
+    entities: &Entities,
+    normalize: bool,
+) -> Result<String, EntityError> {
+    let mut text = String::new();
+    let mut error = None;
+
+    while let Some(c) = input.chars().next().filter(|&c| c != end) {
+        if c == '&' {
+            if let Err(e) = reference(input, entities, normalize, &mut text) {
+                error.get_or_insert(e);
+            }
+        } else if normalize && c == '<' {
+            error.get_or_insert(EntityError {
+                kind: EntityErrorKind::LessThan,
+                position: input.len(),
+            });
+            *input = &input[1..];
+        } else if normalize && normalize_whitespace(c) != c {
+            text.push(' ');
+            *input = input.strip_prefix("\r\n").unwrap_or(&input[1..]);
+        } else {
+            text.push(c);
+            *input = &input[c.len_utf8()..];
+        }
+    }
+
+    match error {
+        None => Ok(text),
+        Some(e) => Err(e),
+    }
+}
+
+/// Collects items until one of them is an error, then holds on to that error
+struct Fallible<C, E>(Result<C, E>);
+
+impl<C: Default, E> Default for Fallible<C, E> {
+    fn default() -> Self {
+        Fallible(Ok(C::default()))
+    }
+}
+
+impl<T, E, C: Collection<T>> Collection<Result<T, E>> for Fallible<C, E> {
+    fn put(&mut self, item: Result<T, E>) {
+        match (&mut self.0, item) {
+            (Ok(c), Ok(item)) => c.put(item),
+            (Ok(_), Err(e)) => self.0 = Err(e),
+            (Err(_), _) => (),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, C::len)
+    }
+}
+
+/// An attribute value in either kind of quotes, with its references decoded
+/// and its whitespace normalized
+fn quoted_string(
+    entities: Entities,
+) -> impl for<'a> Parser<&'a str, Output = Result<String, EntityError>, Error = MissingQuote> {
+    (move |input: &mut &str| {
+        let quote = input
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or(MissingQuote::First)?;
+
+        let mut rest = &input[1..];
+        let value = decode_until(&mut rest, quote, &entities, true);
+        *input = rest.strip_prefix(quote).ok_or(MissingQuote::Second)?;
+        Ok(value)
+    })
+    .as_parser_in_place()
+}
+
+/// `S` from the XML 1.0 spec, which is narrower than `char::is_whitespace`
+fn is_white_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r')
+}
+
+fn whitespace_char(
+) -> impl for<'a> Parser<&'a str, Output = char, Error = FilterError<EmptyInput>> + Copy {
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    any_char().filter(|&x: &char| is_white_space(x))
+}
+
+fn eat_white_space() -> impl for<'a> Parser<&'a str, Output = (), Error = Infallible> + Copy {
+    whitespace_char().zero_or_more(util::ignore)
+}
+
+#[derive(Debug)]
+enum AttributeError {
+    Whitespace,
+    Ident,
+    Equal,
+    Value,
+}
+
+impl From<Either<Either<Either<FoundZero, InvalidIdent>, LiteralError>, MissingQuote>>
+    for AttributeError
+{
+    fn from(
+        e: Either<Either<Either<FoundZero, InvalidIdent>, LiteralError>, MissingQuote>,
+    ) -> Self {
+        match e {
+            Either::Left(Either::Left(Either::Left(_))) => AttributeError::Whitespace,
+            Either::Left(Either::Left(Either::Right(_))) => AttributeError::Ident,
+            Either::Left(Either::Right(_)) => AttributeError::Equal,
+            Either::Right(_) => AttributeError::Value,
+        }
+    }
+}
+
+fn attribute(
+    entities: Entities,
+) -> impl for<'a> Parser<&'a str, Output = Result<(QName, String), EntityError>, Error = AttributeError>
+{
+    whitespace_char()
+        .one_or_more(util::count)
+        .then(identifier())
+        .map(util::snd)
+        .then(eat_white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(match_literal("="))
+        .map(util::fst)
+        .then(eat_white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(quoted_string(entities))
+        .map_both(
+            |(name, value): (String, Result<_, _>)| value.map(|value| (QName::new(name), value)),
+            AttributeError::from,
+        )
+}
+
+/// The variants that hold a `usize` have the remaining input at the offending character
+#[derive(Debug)]
+enum SingleElementError {
+    MissingStart(usize),
+    InvalidIdent(usize),
+    MissingEnd(usize),
+    Entity(EntityError),
+    DuplicateAttribute(String),
+    Namespace(NamespaceError),
+    TooManyAttributes,
+    DepthExceeded,
+}
+
+type OpenTagError = Either<
+    Either<Either<(usize, LiteralError), (usize, InvalidIdent)>, TooMany>,
+    (usize, LiteralError),
+>;
+
+impl From<OpenTagError> for SingleElementError {
+    fn from(e: OpenTagError) -> Self {
+        match e {
+            Either::Left(Either::Left(Either::Left((at, _)))) => {
+                SingleElementError::MissingStart(at)
+            }
+            Either::Left(Either::Left(Either::Right((at, _)))) => {
+                SingleElementError::InvalidIdent(at)
+            }
+            Either::Left(Either::Right(TooMany)) => SingleElementError::TooManyAttributes,
+            Either::Right((at, _)) => SingleElementError::MissingEnd(at),
+        }
+    }
+}
+
+impl SingleElementError {
+    /// `start` is the remaining input at the start of the element
+    fn diagnose(self, start: usize) -> (usize, ErrorKind) {
+        match self {
+            SingleElementError::MissingStart(at) => (at, ErrorKind::ExpectedElement),
+            SingleElementError::InvalidIdent(at) => (at, ErrorKind::InvalidName),
+            SingleElementError::MissingEnd(at) => (at, ErrorKind::UnclosedTag),
+            SingleElementError::Entity(e) => (e.position, ErrorKind::Entity(e.kind)),
+            SingleElementError::DuplicateAttribute(name) => {
+                (start, ErrorKind::DuplicateAttribute(name))
+            }
+            SingleElementError::Namespace(e) => (start, ErrorKind::Namespace(e)),
+            SingleElementError::TooManyAttributes => (start, ErrorKind::TooManyItems),
+            SingleElementError::DepthExceeded => (start, ErrorKind::DepthExceeded),
+        }
+    }
+}
+
+/// `depth` counts the parent elements that this one is inside of, and `namespaces` are the
+/// namespaces in scope
+fn single_element(
+    depth: Depth,
+    max_items: usize,
+    entities: Entities,
+    namespaces: Namespaces,
+) -> impl for<'a> Parser<&'a str, Output = Element, Error = SingleElementError> {
+    located(match_literal("<"))
+        .then(located(identifier()))
+        .map(util::snd)
+        .then(attribute(entities).at_most(max_items, Fallible::<Vec<_>, _>::default))
+        .then(eat_white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(located(match_literal("/>")))
+        .map_both(
+            |((name, Fallible(attributes)), ())| {
+                attributes.map(|attributes| Element::Node {
+                    name: QName::new(name),
+                    attributes,
+                    children: Vec::new(),
+                })
+            },
+            SingleElementError::from,
+        )
+        .flat_map(move |element: Result<Element, _>| {
+            element
+                .map_err(SingleElementError::Entity)?
+                .unique_attributes()
+                .map_err(SingleElementError::DuplicateAttribute)?
+                .resolve_tag(&namespaces)
+                .map_err(SingleElementError::Namespace)
+        })
+        // the element is a level of nesting, even though nothing can be inside of it
+        .depth_limit(depth.deeper())
+        .map_err(|e| match e {
+            Either::Left(DepthExceeded) => SingleElementError::DepthExceeded,
+            Either::Right(e) => e,
+        })
+}
+
+/// The variants that hold a `usize` have the remaining input at the offending character
+#[derive(Debug)]
+enum ParentElementError {
+    Open(SingleElementError),
+    /// The content stopped at a child that isn't well-formed
+    Child(Box<ElementError>),
+    UnterminatedCData(usize),
+    /// The content stopped without a closing tag for the element with this name
+    MissingClose(usize, String),
+    /// The closing tag has another name than the element with this name
+    WrongCloseTag(usize, String),
+    MissingCloseEnd(usize),
+    DepthExceeded,
+    /// The child that made for one child too many
+    TooManyChildren(usize),
+    /// A bad reference in an attribute or the text of the element
+    Entity(EntityError),
+    DuplicateAttribute(String),
+    Namespace(NamespaceError),
+}
+
+impl ParentElementError {
+    /// `start` is the remaining input at the start of the element
+    fn diagnose(self, start: usize) -> (usize, ErrorKind) {
+        match self {
+            ParentElementError::Open(e) => e.diagnose(start),
+            ParentElementError::Child(e) => e.diagnose(),
+            ParentElementError::UnterminatedCData(at) => (at, ErrorKind::UnterminatedCData),
+            ParentElementError::MissingClose(at, name) => (at, ErrorKind::UnclosedElement(name)),
+            ParentElementError::WrongCloseTag(at, name) => (at, ErrorKind::WrongCloseTag(name)),
+            ParentElementError::MissingCloseEnd(at) => (at, ErrorKind::UnclosedTag),
+            ParentElementError::DepthExceeded => (start, ErrorKind::DepthExceeded),
+            ParentElementError::TooManyChildren(at) => (at, ErrorKind::TooManyItems),
+            ParentElementError::Entity(e) => (e.position, ErrorKind::Entity(e.kind)),
+            ParentElementError::DuplicateAttribute(name) => {
+                (start, ErrorKind::DuplicateAttribute(name))
+            }
+            ParentElementError::Namespace(e) => (start, ErrorKind::Namespace(e)),
+        }
+    }
+}
+
+/// The children of the element with `name`, up to and including the `</` of its closing tag
+///
+/// The content of an element can't fail, it just stops at the first thing that isn't
+/// content, so when that isn't the closing tag the error of the child it stopped at is
+/// reported. That error is kept from the attempt that found it, as parsing the child again
+/// would double the work at every level of nesting. A bad reference in the `attribute` of
+/// the element, or a `namespace` of its tag that can't be resolved, comes first though,
+/// since it is earlier in the document.
+fn content(
+    name: String,
+    attribute: Option<EntityError>,
+    namespace: Option<NamespaceError>,
+    depth: Depth,
+    max_items: usize,
+    entities: Entities,
+    namespaces: Namespaces,
+) -> impl for<'a> Parser<&'a str, Output = Fallible<Children, EntityError>, Error = ParentElementError>
+{
+    let child = element(depth, max_items, entities.clone(), namespaces)
+        .or(cdata())
+        .map(Either::into_inner)
+        .map(Ok::<_, EntityError>)
+        .or(text(entities))
+        .map(Either::into_inner);
+
+    (move |input: &mut &str| {
+        let mut children = Fallible::<Children, _>::default();
+
+        let stopped_at = loop {
+            match child.parse(*input) {
+                (rest, Ok(next)) => {
+                    children.put(next);
+
+                    if children.len() > max_items {
+                        return Err(ParentElementError::TooManyChildren(input.len()));
+                    }
+
+                    *input = rest;
+                }
+                (_, Err(((e, _), _))) => break e,
+            }
+        };
+
+        if let Some(rest) = input.strip_prefix("</") {
+            *input = rest;
+            return Ok(children);
+        }
+
+        if let Some(e) = &attribute {
+            return Err(ParentElementError::Entity(e.clone()));
+        }
+
+        if let Some(e) = &namespace {
+            return Err(ParentElementError::Namespace(e.clone()));
+        }
+
+        let at = input.len();
+        if input.starts_with("<![CDATA[") {
+            Err(ParentElementError::UnterminatedCData(at))
+        } else if input.starts_with('<') {
+            Err(ParentElementError::Child(Box::new(stopped_at)))
+        } else {
+            Err(ParentElementError::MissingClose(at, name.clone()))
+        }
+    })
+    .as_parser_in_place()
+}
+
+/// `depth` counts the parent elements that this one is inside of, and `namespaces` are the
+/// namespaces in scope
+fn parent_element(
+    depth: Depth,
+    max_items: usize,
+    entities: Entities,
+    namespaces: Namespaces,
+) -> impl for<'a> Parser<&'a str, Output = Element, Error = ParentElementError> {
+    located(match_literal("<"))
+        .then(located(identifier()))
+        .map(util::snd)
+        .then(attribute(entities.clone()).at_most(max_items, Fallible::<Vec<_>, _>::default))
+        .then(eat_white_space())
+        .map_both(util::fst, util::unwrap_left)
+        .then(located(match_literal(">")))
+        .map(util::fst)
+        .and_then(
+            move |(ident, Fallible(mut attributes)): (String, Fallible<Vec<_>, _>)| {
+                let mut name = QName::new(ident.clone());
+                let expected = ident.clone();
+
+                // the tag is resolved before its content, which is in the scope that it
+                // declares. If it can't be resolved, its own error is the one reported, so
+                // the content is parsed in the scope around it instead
+                let resolved = attributes
+                    .as_mut()
+                    .map(|attributes| namespaces.resolve_tag(&mut name, attributes));
+                let (scope, namespace) = match resolved {
+                    Ok(Ok(scope)) => (scope.into_owned(), None),
+                    Ok(Err(e)) => (namespaces.clone(), Some(e)),
+                    Err(_) => (namespaces.clone(), None),
+                };
+
+                content(
+                    ident.clone(),
+                    attributes.as_ref().err().cloned(),
+                    namespace.clone(),
+                    depth.deeper(),
+                    max_items,
+                    entities.clone(),
+                    scope,
+                )
+                .then(located(identifier().filter(move |i: &String| i == &ident)))
+                .map_both(util::fst, move |e| match e {
+                    Either::Left(e) => e,
+                    Either::Right((at, _)) => {
+                        ParentElementError::WrongCloseTag(at, expected.clone())
+                    }
+                })
+                .then(eat_white_space())
+                .map_both(util::fst, util::unwrap_left)
+                .then(located(match_literal(">")))
+                .map_both(util::fst, |e| match e {
+                    Either::Left(e) => e,
+                    Either::Right((at, _)) => ParentElementError::MissingCloseEnd(at),
+                })
+                .map(move |Fallible(children)| {
+                    let element = attributes.and_then(|attributes| {
+                        children.map(|Children(children)| Element::Node {
+                            name,
+                            attributes,
+                            children,
+                        })
+                    });
+
+                    (element, namespace)
+                })
+            },
+        )
+        .map_err(|e| match e {
+            Either::Left(e) => ParentElementError::Open(SingleElementError::from(e)),
+            Either::Right(e) => e,
+        })
+        .flat_map(
+            |(element, namespace): (Result<Element, _>, Option<NamespaceError>)| {
+                let element = element
+                    .map_err(ParentElementError::Entity)?
+                    .unique_attributes()
+                    .map_err(ParentElementError::DuplicateAttribute)?;
+
+                match namespace {
+                    Some(e) => Err(ParentElementError::Namespace(e)),
+                    None => Ok(element),
+                }
+            },
+        )
+        .depth_limit(depth.deeper())
+        .map_err(|e| match e {
+            Either::Left(DepthExceeded) => ParentElementError::DepthExceeded,
+            Either::Right(e) => e,
+        })
+}
+
+fn comment_element() -> impl for<'a> Parser<&'a str, Output = Element, Error = LiteralError> {
+    match_literal("<!--")
+        .then(
+            match_literal("-->")
+                .or(any_char())
+                .map_err(drop)
+                .flat_map(|e| match e {
+                    Either::Left(()) => Err(()),
+                    Either::Right(c) => Ok(c),
+                })
+                .zero_or_more(String::new),
+        )
+        .map_both(util::snd, util::unwrap_left)
+        .map(Element::Comment)
+}
+
+#[derive(Debug)]
+enum PiError {
+    MissingStart,
+    InvalidTarget,
+    MissingEnd,
+}
+
+/// A processing instruction, such as `<?xml-stylesheet href="style.css"?>`
+fn processing_instruction() -> impl for<'a> Parser<&'a str, Output = Element, Error = PiError> {
+    match_literal("<?")
+        .then(identifier().filter(|target: &String| !target.eq_ignore_ascii_case("xml")))
+        .map_both(util::snd, |e| match e {
+            Either::Left(LiteralError) => PiError::MissingStart,
+            Either::Right(_) => PiError::InvalidTarget,
+        })
+        .then(
+            (|input: &mut &str| {
+                let end = input.find("?>").ok_or(PiError::MissingEnd)?;
+                let data = &input[..end];
+
+                // the target has to be split from the data by whitespace
+                if !data.is_empty() && !data.starts_with(is_white_space) {
+                    return Err(PiError::InvalidTarget);
+                }
+
+                let data = data.trim_start_matches(is_white_space).to_string();
+                *input = &input[end + "?>".len()..];
+                Ok(data)
+            })
+            .as_parser_in_place(),
+        )
+        .map_both(
+            |(target, data)| Element::ProcessingInstruction { target, data },
+            Either::into_inner,
+        )
+}
+
+#[derive(Debug)]
+struct NoText;
+
+/// Character data up to the next tag, with its whitespace kept as is
+fn text(
+    entities: Entities,
+) -> impl for<'a> Parser<&'a str, Output = Result<Element, EntityError>, Error = NoText> {
+    (move |input: &mut &str| {
+        if input.is_empty() || input.starts_with('<') {
+            Err(NoText)
+        } else {
+            Ok(decode_until(input, '<', &entities, false).map(Element::Text))
+        }
+    })
+    .as_parser_in_place()
+}
+
+#[derive(Debug)]
+enum CDataError {
+    MissingStart,
+    MissingEnd,
+}
+
+fn cdata() -> impl for<'a> Parser<&'a str, Output = Element, Error = CDataError> {
+    match_literal("<![CDATA[")
+        .then(
+            (|input: &mut &str| {
+                let end = input.find("]]>").ok_or(CDataError::MissingEnd)?;
+                let text = input[..end].to_string();
+                *input = &input[end + "]]>".len()..];
+                Ok(Element::Text(text))
+            })
+            .as_parser_in_place(),
+        )
+        .map_both(util::snd, |e| match e {
+            Either::Left(LiteralError) => CDataError::MissingStart,
+            Either::Right(e) => e,
+        })
+}
+
+/// The children of a parent element, where neighbouring text and CDATA sections
+/// are merged into a single `Element::Text`
+#[derive(Default)]
+struct Children(Vec<Element>);
+
+impl Collection<Element> for Children {
+    fn put(&mut self, child: Element) {
+        match (self.0.last_mut(), child) {
+            (Some(Element::Text(text)), Element::Text(more)) => text.push_str(&more),
+            (_, child) => self.0.push(child),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// The errors of every kind of element, along with the remaining input at the element
+#[derive(Debug)]
+struct ElementError(
+    usize,
+    (
+        ((SingleElementError, ParentElementError), LiteralError),
+        PiError,
+    ),
+);
+
+impl ElementError {
+    /// Picks the error of the kind of element that the input looked like
+    fn diagnose(self) -> (usize, ErrorKind) {
+        let ElementError(start, (((single, parent), LiteralError), pi)) = self;
+
+        match (single, parent, pi) {
+            (_, _, PiError::InvalidTarget | PiError::MissingEnd) => {
+                (start, ErrorKind::InvalidProcessingInstruction)
+            }
+            // a tag that closes itself is only checked any further by `single_element`
+            (single, ParentElementError::Open(SingleElementError::MissingEnd(_)), _) => {
+                single.diagnose(start)
+            }
+            (_, parent, _) => parent.diagnose(start),
+        }
+    }
+}
+
+/// The type is spelled out instead of being `impl Parser`, because `parent_element` needs
+/// to know that it is `Send` and `Sync` while the type is still being worked out
+type ElementParser =
+    Box<dyn for<'a> Parser<&'a str, Output = Element, Error = ElementError> + Send + Sync>;
+
+/// `depth` counts the parent elements that this one is inside of, `max_items` is how many
+/// attributes or children an element may have, and `namespaces` are the namespaces in scope
+fn element(
+    depth: Depth,
+    max_items: usize,
+    entities: Entities,
+    namespaces: Namespaces,
+) -> ElementParser {
+    Box::new(defer(move || {
+        located(
+            single_element(depth, max_items, entities.clone(), namespaces.clone())
+                .or(parent_element(
+                    depth,
+                    max_items,
+                    entities.clone(),
+                    namespaces.clone(),
+                ))
+                .map(Either::into_inner)
+                .or(comment_element())
+                .map(Either::into_inner)
+                .or(processing_instruction())
+                .map(Either::into_inner),
+        )
+        .map_err(|(at, e)| ElementError(at, e))
+    }))
+}
+
+/// A string in either kind of quotes, taken as is
+fn literal_string() -> impl for<'a> Parser<&'a str, Output = String, Error = MissingQuote> + Copy {
+    (|input: &mut &str| {
+        let quote = input
+            .chars()
+            .next()
+            .filter(|&c| c == '"' || c == '\'')
+            .ok_or(MissingQuote::First)?;
+
+        let end = input[1..].find(quote).ok_or(MissingQuote::Second)?;
+        let value = input[1..=end].to_string();
+        *input = &input[end + 2..];
+        Ok(value)
+    })
+    .as_parser_in_place()
+}
+
+/// `literal` after any whitespace, for the grammars of queries and DTDs,
+/// where whitespace between tokens doesn't matter
+fn token(literal: &'static str) -> impl for<'a> Parser<&'a str, Output = (), Error = ()> + Copy {
+    eat_white_space()
+        .then(match_literal(literal))
+        .map_both(util::snd, drop)
+}
+
+/// A word such as `and`, which can't be followed by more of a name
+fn keyword(word: &'static str) -> impl for<'a> Parser<&'a str, Output = (), Error = ()> + Copy {
+    (move |input: &mut &str| {
+        let rest = input
+            .trim_start_matches(is_white_space)
+            .strip_prefix(word)
+            .ok_or(())?;
+
+        if rest.starts_with(is_name_char) {
+            return Err(());
+        }
+
+        *input = rest;
+        Ok(())
+    })
+    .as_parser_in_place()
+}
+
+fn name() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    eat_white_space()
+        .then(identifier())
+        .map_both(util::snd, drop)
+}
+
+fn literal() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    eat_white_space()
+        .then(literal_string())
+        .map_both(util::snd, drop)
+}
+
+/// The `name="value"` pairs of the XML declaration, which look like attributes but
+/// have to come in a fixed order
+fn pseudo_attribute(
+    name: &'static str,
+) -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    whitespace_char()
+        .one_or_more(util::count)
+        .then(match_literal(name))
+        .map_err(drop)
+        .then(eat_white_space())
+        .map_err(util::unwrap_left)
+        .then(match_literal("="))
+        .map_err(drop)
+        .then(eat_white_space())
+        .map_err(util::unwrap_left)
+        .then(literal_string())
+        .map_both(util::snd, drop)
+}
+
+#[derive(Debug)]
+enum DeclarationError {
+    MissingStart,
+    Version,
+    MissingEnd,
+}
+
+fn declaration() -> impl for<'a> Parser<&'a str, Output = Declaration, Error = DeclarationError> {
+    // `<?xml-stylesheet` and the like are processing instructions instead
+    (|input: &mut &str| match input.strip_prefix("<?xml") {
+        Some(rest) if rest.starts_with(is_white_space) => {
+            *input = rest;
+            Ok(())
+        }
+        _ => Err(DeclarationError::MissingStart),
+    })
+    .as_parser_in_place()
+    .then(pseudo_attribute("version"))
+    .map_both(util::snd, |e| match e {
+        Either::Left(e) => e,
+        Either::Right(()) => DeclarationError::Version,
+    })
+    .then(pseudo_attribute("encoding").optional())
+    .map_err(util::unwrap_left)
+    .then(
+        pseudo_attribute("standalone")
+            .filter(|value: &String| value == "yes" || value == "no")
+            .map(|value| value == "yes")
+            .optional(),
+    )
+    .map_err(util::unwrap_left)
+    .then(eat_white_space())
+    .map_err(util::unwrap_left)
+    .then(match_literal("?>"))
+    .map_both(
+        |((((version, encoding), standalone), ()), ()): (
+            (((_, Result<_, _>), Result<_, _>), _),
+            _,
+        )| Declaration {
+            version,
+            encoding: encoding.ok(),
+            standalone: standalone.ok(),
+        },
+        |e| match e {
+            Either::Left(e) => e,
+            Either::Right(LiteralError) => DeclarationError::MissingEnd,
+        },
+    )
+}
+
+/// Whitespace, then `keyword` and a literal
+fn keyword_literal(
+    keyword: &'static str,
+) -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    whitespace_char()
+        .one_or_more(util::count)
+        .then(match_literal(keyword))
+        .map_err(drop)
+        .then(whitespace_char().one_or_more(util::count))
+        .map_err(drop)
+        .then(literal_string())
+        .map_both(util::snd, drop)
+}
+
+/// `SYSTEM "system id"`, as the public and system ids
+fn system_id(
+) -> impl for<'a> Parser<&'a str, Output = (Option<String>, Option<String>), Error = ()> + Copy {
+    keyword_literal("SYSTEM").map(|system| (None, Some(system)))
+}
+
+/// `PUBLIC "public id" "system id"`, as the public and system ids
+fn public_id(
+) -> impl for<'a> Parser<&'a str, Output = (Option<String>, Option<String>), Error = ()> + Copy {
+    keyword_literal("PUBLIC")
+        .then(whitespace_char().one_or_more(util::count))
+        .map_both(util::fst, drop)
+        .then(literal_string())
+        .map_both(|(public, system)| (Some(public), Some(system)), drop)
+}
+
+/// The public and system ids of a DOCTYPE, which are both left out if there isn't an
+/// external id
+fn external_id(
+) -> impl for<'a> Parser<&'a str, Output = (Option<String>, Option<String>), Error = Infallible> + Copy
+{
+    system_id()
+        .or(public_id())
+        .map_both(Either::into_inner, drop)
+        .flat_map_err(|()| Ok((None, None)))
+}
+
+/// The declarations between `[` and `]`, kept as they are
+fn internal_subset() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> + Copy {
+    (|input: &mut &str| {
+        let subset = input.strip_prefix('[').ok_or(())?;
+        let mut rest = subset;
+
+        // a `]` only ends the subset outside of literals and comments
+        loop {
+            let c = rest.chars().next().ok_or(())?;
+
+            if c == ']' {
+                let subset = subset[..subset.len() - rest.len()].to_string();
+                *input = &rest[1..];
+                return Ok(subset);
+            }
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let end = comment.find("-->").ok_or(())?;
+                rest = &comment[end + "-->".len()..];
+            } else if c == '"' || c == '\'' {
+                let end = rest[1..].find(c).ok_or(())?;
+                rest = &rest[end + 2..];
+            } else {
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    })
+    .as_parser_in_place()
+}
+
+#[derive(Debug)]
+enum DoctypeError {
+    MissingStart,
+    InvalidName,
+    MissingEnd,
+}
+
+fn doctype() -> impl for<'a> Parser<&'a str, Output = Doctype, Error = DoctypeError> {
+    match_literal("<!DOCTYPE")
+        .map_err(|LiteralError| DoctypeError::MissingStart)
+        .then(whitespace_char().one_or_more(util::count))
+        .then(identifier())
+        .map_both(util::snd, |e| match e {
+            Either::Left(Either::Left(e)) => e,
+            Either::Left(Either::Right(_)) | Either::Right(InvalidIdent) => {
+                DoctypeError::InvalidName
+            }
+        })
+        .then(external_id())
+        .map_err(util::unwrap_left)
+        .then(eat_white_space())
+        .map_err(util::unwrap_left)
+        .then(
+            internal_subset()
+                .map(Some)
+                .flat_map_err(|()| Ok::<_, Infallible>(None)),
+        )
+        .map_err(util::unwrap_left)
+        .then(eat_white_space())
+        .map_err(util::unwrap_left)
+        .then(match_literal(">"))
+        .map_both(
+            |(((((name, (public_id, system_id)), ()), internal_subset), ()), ())| Doctype {
+                name,
+                public_id,
+                system_id,
+                internal_subset,
+            },
+            |e| match e {
+                Either::Left(e) => e,
+                Either::Right(LiteralError) => DoctypeError::MissingEnd,
+            },
+        )
+}
+
+/// The comments and processing instructions that can be around the root element,
+/// along with any whitespace
+fn misc() -> impl for<'a> Parser<&'a str, Output = Vec<Element>, Error = Infallible> {
+    eat_white_space()
+        .then(
+            comment_element()
+                .or(processing_instruction())
+                .map(Either::into_inner),
+        )
+        .map(util::snd)
+        .zero_or_more(Vec::new)
+        .then(eat_white_space())
+        .map_both(util::fst, util::unwrap_left)
+}
+
+/// The variants that hold a `usize` have the remaining input at the offending markup
+#[derive(Debug)]
+enum DocumentError {
+    Declaration(usize),
+    Doctype(usize),
+    Root(ElementError),
+    TrailingCharacters(usize),
+}
+
+impl DocumentError {
+    fn diagnose(self) -> (usize, ErrorKind) {
+        match self {
+            DocumentError::Declaration(at) => (at, ErrorKind::InvalidDeclaration),
+            DocumentError::Doctype(at) => (at, ErrorKind::InvalidDoctype),
+            DocumentError::Root(e) => e.diagnose(),
+            DocumentError::TrailingCharacters(at) => (at, ErrorKind::TrailingCharacters),
+        }
+    }
+}
+
+/// A whole document, with the prolog before the root element
+///
+/// If `internal_entities` is set, the entities declared in the internal subset of the
+/// DOCTYPE can be referenced on top of `entities`
+fn document(
+    entities: Entities,
+    internal_entities: bool,
+    limits: Limits,
+) -> impl for<'a> Parser<&'a str, Output = Document, Error = DocumentError> {
+    located(declaration())
+        .map(Some)
+        .flat_map_err(|e| match e {
+            (_, DeclarationError::MissingStart) => Ok(None),
+            (at, _) => Err(DocumentError::Declaration(at)),
+        })
+        .then(misc())
+        .map_err(util::unwrap_left)
+        .then(located(doctype()).map(Some).flat_map_err(|e| match e {
+            (_, DoctypeError::MissingStart) => Ok(None),
+            (at, _) => Err(DocumentError::Doctype(at)),
+        }))
+        .map_err(Either::into_inner)
+        .then(misc())
+        .map_err(util::unwrap_left)
+        .and_then(
+            move |(((declaration, mut prolog), doctype), more): (
+                ((_, Vec<_>), Option<Doctype>),
+                _,
+            )| {
+                prolog.extend(more);
+
+                let mut entities = entities.clone();
+                if let Some(subset) = doctype.as_ref().and_then(|d: &Doctype| {
+                    d.internal_subset.as_ref().filter(|_| internal_entities)
+                }) {
+                    entities.declare_internal(subset);
+                }
+
+                element(
+                    Depth::limit(limits.max_depth),
+                    limits.max_items,
+                    entities,
+                    Namespaces::default(),
+                )
+                .map_err(DocumentError::Root)
+                .then(misc())
+                .map_err(util::unwrap_left)
+                .then(
+                    (|input: &mut &str| {
+                        if input.is_empty() {
+                            Ok(())
+                        } else {
+                            Err(DocumentError::TrailingCharacters(input.len()))
+                        }
+                    })
+                    .as_parser_in_place(),
+                )
+                .map_err(Either::into_inner)
+                .map(move |((root, epilog), ())| Document {
+                    declaration,
+                    doctype,
+                    prolog,
+                    root,
+                    epilog,
+                })
+            },
+        )
+        .map_err(Either::into_inner)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Element {
+    Comment(String),
+    /// Text and CDATA sections, which keep all of their whitespace
+    Text(String),
+    Node {
+        name: QName,
+        attributes: Vec<(QName, String)>,
+        children: Vec<Element>,
+    },
+    ProcessingInstruction {
+        target: String,
+        data: String,
+    },
+}
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// A name as it was written, split at the `:`, along with the namespace that the
+/// prefix resolved to
+///
+/// The namespace is filled in by the parser, from the declarations in scope where the
+/// name is written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QName {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub namespace_uri: Option<String>,
+}
+
+impl QName {
+    /// Splits a name at its `:`, leaving the namespace to be resolved
+    pub fn new(mut name: String) -> Self {
+        let prefix = name.find(':').map(|colon| {
+            let local_name = name.split_off(colon + 1);
+            name.pop();
+            std::mem::replace(&mut name, local_name)
+        });
+
+        QName {
+            prefix,
+            local_name: name,
+            namespace_uri: None,
+        }
+    }
+
+    /// The name as it was written, with its prefix
+    pub fn qualified(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.local_name),
+            None => self.local_name.clone(),
+        }
+    }
+
+    /// Checks that the prefix and local name aren't empty, and that there is only one `:`
+    fn validate(&self) -> Result<(), NamespaceError> {
+        if self.prefix.as_deref() == Some("")
+            || self.local_name.is_empty()
+            || self.local_name.contains(':')
+        {
+            Err(NamespaceError::InvalidName(self.qualified()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NamespaceError {
+    /// A name with an empty prefix or local name, or more than one `:`
+    InvalidName(String),
+    UndeclaredPrefix(String),
+    /// `xmlns:prefix=""`, which is only allowed for the default namespace
+    EmptyDeclaration(String),
+    /// Two attributes with different prefixes for the same namespace, and the same local name
+    DuplicateAttribute(String),
+}
+
+/// The namespaces in scope, by their prefix, with `None` for the default namespace
+#[derive(Debug, Default, Clone)]
+struct Namespaces(Arc<HashMap<Option<String>, String>>);
+
+impl Namespaces {
+    fn declare(&mut self, prefix: Option<&str>, uri: &str) -> Result<(), NamespaceError> {
+        match (prefix, uri) {
+            // `xmlns=""` takes away the default namespace
+            (None, "") => {
+                Arc::make_mut(&mut self.0).remove(&None);
+            }
+            (Some(prefix), "") => return Err(NamespaceError::EmptyDeclaration(prefix.to_string())),
+            (prefix, uri) => {
+                Arc::make_mut(&mut self.0).insert(prefix.map(str::to_string), uri.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fills in the namespaces of an element's name and attributes, giving back the
+    /// namespaces in scope inside of it, which are only copied if it declares any
+    fn resolve_tag(
+        &self,
+        name: &mut QName,
+        attributes: &mut [(QName, String)],
+    ) -> Result<Cow<'_, Self>, NamespaceError> {
+        let mut scope = Cow::Borrowed(self);
+        for (attribute, value) in attributes.iter() {
+            attribute.validate()?;
+
+            match (attribute.prefix.as_deref(), attribute.local_name.as_str()) {
+                (None, "xmlns") => scope.to_mut().declare(None, value)?,
+                (Some("xmlns"), prefix) => scope.to_mut().declare(Some(prefix), value)?,
+                _ => (),
+            }
+        }
+
+        name.validate()?;
+        name.namespace_uri = scope.resolve(name.prefix.as_deref(), true)?;
+
+        for (attribute, _) in attributes.iter_mut() {
+            attribute.namespace_uri =
+                if attribute.prefix.is_none() && attribute.local_name == "xmlns" {
+                    Some(XMLNS_NAMESPACE.to_string())
+                } else {
+                    scope.resolve(attribute.prefix.as_deref(), false)?
+                };
+        }
+
+        for (i, (attribute, _)) in attributes.iter().enumerate() {
+            let same = |(other, _): &(QName, String)| {
+                other.namespace_uri == attribute.namespace_uri
+                    && other.local_name == attribute.local_name
+            };
+
+            if attributes[..i].iter().any(same) {
+                return Err(NamespaceError::DuplicateAttribute(attribute.qualified()));
+            }
+        }
+
+        Ok(scope)
+    }
+
+    /// The namespace of a name with `prefix`, where `default` says whether names
+    /// without a prefix are in the default namespace, as elements are but attributes aren't
+    fn resolve(
+        &self,
+        prefix: Option<&str>,
+        default: bool,
+    ) -> Result<Option<String>, NamespaceError> {
+        match prefix {
+            None if !default => Ok(None),
+            None => Ok(self.0.get(&None).cloned()),
+            Some("xml") => Ok(Some(XML_NAMESPACE.to_string())),
+            Some("xmlns") => Ok(Some(XMLNS_NAMESPACE.to_string())),
+            Some(prefix) => match self.0.get(&Some(prefix.to_string())) {
+                Some(uri) => Ok(Some(uri.clone())),
+                None => Err(NamespaceError::UndeclaredPrefix(prefix.to_string())),
+            },
+        }
+    }
+}
+
+/// The name of the first attribute that repeats the name of one before it
+fn duplicate_attribute(attributes: &[(QName, String)]) -> Option<String> {
+    attributes.iter().enumerate().find_map(|(i, (name, _))| {
+        let same = |(other, _): &(QName, String)| {
+            other.prefix == name.prefix && other.local_name == name.local_name
+        };
+
+        attributes[..i].iter().any(same).then(|| name.qualified())
+    })
+}
+
+impl Element {
+    /// Checks that no two attributes of a node have the same name, giving back
+    /// the name of the first repeated one otherwise
+    fn unique_attributes(self) -> Result<Self, String> {
+        if let Element::Node { attributes, .. } = &self {
+            if let Some(name) = duplicate_attribute(attributes) {
+                return Err(name);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Fills in the namespaces of this element's name and attributes, but not of its children
+    fn resolve_tag(mut self, namespaces: &Namespaces) -> Result<Self, NamespaceError> {
+        if let Element::Node {
+            name, attributes, ..
+        } = &mut self
+        {
+            namespaces.resolve_tag(name, attributes)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Fills in the namespaces of this element, its attributes and everything inside of it,
+    /// using the `xmlns` attributes on top of the declarations from its `parent`
+    fn resolve_namespaces(&mut self, parent: &Namespaces) -> Result<(), NamespaceError> {
+        if let Element::Node {
+            name,
+            attributes,
+            children,
+        } = self
+        {
+            let scope = parent.resolve_tag(name, attributes)?;
+
+            for child in children {
+                child.resolve_namespaces(&scope)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The `<?xml ...?>` declaration at the start of a document
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+    pub version: String,
+    pub encoding: Option<String>,
+    pub standalone: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Doctype {
+    /// The name of the root element
+    pub name: String,
+    pub public_id: Option<String>,
+    pub system_id: Option<String>,
+    /// The declarations between the brackets, which aren't parsed beyond the entities
+    pub internal_subset: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document {
+    pub declaration: Option<Declaration>,
+    pub doctype: Option<Doctype>,
+    /// The comments and processing instructions before the root element
+    pub prolog: Vec<Element>,
+    pub root: Element,
+    /// The comments and processing instructions after the root element
+    pub epilog: Vec<Element>,
+}
+
+/// Bounds on the size and shape of a document, for parsing input that can't be trusted
+///
+/// Every level of nesting takes up stack space while a document is parsed into a tree, so
+/// documents nested deeper than `max_depth` are rejected. Levels that don't fit on the stack
+/// of the thread doing the parsing carry on in newly allocated segments of stack, so that the
+/// limit holds on any thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// How many elements may be nested inside of each other, counting the root element
+    pub max_depth: usize,
+    /// How many attributes, or children, a single element may have, where neighbouring text
+    /// and CDATA sections are a single child
+    pub max_items: usize,
+    /// How long the document may be, in bytes
+    pub max_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_items: usize::MAX,
+            max_len: usize::MAX,
+        }
+    }
+}
+
+/// Which entities a document can reference, on top of the five predefined ones,
+/// and how large it may be
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Entities declared up front, like an external DTD would
+    pub entities: Entities,
+    /// Whether the entities declared in the internal subset of the DOCTYPE can be used
+    pub internal_entities: bool,
+    pub limits: Limits,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            entities: Entities::default(),
+            internal_entities: true,
+            limits: Limits::default(),
+        }
+    }
+}
+
+/// Parses a complete document, which has to hold exactly one root element
+pub fn parse_document(doc: &str) -> Result<Document, XmlError> {
+    parse_document_with(doc, &ParseOptions::default())
+}
+
+/// Parses a complete document, with the entities and limits from `options`
+pub fn parse_document_with(doc: &str, options: &ParseOptions) -> Result<Document, XmlError> {
+    if doc.len() > options.limits.max_len {
+        return Err(XmlError::new(doc, doc.len(), ErrorKind::TooLong));
+    }
+
+    let parser = document(
+        options.entities.clone(),
+        options.internal_entities,
+        options.limits,
+    );
+
+    match parser.parse(doc) {
+        (_, Ok(document)) => Ok(document),
+        (_, Err(e)) => {
+            let (remaining, kind) = e.diagnose();
+            Err(XmlError::new(doc, remaining, kind))
+        }
+    }
+}
@@ -1,305 +1,303 @@
 #![deny(clippy::pedantic)]
 #![deny(clippy::all)]
-#![allow(non_camel_case_types)]
 
-use parser_combinators::prelude::*;
-
-use parser_combinators::filter::FilterError;
-use parser_combinators::repeat::FoundZero;
+use xml_parser::{
+    parse_document_with, Document, Element, ParseOptions, WriteOptions, XPath, XmlError,
+};
+use xml_parser::{XmlEvent, XmlReader};
+
+use std::fmt::Write;
+use std::io::{BufRead, BufReader, Read};
+use std::process::exit;
+
+const USAGE: &str = "\
+usage: xml_parser [--validate | --pretty | --compact | --xpath <expr> | --to-json | --from-json
+                   | --events] [OPTIONS] [FILE]...
+
+Reads each FILE, or standard input when there are none or FILE is `-`.
+
+    --validate           only check that the input is well-formed XML
+    --pretty             print the document as indented XML (default)
+    --compact            print the document as XML without any added whitespace
+    --xpath <expr>       print what an XPath expression evaluates to on the root element
+    --to-json            print the root element as JSON
+    --from-json          read JSON written by `--to-json` and print it as XML
+    --events             stream the input and print each event as it is read
+
+OPTIONS:
+    --entity NAME=VALUE      declare an entity on top of the ones in the document
+    --no-internal-entities   ignore the entities declared in the document's DOCTYPE
+    --indent <n>             indent pretty output by n spaces per level (default 2)
+    --sort-attributes        print attributes in canonical order
+    --dtd                    check the document against the DTD in its DOCTYPE, and fill in
+                             the default values of attributes
+
+`--events` prints a line for each event, with text quoted and escaped as a JSON string:
+
+    start NAME [ATTRIBUTE=\"VALUE\"]...
+    end NAME
+    text \"TEXT\"
+    cdata \"TEXT\"
+    comment \"TEXT\"
+    pi TARGET \"DATA\"";
+
+enum Mode {
+    Validate,
+    Pretty,
+    Compact,
+    XPath(XPath),
+    ToJson,
+    FromJson,
+    Events,
+}
 
-use either::Either;
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {message}\n\n{USAGE}");
+    exit(2)
+}
 
-use std::convert::Infallible;
+fn open_input(path: &str) -> std::io::Result<Box<dyn BufRead>> {
+    if path == "-" {
+        Ok(Box::new(BufReader::new(std::io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(std::fs::File::open(path)?)))
+    }
+}
 
-#[derive(Debug)]
-struct LiteralError(&'static str);
-fn match_literal(
-    expected: &'static str,
-) -> impl for<'r> Parser<&'r str, Output = (), Error = LiteralError> + Copy {
-    (move |input: &mut &str| {
-        if input.starts_with(expected) {
-            *input = &input[expected.len()..];
-            Ok(())
-        } else {
-            Err(LiteralError(expected))
-        }
-    })
-    .as_parser_in_place()
+fn read_input(path: &str) -> std::io::Result<String> {
+    let mut doc = String::new();
+    open_input(path)?.read_to_string(&mut doc)?;
+    Ok(doc)
 }
 
-#[derive(Debug)]
-pub struct EmptyInput;
-fn any_char() -> impl for<'a> Parser<&'a str, Output = char, Error = EmptyInput> + Copy {
-    (|input: &mut &str| {
-        let c = input.chars().next().ok_or(EmptyInput)?;
-        *input = &input[c.len_utf8()..];
-        Ok(c)
-    })
-    .as_parser_in_place()
+/// Prints the error along with the line it was found on, and a caret under the offending column
+fn render_error(path: &str, doc: &str, error: &XmlError) {
+    let line = doc.lines().nth(error.line() - 1).unwrap_or("");
+    let gutter = error.line().to_string().len();
+
+    eprintln!("error: {}", error.kind());
+    eprintln!(
+        "{:gutter$}--> {path}:{}:{}",
+        "",
+        error.line(),
+        error.column()
+    );
+    eprintln!("{:gutter$} |", "");
+    eprintln!("{} | {line}", error.line());
+    eprintln!("{:gutter$} | {:>column$}", "", "^", column = error.column());
 }
 
-#[derive(Debug)]
-pub struct InvalidIdent;
-fn identifier() -> impl for<'a> Parser<&'a str, Output = String, Error = InvalidIdent> + Copy {
-    (|input: &mut &str| {
-        let mut matched = String::new();
-        let mut chars = input.chars();
+/// Prints the JSON from `doc` as XML, returning whether it described an element
+fn print_from_json(path: &str, doc: &str, options: WriteOptions) -> bool {
+    let element = json_parser::parse_json(doc)
+        .map_err(|e| e.to_string())
+        .and_then(|value| Element::from_json(&value).map_err(|e| e.to_string()));
 
-        match chars.next() {
-            Some(next) if next.is_alphabetic() => matched.push(next),
-            _ => return Err(InvalidIdent),
+    match element {
+        Ok(element) => {
+            println!("{}", element.formatted(options));
+            true
         }
-
-        for next in chars {
-            if next.is_alphanumeric() || next == '-' {
-                matched.push(next);
-            } else {
-                break;
-            }
+        Err(e) => {
+            eprintln!("error: {path}: {e}");
+            false
         }
-
-        let next_index = matched.len();
-        *input = &input[next_index..];
-        Ok(matched)
-    })
-    .as_parser_in_place()
+    }
 }
 
-#[derive(Debug)]
-pub enum MissingQuote {
-    First,
-    Second,
+/// `s` in double quotes, escaped so that it fits on one line
+fn quoted(s: &str) -> String {
+    json_parser::JsonValue::String(s.to_string()).to_string()
 }
 
-impl From<Either<LiteralError, LiteralError>> for MissingQuote {
-    fn from(e: Either<LiteralError, LiteralError>) -> Self {
-        match e {
-            Either::Left(LiteralError(_)) => MissingQuote::First,
-            Either::Right(LiteralError(_)) => MissingQuote::Second,
+/// The line `--events` prints for `event`
+fn event_line(event: &XmlEvent) -> String {
+    match event {
+        XmlEvent::StartElement { name, attributes } => {
+            let mut line = format!("start {}", name.qualified());
+            for (name, value) in attributes {
+                write!(line, " {}={}", name.qualified(), quoted(value)).unwrap();
+            }
+            line
+        }
+        XmlEvent::EndElement { name } => format!("end {}", name.qualified()),
+        XmlEvent::Text(text) => format!("text {}", quoted(text)),
+        XmlEvent::CData(text) => format!("cdata {}", quoted(text)),
+        XmlEvent::Comment(text) => format!("comment {}", quoted(text)),
+        XmlEvent::ProcessingInstruction { target, data } => {
+            format!("pi {target} {}", quoted(data))
         }
     }
 }
 
-fn quoted_string() -> impl for<'a> Parser<&'a str, Output = String, Error = MissingQuote> + Copy {
-    match_literal("\"")
-        .then(
-            any_char()
-                .filter(|&c: &char| c != '"')
-                .zero_or_more(String::new),
-        )
-        .map_both(util::snd, util::unwrap_left)
-        .then(match_literal("\""))
-        .map(util::fst)
-        .map_err(MissingQuote::from)
-}
-
-fn whitespace_char(
-) -> impl for<'a> Parser<&'a str, Output = char, Error = FilterError<EmptyInput>> + Copy {
-    #[allow(clippy::trivially_copy_pass_by_ref)]
-    any_char().filter(|x: &char| x.is_whitespace())
-}
-
-fn eat_white_space() -> impl for<'a> Parser<&'a str, Output = (), Error = Infallible> + Copy {
-    whitespace_char().zero_or_more(util::ignore)
-}
+/// Prints every event of the input as it is read, returning whether it was well-formed
+fn print_events(path: &str, options: &ParseOptions) -> bool {
+    let input = match open_input(path) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: could not read {path}: {e}");
+            exit(2)
+        }
+    };
+
+    for event in XmlReader::with_options(input, options.clone()) {
+        match event {
+            Ok(event) => println!("{}", event_line(&event)),
+            Err(e) => {
+                eprintln!("error: {path}: {e}");
+                return false;
+            }
+        }
+    }
 
-#[derive(Debug)]
-enum AttributeError {
-    Whitespace,
-    Ident,
-    Equal,
-    Value,
+    true
 }
 
-impl From<Either<Either<Either<FoundZero, InvalidIdent>, LiteralError>, MissingQuote>>
-    for AttributeError
-{
-    fn from(
-        e: Either<Either<Either<FoundZero, InvalidIdent>, LiteralError>, MissingQuote>,
-    ) -> Self {
-        match e {
-            Either::Left(Either::Left(Either::Left(_))) => AttributeError::Whitespace,
-            Either::Left(Either::Left(Either::Right(_))) => AttributeError::Ident,
-            Either::Left(Either::Right(_)) => AttributeError::Equal,
-            Either::Right(_) => AttributeError::Value,
+/// Checks `document` against the DTD in its DOCTYPE and fills in the default attributes,
+/// returning whether it was valid
+fn validate_dtd(path: &str, document: &mut Document) -> bool {
+    let dtd = match document.dtd() {
+        Some(Ok(dtd)) => dtd,
+        Some(Err(e)) => {
+            eprintln!("error: {path}: {e}");
+            return false;
+        }
+        None => {
+            eprintln!("error: {path} has no DTD to validate against");
+            return false;
         }
+    };
+
+    let errors = dtd.validate(document);
+    for e in &errors {
+        eprintln!("error: {path}: {e}");
     }
-}
 
-fn attribute() -> impl for<'a> Parser<&'a str, Output = (String, String), Error = AttributeError> {
-    whitespace_char()
-        .one_or_more(util::count)
-        .then(identifier())
-        .map(util::snd)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(match_literal("="))
-        .map(util::fst)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(quoted_string())
-        .map_err(AttributeError::from)
+    dtd.apply_defaults(&mut document.root);
+    errors.is_empty()
 }
 
-#[derive(Debug)]
-enum SingleElementError {
-    MissingStart,
-    InvalidIdent,
-    MissingEnd,
-}
+/// Prints the document as `mode` asks, returning whether there was anything to print
+fn print_document(path: &str, document: &Document, mode: &Mode, write: WriteOptions) -> bool {
+    match mode {
+        Mode::Validate | Mode::FromJson | Mode::Events => (),
+        Mode::Pretty => println!("{}", document.formatted(write)),
+        Mode::Compact => {
+            let compact = WriteOptions {
+                indent: None,
+                ..write
+            };
+            println!("{}", document.formatted(compact));
+        }
+        Mode::XPath(xpath) => {
+            let value = xpath.evaluate(&document.root);
 
-impl From<Either<Either<LiteralError, InvalidIdent>, LiteralError>> for SingleElementError {
-    fn from(e: Either<Either<LiteralError, InvalidIdent>, LiteralError>) -> Self {
-        match e {
-            Either::Left(Either::Left(_)) => SingleElementError::MissingStart,
-            Either::Left(Either::Right(_)) => SingleElementError::InvalidIdent,
-            Either::Right(_) => SingleElementError::MissingEnd,
+            if matches!(&value, xml_parser::Value::Nodes(nodes) if nodes.is_empty()) {
+                eprintln!("error: {path} has no nodes matching the expression");
+                return false;
+            }
+
+            println!("{value}");
+        }
+        Mode::ToJson => {
+            // objects are unordered, so their keys are sorted to print the same every time
+            let options = json_parser::WriteOptions {
+                sort_keys: true,
+                ..json_parser::WriteOptions::pretty()
+            };
+            println!("{}", document.root.to_json().formatted(options));
         }
     }
-}
-
-fn single_element() -> impl for<'a> Parser<&'a str, Output = Element, Error = SingleElementError> {
-    match_literal("<")
-        .then(identifier())
-        .map(util::snd)
-        .then(attribute().zero_or_more(Vec::new))
-        .map_err(util::unwrap_left)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .then(match_literal("/>"))
-        .map_both(
-            |((name, attributes), _)| Element::Node {
-                name,
-                attributes,
-                children: Vec::new(),
-            },
-            SingleElementError::from,
-        )
-}
 
-#[derive(Debug)]
-enum ParentElementError {
-    Open(SingleElementError),
-    Close(SingleElementError),
-    WrongCloseTag,
+    true
 }
 
-#[allow(clippy::type_complexity)]
-impl
-    From<
-        Either<
-            Either<Either<LiteralError, InvalidIdent>, LiteralError>,
-            Either<Either<LiteralError, FilterError<InvalidIdent>>, LiteralError>,
-        >,
-    > for ParentElementError
-{
-    fn from(
-        e: Either<
-            Either<Either<LiteralError, InvalidIdent>, LiteralError>,
-            Either<Either<LiteralError, FilterError<InvalidIdent>>, LiteralError>,
-        >,
-    ) -> Self {
-        match e {
-            Either::Left(e) => ParentElementError::Open(e.into()),
-            Either::Right(Either::Left(Either::Left(_))) => {
-                ParentElementError::Close(SingleElementError::MissingStart)
-            }
-            Either::Right(Either::Left(Either::Right(FilterError::ParseError(_)))) => {
-                ParentElementError::Close(SingleElementError::InvalidIdent)
-            }
-            Either::Right(Either::Left(Either::Right(FilterError::FilterError))) => {
-                ParentElementError::WrongCloseTag
-            }
-            Either::Right(Either::Right(_)) => {
-                ParentElementError::Close(SingleElementError::MissingEnd)
+fn main() {
+    let mut mode = Mode::Pretty;
+    let mut options = ParseOptions::default();
+    let mut write = WriteOptions::pretty();
+    let mut paths = Vec::new();
+    let mut dtd = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--validate" => mode = Mode::Validate,
+            "--pretty" => mode = Mode::Pretty,
+            "--compact" => mode = Mode::Compact,
+            "--xpath" => match args.next().map(|expr| expr.parse::<XPath>()) {
+                Some(Ok(xpath)) => mode = Mode::XPath(xpath),
+                Some(Err(e)) => usage_error(&e.to_string()),
+                None => usage_error("`--xpath` needs an XPath expression"),
+            },
+            "--to-json" => mode = Mode::ToJson,
+            "--from-json" => mode = Mode::FromJson,
+            "--events" => mode = Mode::Events,
+            "--entity" => match args.next().as_ref().and_then(|d| d.split_once('=')) {
+                Some((name, value)) => options
+                    .entities
+                    .declare(name.to_string(), value.to_string()),
+                None => usage_error("`--entity` needs a declaration like NAME=VALUE"),
+            },
+            "--no-internal-entities" => options.internal_entities = false,
+            "--indent" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(indent) => write.indent = Some(indent),
+                None => usage_error("`--indent` needs a number of spaces"),
+            },
+            "--sort-attributes" => write.sort_attributes = true,
+            "--dtd" => dtd = true,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                return;
             }
+            _ if arg.starts_with("--") => usage_error(&format!("unknown option `{arg}`")),
+            _ => paths.push(arg),
         }
     }
-}
 
-fn parent_element() -> impl for<'a> Parser<&'a str, Output = Element, Error = ParentElementError> {
-    match_literal("<")
-        .then(identifier())
-        .map(util::snd)
-        .then(attribute().zero_or_more(Vec::new))
-        .map_err(util::unwrap_left)
-        .then(match_literal(">"))
-        .map(util::fst)
-        .then(eat_white_space())
-        .map_both(util::fst, util::unwrap_left)
-        .and_then(|(ident, attributes): (String, Vec<_>)| {
-            let name = ident.clone();
-
-            element()
-                .then(eat_white_space())
-                .map_both(util::fst, util::unwrap_left)
-                .zero_or_more(Vec::new)
-                .then(match_literal("</"))
-                .map_both(util::fst, util::unwrap_right)
-                .then(identifier().filter(move |i: &String| i == &ident))
-                .map(util::fst)
-                .then(match_literal(">"))
-                .map(util::fst)
-                .map(move |children| Element::Node {
-                    name,
-                    attributes,
-                    children,
-                })
-        })
-        .map_err(ParentElementError::from)
-}
+    if paths.is_empty() {
+        paths.push(String::from("-"));
+    }
 
-fn comment_element() -> impl for<'a> Parser<&'a str, Output = Element, Error = LiteralError> {
-    match_literal("<!--")
-        .then(
-            match_literal("-->")
-                .or(any_char())
-                .map_err(drop)
-                .flat_map(|e| match e {
-                    Either::Left(()) => Err(()),
-                    Either::Right(c) => Ok(c),
-                })
-                .zero_or_more(String::new),
-        )
-        .map_both(util::snd, util::unwrap_left)
-        .map(Element::Comment)
-}
+    let mut failed = false;
 
-#[derive(Debug)]
-struct ElementError(((SingleElementError, ParentElementError), LiteralError));
-
-fn element() -> impl for<'a> Parser<&'a str, Output = Element, Error = ElementError> {
-    Box::new(defer(|| {
-        single_element()
-            .or(parent_element())
-            .map(Either::into_inner)
-            .or(comment_element())
-            .map(Either::into_inner)
-            .map_err(ElementError)
-    })) as Box<dyn for<'a> Parser<&'a str, Output = _, Error = _>>
-}
+    for path in &paths {
+        if let Mode::Events = mode {
+            failed |= !print_events(path, &options);
+            continue;
+        }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Element {
-    Comment(String),
-    Node {
-        name: String,
-        attributes: Vec<(String, String)>,
-        children: Vec<Element>,
-    },
-}
+        let doc = match read_input(path) {
+            Ok(doc) => doc,
+            Err(e) => {
+                eprintln!("error: could not read {path}: {e}");
+                exit(2)
+            }
+        };
 
-fn main() -> std::io::Result<()> {
-    use std::fs::File;
-    use std::io::Read;
+        if let Mode::FromJson = mode {
+            failed |= !print_from_json(path, &doc, write);
+            continue;
+        }
 
-    let mut file = File::open("./text.xml").unwrap();
+        let mut document = match parse_document_with(&doc, &options) {
+            Ok(document) => document,
+            Err(e) => {
+                render_error(path, &doc, &e);
+                failed = true;
+                continue;
+            }
+        };
 
-    let mut doc = String::new();
-    file.read_to_string(&mut doc).unwrap();
-    let doc = &doc;
+        if dtd && !validate_dtd(path, &mut document) {
+            failed = true;
+            continue;
+        }
 
-    println!("{:#?}", element().parse(doc));
+        failed |= !print_document(path, &document, &mode, write);
+    }
 
-    Ok(())
+    if failed {
+        exit(1)
+    }
 }
@@ -0,0 +1,589 @@
+use super::match_literal;
+use super::{attribute, cdata, comment_element, declaration, doctype, processing_instruction};
+use super::{decode_until, duplicate_attribute, eat_white_space, identifier, is_white_space};
+use super::{Element, Entities, EntityError, EntityErrorKind, Fallible, Namespaces};
+use super::{Limits, NamespaceError, ParseOptions, QName};
+
+use parser_combinators::prelude::*;
+
+use either::Either;
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+/// How much text is gathered before it is given out as an event of its own, so that a
+/// long run of text doesn't have to be held in memory all at once
+const TEXT_CHUNK: usize = 8 * 1024;
+
+/// A piece of an XML document, as read by `XmlReader`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XmlEvent {
+    /// An opening tag, which is followed by the events of its content, and then its
+    /// `EndElement`, even if the tag closed itself with `/>`
+    StartElement {
+        name: QName,
+        attributes: Vec<(QName, String)>,
+    },
+    EndElement {
+        name: QName,
+    },
+    /// Character data with its references decoded, which may come in more than one piece
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction {
+        target: String,
+        data: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// A problem with the document, along with the byte offset it was found at
+    Syntax {
+        kind: SyntaxError,
+        offset: usize,
+    },
+}
+
+#[derive(Debug)]
+pub enum SyntaxError {
+    InvalidUtf8,
+    /// Markup that isn't closed, or that doesn't parse
+    Malformed,
+    Entity(EntityErrorKind),
+    DuplicateAttribute(String),
+    Namespace(NamespaceError),
+    WrongCloseTag {
+        expected: String,
+        found: String,
+    },
+    /// A closing tag after the root element was closed
+    UnmatchedCloseTag(String),
+    /// The document ended inside of this element
+    Unclosed(String),
+    MissingRoot,
+    /// Text, CDATA or another element outside of the root element
+    ContentOutsideRoot,
+    /// An XML declaration that isn't at the very start, or a DOCTYPE that isn't before
+    /// the root element, or that is repeated
+    Misplaced,
+    DepthExceeded,
+    /// An element with more attributes than the limit
+    TooManyItems,
+    TooLong,
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxError::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            SyntaxError::Malformed => f.write_str("malformed markup"),
+            SyntaxError::Entity(e) => write!(f, "{e}"),
+            SyntaxError::DuplicateAttribute(name) => write!(f, "attribute `{name}` is repeated"),
+            SyntaxError::Namespace(e) => write!(f, "{e}"),
+            SyntaxError::WrongCloseTag { expected, found } => {
+                write!(f, "expected `</{expected}>`, found `</{found}>`")
+            }
+            SyntaxError::UnmatchedCloseTag(name) => {
+                write!(f, "`</{name}>` doesn't close any element")
+            }
+            SyntaxError::Unclosed(name) => write!(f, "`<{name}>` is never closed"),
+            SyntaxError::MissingRoot => f.write_str("the document has no root element"),
+            SyntaxError::ContentOutsideRoot => f.write_str("content outside of the root element"),
+            SyntaxError::Misplaced => f.write_str("declaration in the wrong place"),
+            SyntaxError::DepthExceeded => f.write_str("elements are nested too deeply"),
+            SyntaxError::TooManyItems => f.write_str("too many attributes in an element"),
+            SyntaxError::TooLong => f.write_str("the document is too long"),
+        }
+    }
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{e}"),
+            ReadError::Syntax { kind, offset } => write!(f, "{kind} at byte {offset}"),
+        }
+    }
+}
+
+impl Error for ReadError {}
+
+/// How far into the document the reader is, which decides where a DOCTYPE may go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Prolog,
+    /// The DOCTYPE has been read, but not the root element
+    Doctype,
+    /// The root element has been opened
+    Root,
+}
+
+/// An element that has been opened but not closed yet
+#[derive(Debug)]
+struct Open {
+    /// The name as it was written, which the closing tag has to repeat
+    raw: String,
+    name: QName,
+    /// Whether the element declared namespaces, which go out of scope when it is closed
+    declares: bool,
+}
+
+/// A streaming parser, which reads a document from a `BufRead` one `XmlEvent` at a time
+/// instead of building an `Element` tree
+///
+/// Tags are checked to be well-formed and balanced, and names are resolved to their
+/// namespaces. The XML declaration and the DOCTYPE are checked and skipped, although the
+/// entities declared in the DOCTYPE are used.
+///
+/// Apart from the elements that are still open, only the markup or text being read is held
+/// in memory, and long runs of text are given out in pieces. After the first error the
+/// iterator is finished.
+///
+/// The `limits` of the options bound how many elements may be open at once, how many
+/// attributes an element may have and how long the document may be. The children of an
+/// element aren't held on to, so there is no limit on them.
+#[derive(Debug)]
+pub struct XmlReader<R> {
+    reader: R,
+    /// Bytes that have been read but not parsed yet
+    buf: Vec<u8>,
+    /// How much of the document came before `buf`
+    offset: usize,
+    eof: bool,
+    entities: Entities,
+    internal_entities: bool,
+    limits: Limits,
+    open: Vec<Open>,
+    /// The namespaces in scope, with a new level for each open element that declared some
+    scopes: Vec<Namespaces>,
+    stage: Stage,
+    /// The `EndElement` of a tag that closed itself
+    pending: Option<XmlEvent>,
+    finished: bool,
+}
+
+impl<R: BufRead> XmlReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            offset: 0,
+            eof: false,
+            entities: options.entities,
+            internal_entities: options.internal_entities,
+            limits: options.limits,
+            open: Vec::new(),
+            scopes: vec![Namespaces::default()],
+            stage: Stage::Prolog,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// How many elements the reader is currently inside of
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Reads another chunk onto the end of `buf`, returning `false` at the end of the input
+    fn fill(&mut self) -> Result<bool, ReadError> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        let available = match self.reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) => {
+                self.finished = true;
+                return Err(e.into());
+            }
+        };
+
+        let len = available.len();
+        self.buf.extend_from_slice(available);
+        self.reader.consume(len);
+        self.eof = len == 0;
+
+        if self.offset + self.buf.len() > self.limits.max_len {
+            let max_len = self.limits.max_len;
+            return Err(self.error(SyntaxError::TooLong, max_len));
+        }
+
+        Ok(len != 0)
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.buf.drain(..len);
+        self.offset += len;
+    }
+
+    fn error(&mut self, kind: SyntaxError, offset: usize) -> ReadError {
+        self.finished = true;
+        ReadError::Syntax { kind, offset }
+    }
+
+    /// Turns the position of an `EntityError` in something that started at `start`
+    /// and was `len` bytes long into an error at the offset of the reference
+    fn entity_error(&mut self, e: EntityError, start: usize, len: usize) -> ReadError {
+        self.error(SyntaxError::Entity(e.kind), start + len - e.position)
+    }
+
+    fn next_event(&mut self) -> Result<Option<XmlEvent>, ReadError> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+
+        loop {
+            // enough to tell the kinds of markup apart, since `<![CDATA[` is the longest start
+            while self.buf.len() < "<![CDATA[".len() && self.fill()? {}
+
+            if self.buf.is_empty() {
+                return match self.open.last() {
+                    Some(open) => {
+                        let name = open.raw.clone();
+                        Err(self.error(SyntaxError::Unclosed(name), self.offset))
+                    }
+                    None if self.stage != Stage::Root => {
+                        Err(self.error(SyntaxError::MissingRoot, 0))
+                    }
+                    None => {
+                        self.finished = true;
+                        Ok(None)
+                    }
+                };
+            }
+
+            let event = if self.buf[0] == b'<' {
+                let len = loop {
+                    if let Some(len) = markup_len(&self.buf) {
+                        break len;
+                    }
+
+                    if !self.fill()? {
+                        return Err(self.error(SyntaxError::Malformed, self.offset));
+                    }
+                };
+
+                self.markup(len)?
+            } else {
+                self.text()?
+            };
+
+            if event.is_some() {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Reads the text at the start of `buf`, which is only given out inside of the root element
+    fn text(&mut self) -> Result<Option<XmlEvent>, ReadError> {
+        let len = loop {
+            match self.buf.iter().position(|&b| b == b'<') {
+                Some(len) => break len,
+                None if self.buf.len() >= TEXT_CHUNK => break text_len(&self.buf),
+                None if !self.fill()? => break self.buf.len(),
+                None => (),
+            }
+        };
+
+        let start = self.offset;
+        let text = match std::str::from_utf8(&self.buf[..len]) {
+            Ok(text) => text,
+            Err(e) => return Err(self.error(SyntaxError::InvalidUtf8, start + e.valid_up_to())),
+        };
+
+        if self.open.is_empty() {
+            if !text.chars().all(is_white_space) {
+                return Err(self.error(SyntaxError::ContentOutsideRoot, start));
+            }
+
+            self.consume(len);
+            return Ok(None);
+        }
+
+        let mut rest = text;
+        let decoded = decode_until(&mut rest, '<', &self.entities, false);
+        self.consume(len);
+
+        match decoded {
+            Ok(text) => Ok(Some(XmlEvent::Text(text))),
+            Err(e) => Err(self.entity_error(e, start, len)),
+        }
+    }
+
+    /// Parses the `len` bytes of markup at the start of `buf`
+    fn markup(&mut self, len: usize) -> Result<Option<XmlEvent>, ReadError> {
+        let start = self.offset;
+        let markup = match std::str::from_utf8(&self.buf[..len]) {
+            Ok(markup) => markup.to_string(),
+            Err(e) => return Err(self.error(SyntaxError::InvalidUtf8, start + e.valid_up_to())),
+        };
+        self.consume(len);
+
+        let malformed = |reader: &mut Self| reader.error(SyntaxError::Malformed, start);
+        let outside_root = self.stage == Stage::Root && self.open.is_empty();
+
+        if markup.starts_with("<!--") {
+            match comment_element().parse(&markup) {
+                ("", Ok(Element::Comment(comment))) => Ok(Some(XmlEvent::Comment(comment))),
+                _ => Err(malformed(self)),
+            }
+        } else if markup.starts_with("<![CDATA[") {
+            if self.open.is_empty() {
+                return Err(self.error(SyntaxError::ContentOutsideRoot, start));
+            }
+
+            match cdata().parse(&markup) {
+                ("", Ok(Element::Text(text))) => Ok(Some(XmlEvent::CData(text))),
+                _ => Err(malformed(self)),
+            }
+        } else if markup.starts_with("<?xml") && markup[5..].starts_with(is_white_space) {
+            if start != 0 {
+                return Err(self.error(SyntaxError::Misplaced, start));
+            }
+
+            match declaration().parse(&markup) {
+                ("", Ok(_)) => Ok(None),
+                _ => Err(malformed(self)),
+            }
+        } else if markup.starts_with("<?") {
+            match processing_instruction().parse(&markup) {
+                ("", Ok(Element::ProcessingInstruction { target, data })) => {
+                    Ok(Some(XmlEvent::ProcessingInstruction { target, data }))
+                }
+                _ => Err(malformed(self)),
+            }
+        } else if markup.starts_with("<!DOCTYPE") {
+            if self.stage != Stage::Prolog {
+                return Err(self.error(SyntaxError::Misplaced, start));
+            }
+            self.stage = Stage::Doctype;
+
+            match doctype().parse(&markup) {
+                ("", Ok(doctype)) => {
+                    if let Some(subset) = doctype.internal_subset.filter(|_| self.internal_entities)
+                    {
+                        self.entities.declare_internal(&subset);
+                    }
+                    Ok(None)
+                }
+                _ => Err(malformed(self)),
+            }
+        } else if markup.starts_with("</") {
+            let ("", Ok(found)) = end_tag().parse(&markup) else {
+                return Err(malformed(self));
+            };
+
+            match self.open.last() {
+                Some(open) if open.raw == found => Ok(Some(self.close())),
+                Some(open) => {
+                    let expected = open.raw.clone();
+                    Err(self.error(SyntaxError::WrongCloseTag { expected, found }, start))
+                }
+                None => Err(self.error(SyntaxError::UnmatchedCloseTag(found), start)),
+            }
+        } else {
+            if outside_root {
+                return Err(self.error(SyntaxError::ContentOutsideRoot, start));
+            }
+
+            let ("", Ok(tag)) = start_tag(self.entities.clone()).parse(&markup) else {
+                return Err(malformed(self));
+            };
+
+            self.open(tag, start, len).map(Some)
+        }
+    }
+
+    fn open(&mut self, tag: StartTag, start: usize, len: usize) -> Result<XmlEvent, ReadError> {
+        let mut attributes = match tag.attributes {
+            Ok(attributes) => attributes,
+            Err(e) => return Err(self.entity_error(e, start, len)),
+        };
+
+        if self.open.len() >= self.limits.max_depth {
+            return Err(self.error(SyntaxError::DepthExceeded, start));
+        }
+
+        if attributes.len() > self.limits.max_items {
+            return Err(self.error(SyntaxError::TooManyItems, start));
+        }
+
+        if let Some(name) = duplicate_attribute(&attributes) {
+            return Err(self.error(SyntaxError::DuplicateAttribute(name), start));
+        }
+
+        let mut name = QName::new(tag.name.clone());
+        let scope = self
+            .scopes
+            .last()
+            .map(|scope| scope.resolve_tag(&mut name, &mut attributes));
+        let new_scope = match scope {
+            Some(Ok(Cow::Owned(scope))) => Some(scope),
+            Some(Ok(Cow::Borrowed(_))) | None => None,
+            Some(Err(e)) => return Err(self.error(SyntaxError::Namespace(e), start)),
+        };
+
+        let declares = new_scope.is_some();
+        self.scopes.extend(new_scope);
+        self.open.push(Open {
+            raw: tag.name,
+            name: name.clone(),
+            declares,
+        });
+        self.stage = Stage::Root;
+
+        if tag.self_closing {
+            self.pending = Some(self.close());
+        }
+
+        Ok(XmlEvent::StartElement { name, attributes })
+    }
+
+    /// Closes the innermost open element
+    fn close(&mut self) -> XmlEvent {
+        let open = self.open.pop().expect("an element to close");
+        if open.declares {
+            self.scopes.pop();
+        }
+
+        XmlEvent::EndElement { name: open.name }
+    }
+}
+
+impl<R: BufRead> Iterator for XmlReader<R> {
+    type Item = Result<XmlEvent, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        self.next_event().transpose()
+    }
+}
+
+/// How long the markup at the start of `buf` is, or `None` if it hasn't all been read yet
+fn markup_len(buf: &[u8]) -> Option<usize> {
+    let find = |pattern: &[u8], from: usize| {
+        buf.get(from..)?
+            .windows(pattern.len())
+            .position(|window| window == pattern)
+            .map(|i| from + i + pattern.len())
+    };
+
+    if buf.starts_with(b"<!--") {
+        return find(b"-->", "<!--".len());
+    }
+    if buf.starts_with(b"<![CDATA[") {
+        return find(b"]]>", "<![CDATA[".len());
+    }
+    if buf.starts_with(b"<?") {
+        return find(b"?>", "<?".len());
+    }
+
+    // tags end at the first `>` that isn't quoted, and a DOCTYPE at the first one
+    // that isn't in its internal subset either, where comments are skipped as a whole
+    let doctype = buf.starts_with(b"<!");
+    let mut quote = None;
+    let mut brackets = 0_usize;
+    let mut i = 1;
+    while i < buf.len() {
+        match (quote, buf[i]) {
+            (Some(q), b) if b == q => quote = None,
+            (None, b @ (b'"' | b'\'')) => quote = Some(b),
+            (None, b'[') if doctype => brackets += 1,
+            (None, b']') if doctype => brackets = brackets.saturating_sub(1),
+            (None, b'<') if brackets > 0 && buf[i..].starts_with(b"<!--") => {
+                i = find(b"-->", i + "<!--".len())?;
+                continue;
+            }
+            (None, b'>') if brackets == 0 => return Some(i + 1),
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+/// How much of a long run of text can be decoded on its own, leaving a reference
+/// or a character that has only partly been read for the next piece
+fn text_len(buf: &[u8]) -> usize {
+    let mut len = buf.len();
+
+    if let Some(amp) = buf.iter().rposition(|&b| b == b'&') {
+        if !buf[amp..].contains(&b';') {
+            len = amp;
+        }
+    }
+
+    if let Err(e) = std::str::from_utf8(&buf[..len]) {
+        if e.error_len().is_none() {
+            len = e.valid_up_to();
+        }
+    }
+
+    // a reference this long is malformed anyway, so let decoding report it
+    if len == 0 {
+        buf.len()
+    } else {
+        len
+    }
+}
+
+struct StartTag {
+    name: String,
+    attributes: Result<Vec<(QName, String)>, EntityError>,
+    self_closing: bool,
+}
+
+fn start_tag(entities: Entities) -> impl for<'a> Parser<&'a str, Output = StartTag, Error = ()> {
+    match_literal("<")
+        .then(identifier())
+        .map(util::snd)
+        .then(attribute(entities).zero_or_more(Fallible::<Vec<_>, _>::default))
+        .map_err(drop)
+        .then(eat_white_space())
+        .map_both(util::fst, drop)
+        .then(
+            match_literal("/>")
+                .map(|()| true)
+                .or(match_literal(">").map(|()| false))
+                .map(Either::into_inner),
+        )
+        .map_both(
+            |((name, Fallible(attributes)), self_closing)| StartTag {
+                name,
+                attributes,
+                self_closing,
+            },
+            drop,
+        )
+}
+
+fn end_tag() -> impl for<'a> Parser<&'a str, Output = String, Error = ()> {
+    match_literal("</")
+        .then(identifier())
+        .map(util::snd)
+        .then(eat_white_space())
+        .map(util::fst)
+        .then(match_literal(">"))
+        .map_both(util::fst, drop)
+}
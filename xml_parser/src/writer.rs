@@ -0,0 +1,288 @@
+use super::{Document, Element, QName};
+
+use std::fmt;
+use std::io;
+
+/// Controls how an `Element` or `Document` is written out
+///
+/// The default writes everything on one line, with attributes in the order they were parsed
+///
+/// Compact output parses back into the same tree, except that empty text isn't written at all
+/// and text nodes next to each other read back as one. Indented output doesn't: the parser
+/// keeps all whitespace, so the indentation comes back as text between the elements.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Put every child on its own line, indented by this many spaces per level of nesting
+    ///
+    /// Only elements that hold nothing but other elements, comments and processing
+    /// instructions are broken up, since whitespace added around text would change it
+    pub indent: Option<usize>,
+    /// Write namespace declarations first, ordered by prefix, and then the other
+    /// attributes ordered by namespace and local name, as Canonical XML does
+    pub sort_attributes: bool,
+}
+
+impl WriteOptions {
+    pub fn compact() -> Self {
+        Self::default()
+    }
+
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(2),
+            ..Self::default()
+        }
+    }
+}
+
+/// An `Element` or `Document` that is displayed with the given `WriteOptions`
+#[derive(Debug, Clone, Copy)]
+pub struct Formatted<'a, T> {
+    value: &'a T,
+    options: WriteOptions,
+}
+
+impl Element {
+    pub fn formatted(&self, options: WriteOptions) -> Formatted<'_, Self> {
+        Formatted {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl Document {
+    pub fn formatted(&self, options: WriteOptions) -> Formatted<'_, Self> {
+        Formatted {
+            value: self,
+            options,
+        }
+    }
+
+    /// Serializes the document into `writer`
+    ///
+    /// The output is written in many small pieces, so a buffered writer should be used
+    /// for files and sockets
+    pub fn write_to<W: io::Write>(&self, mut writer: W, options: WriteOptions) -> io::Result<()> {
+        write!(writer, "{}", self.formatted(options))
+    }
+}
+
+fn alternate_options(f: &fmt::Formatter<'_>) -> WriteOptions {
+    if f.alternate() {
+        WriteOptions::pretty()
+    } else {
+        WriteOptions::compact()
+    }
+}
+
+/// Formats the element as compact XML, or as XML indented by two spaces
+/// when the alternate flag is used (`{:#}`)
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_element(f, self, &alternate_options(f), 0)
+    }
+}
+
+/// Formats the document as compact XML, or as XML indented by two spaces
+/// when the alternate flag is used (`{:#}`)
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_document(f, self, &alternate_options(f))
+    }
+}
+
+impl fmt::Display for Formatted<'_, Element> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_element(f, self.value, &self.options, 0)
+    }
+}
+
+impl fmt::Display for Formatted<'_, Document> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_document(f, self.value, &self.options)
+    }
+}
+
+fn write_document(
+    f: &mut fmt::Formatter<'_>,
+    document: &Document,
+    options: &WriteOptions,
+) -> fmt::Result {
+    let mut first = true;
+    let mut separate = |f: &mut fmt::Formatter<'_>| {
+        if !std::mem::replace(&mut first, false) && options.indent.is_some() {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    };
+
+    if let Some(declaration) = &document.declaration {
+        separate(f)?;
+        write!(f, "<?xml version=\"{}\"", declaration.version)?;
+        if let Some(encoding) = &declaration.encoding {
+            write!(f, " encoding=\"{encoding}\"")?;
+        }
+        if let Some(standalone) = declaration.standalone {
+            let standalone = if standalone { "yes" } else { "no" };
+            write!(f, " standalone=\"{standalone}\"")?;
+        }
+        f.write_str("?>")?;
+    }
+
+    if let Some(doctype) = &document.doctype {
+        separate(f)?;
+        write!(f, "<!DOCTYPE {}", doctype.name)?;
+        match (&doctype.public_id, &doctype.system_id) {
+            (Some(public_id), system_id) => {
+                f.write_str(" PUBLIC ")?;
+                write_literal(f, public_id)?;
+                if let Some(system_id) = system_id {
+                    f.write_str(" ")?;
+                    write_literal(f, system_id)?;
+                }
+            }
+            (None, Some(system_id)) => {
+                f.write_str(" SYSTEM ")?;
+                write_literal(f, system_id)?;
+            }
+            (None, None) => (),
+        }
+        if let Some(internal_subset) = &doctype.internal_subset {
+            write!(f, " [{internal_subset}]")?;
+        }
+        f.write_str(">")?;
+    }
+
+    for element in &document.prolog {
+        separate(f)?;
+        write_element(f, element, options, 0)?;
+    }
+
+    separate(f)?;
+    write_element(f, &document.root, options, 0)?;
+
+    for element in &document.epilog {
+        separate(f)?;
+        write_element(f, element, options, 0)?;
+    }
+
+    Ok(())
+}
+
+fn write_element(
+    f: &mut fmt::Formatter<'_>,
+    element: &Element,
+    options: &WriteOptions,
+    depth: usize,
+) -> fmt::Result {
+    match element {
+        // neither of these has a way to escape their end, so they are written as they are
+        Element::Comment(comment) => write!(f, "<!--{comment}-->"),
+        Element::ProcessingInstruction { target, data } if data.is_empty() => {
+            write!(f, "<?{target}?>")
+        }
+        Element::ProcessingInstruction { target, data } => write!(f, "<?{target} {data}?>"),
+        Element::Text(text) => write_escaped(f, text, false),
+        Element::Node {
+            name,
+            attributes,
+            children,
+        } => {
+            write!(f, "<{}", name.qualified())?;
+
+            let mut attributes: Vec<_> = attributes.iter().collect();
+            if options.sort_attributes {
+                attributes.sort_by_cached_key(|(name, _)| canonical_order(name));
+            }
+
+            for (name, value) in attributes {
+                write!(f, " {}=\"", name.qualified())?;
+                write_escaped(f, value, true)?;
+                f.write_str("\"")?;
+            }
+
+            // empty text writes nothing, so an element that only holds that has no children
+            // as far as the parser can tell
+            let children: Vec<_> = children
+                .iter()
+                .filter(|child| !matches!(child, Element::Text(text) if text.is_empty()))
+                .collect();
+
+            if children.is_empty() {
+                return f.write_str("/>");
+            }
+
+            f.write_str(">")?;
+
+            let options = if children
+                .iter()
+                .any(|child| matches!(child, Element::Text(_)))
+            {
+                WriteOptions {
+                    indent: None,
+                    ..*options
+                }
+            } else {
+                *options
+            };
+
+            for child in children {
+                write_newline(f, &options, depth + 1)?;
+                write_element(f, child, &options, depth + 1)?;
+            }
+            write_newline(f, &options, depth)?;
+
+            write!(f, "</{}>", name.qualified())
+        }
+    }
+}
+
+/// Sorts `xmlns` before `xmlns:prefix` before every other attribute, with the other
+/// attributes ordered by namespace, where no namespace comes first, and then by local name
+fn canonical_order(name: &QName) -> (u8, Option<String>, String) {
+    match (name.prefix.as_deref(), name.local_name.as_str()) {
+        (None, "xmlns") => (0, None, String::new()),
+        (Some("xmlns"), prefix) => (1, None, prefix.to_string()),
+        _ => (2, name.namespace_uri.clone(), name.local_name.clone()),
+    }
+}
+
+fn write_newline(f: &mut fmt::Formatter<'_>, options: &WriteOptions, depth: usize) -> fmt::Result {
+    match options.indent {
+        Some(indent) => write!(f, "\n{:1$}", "", indent * depth),
+        None => Ok(()),
+    }
+}
+
+/// Writes text, or an attribute value when `attribute` is set, so that the parser reads it
+/// back the same
+///
+/// In attribute values, tabs and line breaks are written as character references,
+/// since the parser would normalize them into spaces otherwise
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str, attribute: bool) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '&' => f.write_str("&amp;")?,
+            '<' => f.write_str("&lt;")?,
+            '>' => f.write_str("&gt;")?,
+            '\r' => f.write_str("&#13;")?,
+            '"' if attribute => f.write_str("&quot;")?,
+            '\t' if attribute => f.write_str("&#9;")?,
+            '\n' if attribute => f.write_str("&#10;")?,
+            c => write!(f, "{c}")?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a system or public id in whichever quotes it doesn't hold, since these
+/// literals have no escapes
+fn write_literal(f: &mut fmt::Formatter<'_>, literal: &str) -> fmt::Result {
+    if literal.contains('"') {
+        write!(f, "'{literal}'")
+    } else {
+        write!(f, "\"{literal}\"")
+    }
+}
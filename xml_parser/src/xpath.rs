@@ -0,0 +1,816 @@
+// "XPath" is a name, not an item that belongs in backticks
+#![allow(clippy::doc_markdown)]
+
+use super::{eat_white_space, keyword, literal, name, token};
+use super::{Element, QName};
+
+use parser_combinators::limit::Depth;
+use parser_combinators::prelude::*;
+
+use either::Either;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// How deeply predicates, function arguments and parentheses may be nested in a query
+const MAX_NESTING: usize = 32;
+
+impl Element {
+    /// Finds every node matched by an XPath expression, see `XPath` for what is supported
+    pub fn query(&self, path: &str) -> Result<Vec<Node<'_>>, XPathError> {
+        Ok(path.parse::<XPath>()?.select(self))
+    }
+}
+
+/// An XPath 1.0 expression
+///
+/// This supports the following subset of XPath, where an absolute path starts at the element
+/// that is queried, as if it were the root element of a document, and a relative path starts
+/// inside of it
+///
+/// * `/` and `//` between steps, or at the start of an absolute path
+/// * `name` child elements with the given name, `*` every child element, `text()`,
+///   `comment()` and `node()` the child nodes of each kind, and `.` the node itself
+/// * `@name` and `@*` attributes, apart from namespace declarations
+/// * `[2]` a position, counting from 1, and `[expr]` the nodes for which `expr` holds,
+///   such as `[@id = 'a']`, using any of `=`, `!=`, `<`, `<=`, `>` or `>=`, along with
+///   `and`, `or` and parentheses
+/// * the functions `count`, `contains`, `starts-with`, `string-length`, `not`, `position`
+///   and `last`
+///
+/// Names are matched as they were written, prefix included, since a query has no way to
+/// declare namespaces of its own
+#[derive(Debug, Clone, PartialEq)]
+pub struct XPath {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Comparison, Box<Expr>),
+    Literal(String),
+    Number(f64),
+    Call(Function, Vec<Expr>),
+    Path(Path),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    absolute: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    axis: Axis,
+    test: NodeTest,
+    predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Child,
+    /// What `//` stands for, along with the step after it
+    DescendantOrSelf,
+    Attribute,
+    SelfNode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NodeTest {
+    Name(String),
+    /// `*`, which matches elements and attributes but not text or comments
+    Any,
+    Text,
+    Comment,
+    Node,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Count,
+    Contains,
+    StartsWith,
+    StringLength,
+    Not,
+    Position,
+    Last,
+}
+
+/// A malformed XPath expression, along with the column it stopped making sense at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XPathError {
+    column: usize,
+}
+
+impl XPathError {
+    pub fn column(self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for XPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid XPath at column {}", self.column)
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+/// A node that a query matched, which borrows from the queried tree
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Node<'a> {
+    /// An element, or text, a comment or a processing instruction
+    Element(&'a Element),
+    Attribute(&'a QName, &'a str),
+}
+
+/// The result of an XPath expression, which is only a list of nodes for paths
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Nodes(Vec<Node<'a>>),
+    Number(f64),
+    String(String),
+    Boolean(bool),
+}
+
+fn number() -> impl for<'a> Parser<&'a str, Output = f64, Error = ()> + Copy {
+    (|input: &mut &str| {
+        let rest = input.trim_start();
+        let sign = usize::from(rest.starts_with('-'));
+        let len = rest[sign..]
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .map_or(rest.len(), |len| sign + len);
+
+        let number = rest[..len].parse().map_err(drop)?;
+        *input = &rest[len..];
+        Ok(number)
+    })
+    .as_parser_in_place()
+}
+
+fn comparison() -> impl for<'a> Parser<&'a str, Output = Comparison, Error = ()> {
+    // the two character operators go first, so that `<=` isn't read as `<`
+    token("!=")
+        .map(|()| Comparison::Ne)
+        .or(token("<=").map(|()| Comparison::Le))
+        .map(Either::into_inner)
+        .or(token(">=").map(|()| Comparison::Ge))
+        .map(Either::into_inner)
+        .or(token("=").map(|()| Comparison::Eq))
+        .map(Either::into_inner)
+        .or(token("<").map(|()| Comparison::Lt))
+        .map(Either::into_inner)
+        .or(token(">").map(|()| Comparison::Gt))
+        .map_both(Either::into_inner, drop)
+}
+
+/// `text()`, `comment()` or `node()`
+fn node_type(name: &'static str) -> impl for<'a> Parser<&'a str, Output = (), Error = ()> + Copy {
+    keyword(name)
+        .then(token("("))
+        .then(token(")"))
+        .map_both(drop, drop)
+}
+
+fn node_test() -> impl for<'a> Parser<&'a str, Output = NodeTest, Error = ()> {
+    node_type("text")
+        .map(|()| NodeTest::Text)
+        .or(node_type("comment").map(|()| NodeTest::Comment))
+        .map(Either::into_inner)
+        .or(node_type("node").map(|()| NodeTest::Node))
+        .map(Either::into_inner)
+        .or(token("*").map(|()| NodeTest::Any))
+        .map(Either::into_inner)
+        .or(name().map(NodeTest::Name))
+        .map_both(Either::into_inner, drop)
+}
+
+fn predicate(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    token("[")
+        .then(expr(depth.deeper()))
+        .then(token("]"))
+        .map_both(|(((), expr), ()): (((), Expr), ())| expr, drop)
+}
+
+fn step(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Step, Error = ()> {
+    token("@")
+        .then(node_test())
+        .map(|((), test): ((), NodeTest)| (Axis::Attribute, test))
+        .or(token(".").map(|()| (Axis::SelfNode, NodeTest::Node)))
+        .map(Either::into_inner)
+        .or(node_test().map(|test: NodeTest| (Axis::Child, test)))
+        .map(Either::into_inner)
+        .then(predicate(depth).zero_or_more(Vec::new))
+        .map_both(
+            |((axis, test), predicates): ((Axis, NodeTest), Vec<Expr>)| Step {
+                axis,
+                test,
+                predicates,
+            },
+            drop,
+        )
+}
+
+/// `true` for `//` and `false` for `/`
+fn separator() -> impl for<'a> Parser<&'a str, Output = bool, Error = ()> {
+    token("//")
+        .map(|()| true)
+        .or(token("/").map(|()| false))
+        .map_both(Either::into_inner, drop)
+}
+
+fn path(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Path, Error = ()> {
+    let descendant_or_self = || Step {
+        axis: Axis::DescendantOrSelf,
+        test: NodeTest::Node,
+        predicates: Vec::new(),
+    };
+
+    separator()
+        .optional()
+        .map(Result::ok)
+        .then(step(depth))
+        .map_err(drop)
+        .then(separator().then(step(depth)).zero_or_more(Vec::new))
+        .map_both(
+            move |((start, first), rest): ((Option<bool>, Step), Vec<_>)| {
+                let mut steps = Vec::new();
+
+                if start == Some(true) {
+                    steps.push(descendant_or_self());
+                }
+                steps.push(first);
+
+                for (descendant, step) in rest {
+                    if descendant {
+                        steps.push(descendant_or_self());
+                    }
+                    steps.push(step);
+                }
+
+                Path {
+                    absolute: start.is_some(),
+                    steps,
+                }
+            },
+            drop,
+        )
+}
+
+fn function_call(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    name()
+        .then(token("("))
+        .map(util::fst)
+        .then(
+            expr(depth.deeper())
+                .then(
+                    token(",")
+                        .then(expr(depth.deeper()))
+                        .map(util::snd)
+                        .zero_or_more(Vec::new),
+                )
+                .optional()
+                .map(|args: Result<(Expr, Vec<Expr>), _>| match args {
+                    Ok((first, mut rest)) => {
+                        rest.insert(0, first);
+                        rest
+                    }
+                    Err(_) => Vec::new(),
+                }),
+        )
+        .then(token(")"))
+        .map_err(drop)
+        .flat_map(|((name, args), ()): ((String, Vec<Expr>), ())| {
+            let function = match (name.as_str(), args.len()) {
+                ("count", 1) => Function::Count,
+                ("contains", 2) => Function::Contains,
+                ("starts-with", 2) => Function::StartsWith,
+                ("string-length", 0..=1) => Function::StringLength,
+                ("not", 1) => Function::Not,
+                ("position", 0) => Function::Position,
+                ("last", 0) => Function::Last,
+                _ => return Err(()),
+            };
+
+            Ok(Expr::Call(function, args))
+        })
+}
+
+fn primary(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    literal()
+        .map(Expr::Literal)
+        .or(number().map(Expr::Number))
+        .map(Either::into_inner)
+        .or(token("(")
+            .then(expr(depth.deeper()))
+            .then(token(")"))
+            .map(|(((), expr), ()): (((), Expr), ())| expr))
+        .map(Either::into_inner)
+        .or(function_call(depth))
+        .map(Either::into_inner)
+        .or(path(depth).map(Expr::Path))
+        .map_both(Either::into_inner, drop)
+}
+
+fn compare(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    primary(depth)
+        .then(comparison().then(primary(depth)).optional())
+        .map_both(
+            |(left, right): (Expr, Result<(Comparison, Expr), _>)| match right {
+                Ok((comparison, right)) => {
+                    Expr::Compare(Box::new(left), comparison, Box::new(right))
+                }
+                Err(_) => left,
+            },
+            drop,
+        )
+}
+
+fn and(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    compare(depth)
+        .then(
+            keyword("and")
+                .then(compare(depth))
+                .map(util::snd)
+                .zero_or_more(Vec::new),
+        )
+        .map_both(
+            |(first, rest): (Expr, Vec<Expr>)| {
+                rest.into_iter().fold(first, |left, right| {
+                    Expr::And(Box::new(left), Box::new(right))
+                })
+            },
+            drop,
+        )
+}
+
+fn or(depth: Depth) -> impl for<'a> Parser<&'a str, Output = Expr, Error = ()> {
+    and(depth)
+        .then(
+            keyword("or")
+                .then(and(depth))
+                .map(util::snd)
+                .zero_or_more(Vec::new),
+        )
+        .map_both(
+            |(first, rest): (Expr, Vec<Expr>)| {
+                rest.into_iter().fold(first, |left, right| {
+                    Expr::Or(Box::new(left), Box::new(right))
+                })
+            },
+            drop,
+        )
+}
+
+/// The type is spelled out for the same reason as `ElementParser`, since expressions
+/// hold paths which hold predicates which hold expressions
+type ExprParser = Box<dyn for<'a> Parser<&'a str, Output = Expr, Error = ()> + Send + Sync>;
+
+/// `depth` counts the predicates, arguments and parentheses that this expression is inside of
+fn expr(depth: Depth) -> ExprParser {
+    Box::new(defer(move || or(depth)).depth_limit(depth).map_err(drop))
+}
+
+impl FromStr for XPath {
+    type Err = XPathError;
+
+    fn from_str(s: &str) -> Result<Self, XPathError> {
+        let (rest, expr) = expr(Depth::limit(MAX_NESTING))
+            .then(eat_white_space())
+            .map(util::fst)
+            .parse(s);
+
+        match expr {
+            Ok(expr) if rest.is_empty() => Ok(Self { expr }),
+            _ => Err(XPathError {
+                column: s[..s.len() - rest.len()].chars().count() + 1,
+            }),
+        }
+    }
+}
+
+impl XPath {
+    /// Finds every node in `root` that this path matches, in document order
+    ///
+    /// Expressions that aren't paths, such as `count(a)`, match nothing
+    pub fn select<'a>(&self, root: &'a Element) -> Vec<Node<'a>> {
+        match self.evaluate(root) {
+            Value::Nodes(nodes) => nodes,
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn evaluate<'a>(&self, root: &'a Element) -> Value<'a> {
+        let evaluator = Evaluator::new(root);
+        let context = Context::Node(Node::Element(root));
+
+        match evaluator.evaluate(&self.expr, context, 1, 1) {
+            Partial::Nodes(nodes) => Value::Nodes(
+                nodes
+                    .into_iter()
+                    .filter_map(|node| match node {
+                        Context::Node(node) => Some(node),
+                        Context::Root(_) => None,
+                    })
+                    .collect(),
+            ),
+            Partial::Number(n) => Value::Number(n),
+            Partial::String(s) => Value::String(s),
+            Partial::Boolean(b) => Value::Boolean(b),
+        }
+    }
+}
+
+impl Node<'_> {
+    /// The text inside of an element, or the value of anything else
+    pub fn string_value(&self) -> String {
+        match self {
+            Node::Element(element) => text_content(element),
+            Node::Attribute(_, value) => (*value).to_string(),
+        }
+    }
+}
+
+fn text_content(element: &Element) -> String {
+    match element {
+        Element::Text(text) | Element::Comment(text) => text.clone(),
+        Element::ProcessingInstruction { data, .. } => data.clone(),
+        Element::Node { children, .. } => children
+            .iter()
+            .filter(|child| !matches!(child, Element::Comment(_)))
+            .filter(|child| !matches!(child, Element::ProcessingInstruction { .. }))
+            .map(text_content)
+            .collect(),
+    }
+}
+
+/// A node while a query is being evaluated, where the root is the document
+/// that the queried element stands in for
+#[derive(Debug, Clone, Copy)]
+enum Context<'a> {
+    Root(&'a Element),
+    Node(Node<'a>),
+}
+
+/// A `Value` whose nodes can still include the root
+#[derive(Debug, Clone)]
+enum Partial<'a> {
+    Nodes(Vec<Context<'a>>),
+    Number(f64),
+    String(String),
+    Boolean(bool),
+}
+
+struct Evaluator<'a> {
+    root: &'a Element,
+    /// The position of every element and attribute in document order, by their address
+    order: HashMap<*const (), usize>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(root: &'a Element) -> Self {
+        fn visit(element: &Element, order: &mut HashMap<*const (), usize>) {
+            let next = order.len() + 1;
+            order.insert(std::ptr::from_ref(element).cast(), next);
+
+            if let Element::Node {
+                attributes,
+                children,
+                ..
+            } = element
+            {
+                for (name, _) in attributes {
+                    let next = order.len() + 1;
+                    order.insert(std::ptr::from_ref(name).cast(), next);
+                }
+
+                for child in children {
+                    visit(child, order);
+                }
+            }
+        }
+
+        let mut order = HashMap::new();
+        visit(root, &mut order);
+        Self { root, order }
+    }
+
+    fn position(&self, context: Context<'a>) -> usize {
+        let address = match context {
+            Context::Root(_) => return 0,
+            Context::Node(Node::Element(element)) => std::ptr::from_ref(element).cast(),
+            Context::Node(Node::Attribute(name, _)) => std::ptr::from_ref(name).cast(),
+        };
+
+        self.order.get(&address).copied().unwrap_or(0)
+    }
+
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    fn evaluate(
+        &self,
+        expr: &Expr,
+        context: Context<'a>,
+        position: usize,
+        size: usize,
+    ) -> Partial<'a> {
+        let evaluate = |expr: &Expr| self.evaluate(expr, context, position, size);
+
+        match expr {
+            Expr::Or(left, right) => {
+                Partial::Boolean(evaluate(left).to_bool() || evaluate(right).to_bool())
+            }
+            Expr::And(left, right) => {
+                Partial::Boolean(evaluate(left).to_bool() && evaluate(right).to_bool())
+            }
+            Expr::Compare(left, comparison, right) => {
+                Partial::Boolean(comparison.holds(&evaluate(left), &evaluate(right)))
+            }
+            Expr::Literal(s) => Partial::String(s.clone()),
+            Expr::Number(n) => Partial::Number(*n),
+            Expr::Path(path) => Partial::Nodes(self.select(path, context)),
+            Expr::Call(function, args) => {
+                let args: Vec<_> = args.iter().map(evaluate).collect();
+                let string = |i: usize| args.get(i).map_or_else(String::new, ToString::to_string);
+
+                match function {
+                    Function::Count => match args.first() {
+                        Some(Partial::Nodes(nodes)) => Partial::Number(nodes.len() as f64),
+                        _ => Partial::Number(f64::NAN),
+                    },
+                    Function::Contains => Partial::Boolean(string(0).contains(&string(1))),
+                    Function::StartsWith => Partial::Boolean(string(0).starts_with(&string(1))),
+                    Function::StringLength => {
+                        let s = match args.first() {
+                            Some(arg) => arg.to_string(),
+                            None => context_string(context),
+                        };
+                        Partial::Number(s.chars().count() as f64)
+                    }
+                    Function::Not => Partial::Boolean(!args.first().is_some_and(Partial::to_bool)),
+                    Function::Position => Partial::Number(position as f64),
+                    Function::Last => Partial::Number(size as f64),
+                }
+            }
+        }
+    }
+
+    fn select(&self, path: &Path, context: Context<'a>) -> Vec<Context<'a>> {
+        let start = if path.absolute {
+            Context::Root(self.root)
+        } else {
+            context
+        };
+
+        path.steps.iter().fold(vec![start], |contexts, step| {
+            let mut selected = Vec::new();
+
+            for context in contexts {
+                let mut candidates = Vec::new();
+                match step.axis {
+                    Axis::Child => candidates.extend(children(context)),
+                    Axis::DescendantOrSelf => descendants_or_self(context, &mut candidates),
+                    Axis::Attribute => candidates.extend(attributes(context)),
+                    Axis::SelfNode => candidates.push(context),
+                }
+                candidates.retain(|&candidate| step.test.matches(candidate));
+
+                for predicate in &step.predicates {
+                    let size = candidates.len();
+                    let mut position = 0;
+
+                    candidates.retain(|&candidate| {
+                        position += 1;
+                        self.evaluate(predicate, candidate, position, size)
+                            .holds_at(position)
+                    });
+                }
+
+                selected.extend(candidates);
+            }
+
+            selected.sort_by_key(|&node| self.position(node));
+            selected.dedup_by_key(|&mut node| self.position(node));
+            selected
+        })
+    }
+}
+
+fn context_string(context: Context<'_>) -> String {
+    match context {
+        Context::Root(root) => text_content(root),
+        Context::Node(node) => node.string_value(),
+    }
+}
+
+fn children(context: Context<'_>) -> Vec<Context<'_>> {
+    match context {
+        Context::Root(root) => vec![Context::Node(Node::Element(root))],
+        Context::Node(Node::Element(Element::Node { children, .. })) => children
+            .iter()
+            .map(|child| Context::Node(Node::Element(child)))
+            .collect(),
+        Context::Node(_) => Vec::new(),
+    }
+}
+
+fn descendants_or_self<'a>(context: Context<'a>, out: &mut Vec<Context<'a>>) {
+    out.push(context);
+
+    for child in children(context) {
+        descendants_or_self(child, out);
+    }
+}
+
+/// The attributes of an element, leaving out namespace declarations as XPath does
+fn attributes(context: Context<'_>) -> Vec<Context<'_>> {
+    match context {
+        Context::Node(Node::Element(Element::Node { attributes, .. })) => attributes
+            .iter()
+            .filter(|(name, _)| {
+                name.prefix.as_deref() != Some("xmlns")
+                    && !(name.prefix.is_none() && name.local_name == "xmlns")
+            })
+            .map(|(name, value)| Context::Node(Node::Attribute(name, value)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl NodeTest {
+    fn matches(&self, context: Context<'_>) -> bool {
+        let node = match context {
+            Context::Root(_) => return *self == NodeTest::Node,
+            Context::Node(node) => node,
+        };
+
+        match (self, node) {
+            (NodeTest::Node, _)
+            | (NodeTest::Any, Node::Element(Element::Node { .. }) | Node::Attribute(..))
+            | (NodeTest::Text, Node::Element(Element::Text(_)))
+            | (NodeTest::Comment, Node::Element(Element::Comment(_))) => true,
+            (
+                NodeTest::Name(expected),
+                Node::Element(Element::Node { name, .. }) | Node::Attribute(name, _),
+            ) => name.qualified() == *expected,
+            _ => false,
+        }
+    }
+}
+
+impl Partial<'_> {
+    fn to_bool(&self) -> bool {
+        match self {
+            Partial::Nodes(nodes) => !nodes.is_empty(),
+            Partial::Number(n) => *n != 0.0 && !n.is_nan(),
+            Partial::String(s) => !s.is_empty(),
+            Partial::Boolean(b) => *b,
+        }
+    }
+
+    fn to_number(&self) -> f64 {
+        match self {
+            Partial::Number(n) => *n,
+            Partial::Boolean(b) => f64::from(u8::from(*b)),
+            _ => string_to_number(&self.to_string()),
+        }
+    }
+
+    /// A number in a predicate is compared to the position, anything else is taken as a boolean
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    fn holds_at(&self, position: usize) -> bool {
+        match self {
+            Partial::Number(n) => *n == position as f64,
+            _ => self.to_bool(),
+        }
+    }
+}
+
+/// Only plain decimal numbers are numbers in XPath, anything else is NaN
+fn string_to_number(s: &str) -> f64 {
+    let s = s.trim();
+    let digits = s.strip_prefix('-').unwrap_or(s);
+
+    if digits.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        s.parse().unwrap_or(f64::NAN)
+    } else {
+        f64::NAN
+    }
+}
+
+/// The XPath string value, where a list of nodes stands for the first of them
+impl fmt::Display for Partial<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Partial::Nodes(nodes) => match nodes.first() {
+                Some(&node) => f.write_str(&context_string(node)),
+                None => Ok(()),
+            },
+            Partial::Number(n) => write_number(f, *n),
+            Partial::String(s) => f.write_str(s),
+            Partial::Boolean(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+/// Formats the value as XPath's `string()` would, or each node on its own line
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nodes(nodes) => {
+                for (i, node) in nodes.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str("\n")?;
+                    }
+                    match node {
+                        Node::Element(element @ Element::Node { .. }) => write!(f, "{element}")?,
+                        node => f.write_str(&node.string_value())?,
+                    }
+                }
+                Ok(())
+            }
+            Value::Number(n) => write_number(f, *n),
+            Value::String(s) => f.write_str(s),
+            Value::Boolean(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+fn write_number(f: &mut fmt::Formatter<'_>, n: f64) -> fmt::Result {
+    if n.is_nan() {
+        f.write_str("NaN")
+    } else if n.is_infinite() {
+        f.write_str(if n > 0.0 { "Infinity" } else { "-Infinity" })
+    } else {
+        write!(f, "{n}")
+    }
+}
+
+impl Comparison {
+    /// Compares two values the way XPath does, where a list of nodes holds a comparison
+    /// if any of its nodes does
+    fn holds(self, a: &Partial<'_>, b: &Partial<'_>) -> bool {
+        match (a, b) {
+            (Partial::Nodes(a), Partial::Nodes(b)) => a.iter().any(|&a| {
+                let a = Partial::String(context_string(a));
+                b.iter()
+                    .any(|&b| self.holds(&a, &Partial::String(context_string(b))))
+            }),
+            (Partial::Nodes(_), Partial::Boolean(_)) | (Partial::Boolean(_), Partial::Nodes(_)) => {
+                self.holds_atoms(
+                    &Partial::Boolean(a.to_bool()),
+                    &Partial::Boolean(b.to_bool()),
+                )
+            }
+            (Partial::Nodes(a), b) => a
+                .iter()
+                .any(|&a| self.holds_atoms(&Partial::String(context_string(a)), b)),
+            (a, Partial::Nodes(b)) => b
+                .iter()
+                .any(|&b| self.holds_atoms(a, &Partial::String(context_string(b)))),
+            (a, b) => self.holds_atoms(a, b),
+        }
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn holds_atoms(self, a: &Partial<'_>, b: &Partial<'_>) -> bool {
+        // equality compares as booleans if either side is one, then as numbers if either
+        // side is one, and otherwise as strings, while order always compares as numbers
+        let ordering = match (self, a, b) {
+            (Comparison::Eq | Comparison::Ne, Partial::Boolean(_), _)
+            | (Comparison::Eq | Comparison::Ne, _, Partial::Boolean(_)) => {
+                Some(a.to_bool().cmp(&b.to_bool()))
+            }
+            (Comparison::Eq | Comparison::Ne, Partial::String(a), Partial::String(b)) => {
+                Some(a.cmp(b))
+            }
+            _ => a.to_number().partial_cmp(&b.to_number()),
+        };
+
+        match self {
+            Comparison::Eq => ordering == Some(Ordering::Equal),
+            // NaN is unequal to everything, itself included
+            Comparison::Ne => ordering != Some(Ordering::Equal),
+            Comparison::Lt => ordering == Some(Ordering::Less),
+            Comparison::Le => ordering == Some(Ordering::Less) || ordering == Some(Ordering::Equal),
+            Comparison::Gt => ordering == Some(Ordering::Greater),
+            Comparison::Ge => {
+                ordering == Some(Ordering::Greater) || ordering == Some(Ordering::Equal)
+            }
+        }
+    }
+}
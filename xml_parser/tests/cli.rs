@@ -0,0 +1,166 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the command line tool on `stdin`, returning its exit code, standard output and
+/// standard error
+fn run(args: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_xml_parser"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+const DOC: &str = r#"<a x="1"><b>t</b><b/></a>"#;
+
+#[test]
+fn output_modes() {
+    assert_eq!(
+        run(&[], DOC),
+        (
+            0,
+            "<a x=\"1\">\n  <b>t</b>\n  <b/>\n</a>\n".to_string(),
+            String::new()
+        )
+    );
+    assert_eq!(run(&["--compact"], DOC).1, format!("{}\n", DOC));
+    assert_eq!(run(&["--validate"], DOC), (0, String::new(), String::new()));
+    assert_eq!(run(&["--xpath", "count(b)"], DOC).1, "2\n");
+
+    let (code, stdout, stderr) = run(&["--xpath", "c"], DOC);
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert_eq!(stderr, "error: - has no nodes matching the expression\n");
+}
+
+#[test]
+fn from_json() {
+    let (code, json, _) = run(&["--to-json"], DOC);
+    assert_eq!(code, 0);
+    assert_eq!(
+        json,
+        "{\n  \"a\": {\n    \"@x\": \"1\",\n    \"b\": [\n      \"t\",\n      null\n    ]\n  }\n}\n"
+    );
+    // what `--to-json` writes reads back as the same document
+    assert_eq!(
+        run(&["--from-json", "--indent", "1"], &json).1,
+        "<a x=\"1\">\n <b>t</b>\n <b/>\n</a>\n"
+    );
+
+    let (code, stdout, stderr) = run(&["--from-json"], "[1");
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert_eq!(stderr, "error: -: expected `,` or `]` at line 1 column 3\n");
+
+    // JSON that doesn't describe an element
+    assert_eq!(run(&["--from-json"], "[1]").0, 1);
+    assert_eq!(run(&["--from-json"], DOC).0, 1);
+}
+
+#[test]
+fn events() {
+    let doc = "<a x=\"1&amp;&quot;\"><b/>t\nx<![CDATA[<c>]]><!--c--><?pi d e?></a>";
+
+    assert_eq!(
+        run(&["--events"], doc),
+        (
+            0,
+            [
+                r#"start a x="1&\"""#,
+                "start b",
+                "end b",
+                r#"text "t\nx""#,
+                r#"cdata "<c>""#,
+                r#"comment "c""#,
+                r#"pi pi "d e""#,
+                "end a",
+                "",
+            ]
+            .join("\n"),
+            String::new()
+        )
+    );
+
+    // the events before an error are printed, as they are read before it is found
+    assert_eq!(
+        run(&["--events"], "<a><b></a>"),
+        (
+            1,
+            "start a\nstart b\n".to_string(),
+            "error: -: expected `</b>`, found `</a>` at byte 6\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn dtd() {
+    let doc = r#"<!DOCTYPE a [<!ELEMENT a (b*)><!ELEMENT b EMPTY><!ATTLIST b k CDATA "d">]><a><b/><b k="x"/></a>"#;
+
+    // attributes that are left out get their default values
+    assert_eq!(
+        run(&["--dtd", "--compact"], doc),
+        (
+            0,
+            doc.replace("<b/>", r#"<b k="d"/>"#) + "\n",
+            String::new()
+        )
+    );
+    // without `--dtd` they don't
+    assert_eq!(run(&["--compact"], doc).1, format!("{}\n", doc));
+
+    let (code, stdout, stderr) = run(
+        &["--dtd"],
+        "<!DOCTYPE a [<!ELEMENT a (b)><!ELEMENT b EMPTY>]><a><c/></a>",
+    );
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    assert_eq!(
+        stderr,
+        "error: -: /a: content doesn't match `(b)`\nerror: -: /a/c: element is not declared\n"
+    );
+
+    assert_eq!(
+        run(&["--dtd"], "<a/>"),
+        (
+            1,
+            String::new(),
+            "error: - has no DTD to validate against\n".to_string()
+        )
+    );
+}
+
+#[test]
+fn errors() {
+    let (code, stdout, stderr) = run(&[], "<a>\n<b></a>");
+    assert_eq!((code, stdout.as_str()), (1, ""));
+    // the caret goes under the name of the wrong tag
+    assert_eq!(
+        stderr,
+        "error: expected `</b>`\n --> -:2:6\n  |\n2 | <b></a>\n  |      ^\n"
+    );
+
+    let (code, _, stderr) = run(&["--bogus"], DOC);
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: unknown option `--bogus`\n\nusage:"));
+
+    let (code, _, stderr) = run(&["--xpath", "a["], DOC);
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: invalid XPath at column 2"));
+
+    let (code, _, stderr) = run(&["missing.xml"], "");
+    assert_eq!(code, 2);
+    assert!(stderr.starts_with("error: could not read missing.xml: "));
+}
@@ -0,0 +1,306 @@
+use xml_parser::{parse_document, Dtd, Element, XPath};
+use xml_parser::{ValidationError, ValidationErrorKind};
+
+const LIBRARY: &str = r#"
+    <!ELEMENT library (shelf+, note?)>
+    <!ELEMENT shelf (book | magazine)*>
+    <!ELEMENT book (title, (author | editor)+, summary?)>
+    <!ELEMENT magazine (title)>
+    <!ELEMENT title (#PCDATA)>
+    <!ELEMENT author (#PCDATA)>
+    <!ELEMENT editor (#PCDATA)>
+    <!ELEMENT summary (#PCDATA | em)*>
+    <!ELEMENT em (#PCDATA)>
+    <!ELEMENT note ANY>
+    <!ELEMENT br EMPTY>
+    <!-- comments and entities are skipped -->
+    <!ENTITY publisher "Example">
+    <!ATTLIST library version CDATA #FIXED "2">
+    <!ATTLIST shelf label CDATA #IMPLIED>
+    <!ATTLIST book
+        id ID #REQUIRED
+        related IDREFS #IMPLIED
+        format (paper | ebook) "paper"
+        tags NMTOKENS #IMPLIED>
+    <!ATTLIST magazine issue NMTOKEN #REQUIRED>
+"#;
+
+/// `body` under a DOCTYPE with the `LIBRARY` DTD, and the errors of validating it
+fn validate(body: &str) -> Vec<(String, ValidationErrorKind)> {
+    let doc = format!("<!DOCTYPE library [{}]>{}", LIBRARY, body);
+    let document = parse_document(&doc).unwrap();
+    let dtd = document.dtd().unwrap().unwrap();
+
+    dtd.validate(&document)
+        .into_iter()
+        .map(|e| (e.path().to_string(), e.kind().clone()))
+        .collect()
+}
+
+fn invalid(path: &str, model: &str) -> (String, ValidationErrorKind) {
+    (
+        path.to_string(),
+        ValidationErrorKind::InvalidContent(model.to_string()),
+    )
+}
+
+#[test]
+fn valid_document() {
+    let body = r#"<library version="2">
+        <shelf label="fiction">
+            <book id="b1" related="b2" tags="classic novel">
+                <title>Emma</title><author>Austen</author>
+                <summary>A <em>match</em> maker</summary>
+            </book>
+            <magazine issue="12"><title>Weekly</title></magazine>
+        </shelf>
+        <shelf>
+            <book id="b2" format="ebook"><title>Anthology</title><editor>E</editor><author>A</author></book>
+        </shelf>
+        <note>anything <br/> goes <shelf/></note>
+    </library>"#;
+
+    assert_eq!(validate(body), vec![]);
+}
+
+#[test]
+fn content_models() {
+    // `shelf+` needs at least one
+    assert_eq!(
+        validate("<library/>"),
+        vec![invalid("/library", "(shelf+, note?)")]
+    );
+    // the order of a sequence matters
+    assert_eq!(
+        validate("<library><note/><shelf/></library>"),
+        vec![invalid("/library", "(shelf+, note?)")]
+    );
+    // `(author | editor)+` needs one of them, and `summary?` only once
+    let body = r#"<library><shelf>
+        <book id="a"><title>t</title></book>
+        <book id="b"><title>t</title><author>x</author><summary/><summary/></book>
+    </shelf></library>"#;
+    assert_eq!(
+        validate(body),
+        vec![
+            invalid(
+                "/library/shelf/book[1]",
+                "(title, (author | editor)+, summary?)"
+            ),
+            invalid(
+                "/library/shelf/book[2]",
+                "(title, (author | editor)+, summary?)"
+            ),
+        ]
+    );
+    // text isn't allowed between children, and mixed content only allows the listed elements
+    let body = r#"<library><shelf>text</shelf><shelf><book id="a"><title>t<em/></title><author/><summary>s <em/> <title/></summary></book></shelf></library>"#;
+    assert_eq!(
+        validate(body),
+        vec![
+            invalid("/library/shelf[1]", "(book | magazine)*"),
+            invalid("/library/shelf[2]/book/title", "(#PCDATA)"),
+            invalid("/library/shelf[2]/book/summary", "(#PCDATA | em)*"),
+        ]
+    );
+}
+
+#[test]
+fn empty_and_any() {
+    let body = "<library><shelf/><note><br>x</br><br/><undeclared/></note></library>";
+
+    assert_eq!(
+        validate(body),
+        vec![
+            invalid("/library/note/br[1]", "EMPTY"),
+            (
+                "/library/note/undeclared".to_string(),
+                ValidationErrorKind::UndeclaredElement
+            ),
+        ]
+    );
+}
+
+#[test]
+fn attributes() {
+    let body = r#"<library version="3" owner="me"><shelf>
+        <book><title>t</title><author/></book>
+        <book id="x" format="scroll"><title>t</title><author/></book>
+        <magazine issue="no spaces"><title/></magazine>
+    </shelf></library>"#;
+
+    assert_eq!(
+        validate(body),
+        vec![
+            (
+                "/library".to_string(),
+                ValidationErrorKind::FixedAttribute {
+                    name: "version".to_string(),
+                    expected: "2".to_string(),
+                }
+            ),
+            (
+                "/library".to_string(),
+                ValidationErrorKind::UndeclaredAttribute("owner".to_string())
+            ),
+            (
+                "/library/shelf/book[1]".to_string(),
+                ValidationErrorKind::MissingAttribute("id".to_string())
+            ),
+            (
+                "/library/shelf/book[2]".to_string(),
+                ValidationErrorKind::InvalidAttribute {
+                    name: "format".to_string(),
+                    value: "scroll".to_string(),
+                }
+            ),
+            (
+                "/library/shelf/magazine".to_string(),
+                ValidationErrorKind::InvalidAttribute {
+                    name: "issue".to_string(),
+                    value: "no spaces".to_string(),
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn ids_and_references() {
+    let body = r#"<library><shelf>
+        <book id="a" related="b c"><title/><author/></book>
+        <book id="a"><title/><author/></book>
+        <book id="1x"><title/><author/></book>
+    </shelf></library>"#;
+
+    assert_eq!(
+        validate(body),
+        vec![
+            (
+                "/library/shelf/book[2]".to_string(),
+                ValidationErrorKind::DuplicateId("a".to_string())
+            ),
+            // an ID has to be a name, which can't start with a digit
+            (
+                "/library/shelf/book[3]".to_string(),
+                ValidationErrorKind::InvalidAttribute {
+                    name: "id".to_string(),
+                    value: "1x".to_string(),
+                }
+            ),
+            // references are checked once every ID is known
+            (
+                "/library/shelf/book[1]".to_string(),
+                ValidationErrorKind::UnknownId("b".to_string())
+            ),
+            (
+                "/library/shelf/book[1]".to_string(),
+                ValidationErrorKind::UnknownId("c".to_string())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn root_has_to_match_the_doctype() {
+    let document =
+        parse_document("<!DOCTYPE a [<!ELEMENT a EMPTY><!ELEMENT b EMPTY>]><b/>").unwrap();
+    let errors = document.dtd().unwrap().unwrap().validate(&document);
+
+    assert_eq!(
+        errors
+            .iter()
+            .map(ValidationError::to_string)
+            .collect::<Vec<_>>(),
+        vec!["/b: the DOCTYPE expects `a` as the root element"]
+    );
+}
+
+#[test]
+fn error_paths_are_xpath_queries() {
+    let body = r#"<library><shelf/><shelf><book id="a"><title/></book></shelf></library>"#;
+    let doc = format!("<!DOCTYPE library [{}]>{}", LIBRARY, body);
+    let document = parse_document(&doc).unwrap();
+    let errors = document.dtd().unwrap().unwrap().validate(&document);
+
+    assert_eq!(errors.len(), 1);
+    let path: XPath = errors[0].path().parse().unwrap();
+    let found = path.select(&document.root);
+    assert_eq!(found.len(), 1);
+}
+
+#[test]
+fn defaults_are_applied() {
+    let doc = format!(
+        "<!DOCTYPE library [{}]><library><shelf><book id='a' format='ebook'/><book id='b'/></shelf></library>",
+        LIBRARY
+    );
+    let mut document = parse_document(&doc).unwrap();
+    let dtd = document.dtd().unwrap().unwrap();
+    dtd.apply_defaults(&mut document.root);
+
+    let attributes = |element: &Element| match element {
+        Element::Node { attributes, .. } => attributes
+            .iter()
+            .map(|(name, value)| (name.qualified(), value.clone()))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let children = |element: &Element| match element {
+        Element::Node { children, .. } => children.clone(),
+        _ => Vec::new(),
+    };
+
+    let library = &document.root;
+    let books = children(&children(library)[0]);
+
+    assert_eq!(
+        attributes(library),
+        vec![("version".to_string(), "2".to_string())]
+    );
+    // a value that is given is kept, and implied attributes aren't added
+    assert_eq!(
+        attributes(&books[0]),
+        vec![
+            ("id".to_string(), "a".to_string()),
+            ("format".to_string(), "ebook".to_string()),
+        ]
+    );
+    assert_eq!(
+        attributes(&books[1]),
+        vec![
+            ("id".to_string(), "b".to_string()),
+            ("format".to_string(), "paper".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn malformed_declarations() {
+    for (subset, declaration) in [
+        ("<!ELEMENT a (b, c | d)>", "<!ELEMENT a (b, c | d)>"),
+        ("<!ELEMENT a (b>", "<!ELEMENT a (b>"),
+        ("<!ELEMENT a EMPTY><!ELEMENT>", "<!ELEMENT>"),
+        (
+            "<!ATTLIST a b WHAT #IMPLIED>",
+            "<!ATTLIST a b WHAT #IMPLIED>",
+        ),
+        ("<!ELEMENT a EMPTY", "<!ELEMENT a EMPTY"),
+        ("<!OTHER a>", "<!OTHER a>"),
+    ] {
+        let e = subset.parse::<Dtd>().unwrap_err();
+        assert_eq!(e.declaration(), declaration, "{}", subset);
+    }
+
+    assert_eq!(
+        "<!ELEMENT a (b, c | d)>"
+            .parse::<Dtd>()
+            .unwrap_err()
+            .to_string(),
+        "invalid declaration `<!ELEMENT a (b, c | d)>`"
+    );
+    // parameter entities are skipped, along with what they would declare
+    assert!("<!ENTITY % p 'x'> %p; <!ELEMENT a EMPTY>"
+        .parse::<Dtd>()
+        .is_ok());
+}
@@ -0,0 +1,162 @@
+use xml_parser::{parse_document, parse_document_with, Element, Entities, ParseOptions};
+use xml_parser::{EntityErrorKind, ErrorKind};
+
+/// The value of the first attribute of the root element, and its text
+fn decoded(doc: &str) -> (String, String) {
+    decoded_with(doc, &ParseOptions::default())
+}
+
+fn decoded_with(doc: &str, options: &ParseOptions) -> (String, String) {
+    let document = parse_document_with(doc, options).unwrap();
+    let Element::Node {
+        attributes,
+        children,
+        ..
+    } = document.root
+    else {
+        panic!("the root isn't a node");
+    };
+
+    let text = children
+        .iter()
+        .map(|child| match child {
+            Element::Text(text) => text.as_str(),
+            _ => "",
+        })
+        .collect();
+
+    (attributes[0].1.clone(), text)
+}
+
+fn entity_error(doc: &str) -> (EntityErrorKind, usize) {
+    let e = parse_document(doc).unwrap_err();
+
+    match e.kind() {
+        ErrorKind::Entity(kind) => (kind.clone(), e.offset()),
+        kind => panic!("expected an entity error, got {:?}", kind),
+    }
+}
+
+#[test]
+fn predefined_entities() {
+    let doc = r#"<a v="&lt;&gt;&amp;&quot;&apos;">&lt;&gt;&amp;&quot;&apos;</a>"#;
+
+    assert_eq!(decoded(doc), ("<>&\"'".to_string(), "<>&\"'".to_string()));
+}
+
+#[test]
+fn character_references() {
+    let doc = "<a v=\"&#65;&#x42;&#x1F600;\">&#233;t&#xE9;</a>";
+
+    assert_eq!(
+        decoded(doc),
+        ("AB\u{1F600}".to_string(), "\u{e9}t\u{e9}".to_string())
+    );
+}
+
+#[test]
+fn references_are_only_decoded_once() {
+    // `&amp;lt;` is the text `&lt;`, not `<`
+    assert_eq!(
+        decoded(r#"<a v="&amp;lt;">&amp;amp;</a>"#),
+        ("&lt;".to_string(), "&amp;".to_string())
+    );
+}
+
+#[test]
+fn unknown_entity_is_reported_at_its_ampersand() {
+    let doc = r#"<a v="x &nbsp; y"/>"#;
+
+    assert_eq!(
+        entity_error(doc),
+        (
+            EntityErrorKind::Unknown("nbsp".to_string()),
+            doc.find('&').unwrap()
+        )
+    );
+
+    let doc = "<a v=\"\">x &copy;</a>";
+    assert_eq!(
+        entity_error(doc),
+        (
+            EntityErrorKind::Unknown("copy".to_string()),
+            doc.find('&').unwrap()
+        )
+    );
+}
+
+#[test]
+fn malformed_references() {
+    let doc = r#"<a v="fish & chips"/>"#;
+    assert_eq!(
+        entity_error(doc),
+        (EntityErrorKind::Malformed, doc.find('&').unwrap())
+    );
+
+    // characters that XML doesn't allow, and references that aren't numbers
+    for (reference, name) in [
+        ("&#0;", "#0"),
+        ("&#xD800;", "#xD800"),
+        ("&#x110000;", "#x110000"),
+        ("&#12a;", "#12a"),
+        ("&#;", "#"),
+        // only a lowercase `x` starts a hexadecimal reference
+        ("&#X43;", "#X43"),
+    ] {
+        let doc = format!("<a v=\"{}\"/>", reference);
+        assert_eq!(
+            entity_error(&doc),
+            (EntityErrorKind::InvalidChar(name.to_string()), 6),
+            "{}",
+            reference
+        );
+    }
+}
+
+#[test]
+fn entities_of_the_internal_subset() {
+    let doc = r#"<!DOCTYPE a [
+        <!ENTITY company "Example &amp; Co">
+        <!ENTITY % parameter "skipped">
+    ]>
+    <a v="&company;">by &company;</a>"#;
+
+    // the replacement text is put in as is, without decoding it again
+    assert_eq!(
+        decoded(doc),
+        (
+            "Example &amp; Co".to_string(),
+            "by Example &amp; Co".to_string()
+        )
+    );
+
+    let options = ParseOptions {
+        internal_entities: false,
+        ..ParseOptions::default()
+    };
+    let e = parse_document_with(doc, &options).unwrap_err();
+    assert_eq!(
+        e.kind(),
+        &ErrorKind::Entity(EntityErrorKind::Unknown("company".to_string()))
+    );
+
+    assert_eq!(
+        entity_error(r#"<!DOCTYPE a [<!ENTITY % p "x">]><a v="&p;"/>"#).0,
+        EntityErrorKind::Unknown("p".to_string())
+    );
+}
+
+#[test]
+fn entities_declared_up_front() {
+    let mut entities = Entities::default();
+    entities.declare("nbsp".to_string(), "\u{a0}".to_string());
+    let options = ParseOptions {
+        entities,
+        ..ParseOptions::default()
+    };
+
+    assert_eq!(
+        decoded_with(r#"<a v="&nbsp;">&nbsp;</a>"#, &options),
+        ("\u{a0}".to_string(), "\u{a0}".to_string())
+    );
+}
@@ -0,0 +1,77 @@
+use xml_parser::{parse_document, EntityErrorKind, ErrorKind};
+use xml_parser::{ReadError, SyntaxError, XmlReader};
+
+/// `<a>` nested `depth` times around `inner`
+fn nested(depth: usize, inner: &str) -> String {
+    format!("{}{}{}", "<a>".repeat(depth), inner, "</a>".repeat(depth))
+}
+
+#[test]
+fn malformed_child_is_reported_where_it_is() {
+    let e = parse_document("<r><a>text</a><b x=1/></r>").unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::UnclosedTag);
+    assert_eq!(e.offset(), "<r><a>text</a><b ".len());
+}
+
+#[test]
+fn unclosed_element_is_reported_at_the_end_of_its_content() {
+    let e = parse_document("<r><a>text</r>").unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::WrongCloseTag("a".to_string()));
+
+    let e = parse_document("<r>text").unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::UnclosedElement("r".to_string()));
+    assert_eq!(e.offset(), "<r>text".len());
+}
+
+#[test]
+fn deep_malformed_document_fails_quickly() {
+    // every level used to parse the malformed child again to find its error, which took
+    // twice as long for each level of nesting
+    let doc = nested(30, "<b x=1/>");
+    let e = parse_document(&doc).unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::UnclosedTag);
+    assert_eq!(e.offset(), "<a>".len() * 30 + "<b ".len());
+}
+
+#[test]
+fn less_than_in_attribute_value_is_rejected() {
+    let docs = [
+        (r#"<p a="<"/>"#, r#"<p a=""#),
+        (r#"<p a="x<y">text</p>"#, r#"<p a="x"#),
+        ("<r><p b='' a='<'/></r>", "<r><p b='' a='"),
+    ];
+
+    for &(doc, before) in &docs {
+        let e = parse_document(doc).unwrap_err();
+
+        assert_eq!(
+            e.kind(),
+            &ErrorKind::Entity(EntityErrorKind::LessThan),
+            "{}",
+            doc
+        );
+        assert_eq!(e.offset(), before.len(), "{}", doc);
+    }
+
+    assert!(parse_document(r#"<p a="&lt;" b=">"/>"#).is_ok());
+
+    let doc = r#"<r><p a="x<y"/></r>"#;
+    let e = XmlReader::new(doc.as_bytes())
+        .find_map(Result::err)
+        .unwrap();
+
+    match e {
+        ReadError::Syntax { kind, offset } => {
+            assert!(matches!(
+                kind,
+                SyntaxError::Entity(EntityErrorKind::LessThan)
+            ));
+            assert_eq!(offset, doc.find("x<").unwrap() + 1);
+        }
+        ReadError::Io(e) => panic!("{}", e),
+    }
+}
@@ -0,0 +1,117 @@
+//! Round trips between XML and JSON, for what survives them and for each of the losses
+//! documented on `Element::to_json`
+
+use json_parser::{parse_json, JsonValue};
+use xml_parser::{parse_document, Element};
+
+fn xml(doc: &str) -> Element {
+    parse_document(doc).unwrap().root
+}
+
+fn json(doc: &str) -> JsonValue {
+    parse_json(doc).unwrap()
+}
+
+/// Converts the element to JSON and back
+fn xml_round_trip(doc: &str) -> String {
+    Element::from_json(&xml(doc).to_json()).unwrap().to_string()
+}
+
+/// Converts the JSON to an element and back
+fn json_round_trip(doc: &str) -> JsonValue {
+    Element::from_json(&json(doc)).unwrap().to_json()
+}
+
+#[test]
+fn structure_survives() {
+    for doc in &[
+        "<a/>",
+        "<a>text</a>",
+        r#"<a href="/">home</a>"#,
+        "<ul><li>1</li><li>2</li><li/></ul>",
+        // attributes are in order of their names, which is the order they come back in
+        r#"<x:a x:id="1" xmlns:x="urn:x"><x:b>text</x:b></x:a>"#,
+    ] {
+        assert_eq!(xml_round_trip(doc), *doc);
+        assert_eq!(Element::from_json(&xml(doc).to_json()).unwrap(), xml(doc));
+    }
+
+    assert_eq!(
+        xml("<ul><li>1</li><li>2</li></ul>").to_json(),
+        json(r#"{"ul": {"li": ["1", "2"]}}"#)
+    );
+    assert_eq!(
+        xml(r#"<a href="/">home</a>"#).to_json(),
+        json(r##"{"a": {"@href": "/", "#text": "home"}}"##)
+    );
+}
+
+#[test]
+fn namespaces_survive() {
+    let doc = r#"<a xmlns="urn:d" xmlns:p="urn:p"><p:b p:c="1"/></a>"#;
+    let back = Element::from_json(&xml(doc).to_json()).unwrap();
+
+    assert_eq!(back, xml(doc));
+    match back {
+        Element::Node { name, children, .. } => {
+            assert_eq!(name.namespace_uri.as_deref(), Some("urn:d"));
+            match &children[0] {
+                Element::Node { name, .. } => {
+                    assert_eq!(name.namespace_uri.as_deref(), Some("urn:p"))
+                }
+                child => panic!("{:?}", child),
+            }
+        }
+        root => panic!("{:?}", root),
+    }
+}
+
+#[test]
+fn comments_and_processing_instructions_are_lost() {
+    assert_eq!(
+        xml_round_trip("<a><!-- note --><?pi data?><b/></a>"),
+        "<a><b/></a>"
+    );
+}
+
+#[test]
+fn order_of_different_children_is_lost() {
+    assert_eq!(xml_round_trip("<a><c/><b/><c/></a>"), "<a><b/><c/><c/></a>");
+}
+
+#[test]
+fn order_of_attributes_is_lost() {
+    assert_eq!(xml_round_trip(r#"<a z="1" y="2"/>"#), r#"<a y="2" z="1"/>"#);
+}
+
+#[test]
+fn mixed_text_is_joined_and_moved_first() {
+    assert_eq!(
+        xml_round_trip("<p>Hello <b>world</b>!</p>"),
+        "<p>Hello !<b>world</b></p>"
+    );
+    assert_eq!(
+        xml_round_trip("<list>\n  <item/>\n  <item/>\n</list>"),
+        "<list><item/><item/></list>"
+    );
+}
+
+#[test]
+fn single_item_arrays_are_lost() {
+    assert_eq!(
+        json_round_trip(r#"{"ul": {"li": ["1"]}}"#),
+        json(r#"{"ul": {"li": "1"}}"#)
+    );
+    assert_eq!(
+        json_round_trip(r#"{"ul": {"li": ["1", "2"]}}"#),
+        json(r#"{"ul": {"li": ["1", "2"]}}"#)
+    );
+}
+
+#[test]
+fn numbers_and_bools_become_strings() {
+    assert_eq!(
+        json_round_trip(r#"{"a": {"@n": 1.5, "b": true, "c": [1, false]}}"#),
+        json(r#"{"a": {"@n": "1.5", "b": "true", "c": ["1", "false"]}}"#)
+    );
+}
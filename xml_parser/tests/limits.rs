@@ -0,0 +1,162 @@
+use xml_parser::{parse_document, parse_document_with, ErrorKind, Limits, ParseOptions};
+use xml_parser::{ReadError, SyntaxError, XmlReader};
+
+fn nested(depth: usize) -> String {
+    format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth))
+}
+
+fn limited(doc: &str, limits: Limits) -> Result<(), ErrorKind> {
+    let options = ParseOptions {
+        limits,
+        ..ParseOptions::default()
+    };
+
+    parse_document_with(doc, &options)
+        .map(drop)
+        .map_err(|e| e.kind().clone())
+}
+
+fn read(doc: &str, limits: Limits) -> Result<(), SyntaxError> {
+    let options = ParseOptions {
+        limits,
+        ..ParseOptions::default()
+    };
+
+    for event in XmlReader::with_options(doc.as_bytes(), options) {
+        match event {
+            Ok(_) => (),
+            Err(ReadError::Syntax { kind, .. }) => return Err(kind),
+            Err(ReadError::Io(e)) => panic!("{}", e),
+        }
+    }
+
+    Ok(())
+}
+
+fn depth(max_depth: usize) -> Limits {
+    Limits {
+        max_depth,
+        ..Limits::default()
+    }
+}
+
+fn items(max_items: usize) -> Limits {
+    Limits {
+        max_items,
+        ..Limits::default()
+    }
+}
+
+#[test]
+fn default_depth_limit() {
+    let max = Limits::default().max_depth;
+
+    assert!(parse_document(&nested(max)).is_ok());
+
+    let e = parse_document(&nested(max + 1)).unwrap_err();
+    assert_eq!(e.kind(), &ErrorKind::DepthExceeded);
+    assert_eq!(e.offset(), max * "<a>".len());
+}
+
+#[test]
+fn depth_limit() {
+    assert_eq!(limited(&nested(3), depth(3)), Ok(()));
+    assert_eq!(limited(&nested(4), depth(3)), Err(ErrorKind::DepthExceeded));
+    // an element that closes itself is a level too
+    assert_eq!(limited("<a><a><a/></a></a>", depth(3)), Ok(()));
+    assert_eq!(
+        limited("<a><a><a><a/></a></a></a>", depth(3)),
+        Err(ErrorKind::DepthExceeded)
+    );
+    assert_eq!(limited("<a/>", depth(0)), Err(ErrorKind::DepthExceeded));
+
+    assert!(read(&nested(3), depth(3)).is_ok());
+    assert!(matches!(
+        read(&nested(4), depth(3)),
+        Err(SyntaxError::DepthExceeded)
+    ));
+    assert!(matches!(
+        read("<a><a><a><a/></a></a></a>", depth(3)),
+        Err(SyntaxError::DepthExceeded)
+    ));
+}
+
+#[test]
+fn depth_is_not_bounded_by_the_stack() {
+    // levels past what a thread's stack holds carry on in new segments of stack
+    assert_eq!(limited(&nested(1000), depth(1000)), Ok(()));
+    assert_eq!(
+        limited(&nested(1001), depth(1000)),
+        Err(ErrorKind::DepthExceeded)
+    );
+}
+
+#[test]
+fn hostile_depth_is_rejected_instead_of_overflowing() {
+    let e = parse_document(&nested(100_000)).unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::DepthExceeded);
+}
+
+#[test]
+fn child_limit() {
+    assert_eq!(limited("<r><a/><b/></r>", items(2)), Ok(()));
+    assert_eq!(
+        limited("<r><a/><b/><c/></r>", items(2)),
+        Err(ErrorKind::TooManyItems)
+    );
+    // text and CDATA next to each other make up a single child
+    assert_eq!(limited("<r>a<![CDATA[b]]>c<b/></r>", items(2)), Ok(()));
+    assert_eq!(
+        limited("<r>a<b/>c</r>", items(2)),
+        Err(ErrorKind::TooManyItems)
+    );
+    assert_eq!(limited("<r></r>", items(0)), Ok(()));
+    assert_eq!(
+        limited("<r>text</r>", items(0)),
+        Err(ErrorKind::TooManyItems)
+    );
+
+    let options = ParseOptions {
+        limits: items(2),
+        ..ParseOptions::default()
+    };
+    let e = parse_document_with("<r><a/><b/><c/></r>", &options).unwrap_err();
+    assert_eq!(e.offset(), "<r><a/><b/>".len());
+}
+
+#[test]
+fn attribute_limit() {
+    assert_eq!(limited(r#"<r a="1" b="2"/>"#, items(2)), Ok(()));
+    assert_eq!(limited(r#"<r a="1" b="2">x</r>"#, items(2)), Ok(()));
+    assert_eq!(
+        limited(r#"<r a="1" b="2" c="3"/>"#, items(2)),
+        Err(ErrorKind::TooManyItems)
+    );
+    assert_eq!(
+        limited(r#"<r a="1" b="2" c="3">x</r>"#, items(2)),
+        Err(ErrorKind::TooManyItems)
+    );
+
+    assert!(read(r#"<r a="1" b="2"/>"#, items(2)).is_ok());
+    assert!(matches!(
+        read(r#"<r a="1" b="2" c="3"/>"#, items(2)),
+        Err(SyntaxError::TooManyItems)
+    ));
+}
+
+#[test]
+fn length_limit() {
+    let limits = Limits {
+        max_len: "<r>text</r>".len(),
+        ..Limits::default()
+    };
+
+    assert_eq!(limited("<r>text</r>", limits), Ok(()));
+    assert_eq!(limited("<r>texts</r>", limits), Err(ErrorKind::TooLong));
+    assert!(read("<r>text</r>", limits).is_ok());
+    assert!(matches!(
+        read("<r>texts</r>", limits),
+        Err(SyntaxError::TooLong)
+    ));
+}
@@ -0,0 +1,224 @@
+use xml_parser::{parse_document, Element, ErrorKind, NamespaceError, QName};
+use xml_parser::{ReadError, XmlReader};
+
+const SOAP: &str = "http://www.w3.org/2003/05/soap-envelope";
+const SVG: &str = "http://www.w3.org/2000/svg";
+const XLINK: &str = "http://www.w3.org/1999/xlink";
+
+fn root(doc: &str) -> Element {
+    parse_document(doc).unwrap().root
+}
+
+/// The names of `element`, its attributes and its child nodes, in order
+fn names(element: &Element) -> Vec<QName> {
+    let mut names = Vec::new();
+
+    if let Element::Node {
+        name,
+        attributes,
+        children,
+    } = element
+    {
+        names.push(name.clone());
+        names.extend(attributes.iter().map(|(name, _)| name.clone()));
+        for child in children {
+            names.extend(self::names(child));
+        }
+    }
+
+    names
+}
+
+/// `(namespace, local name)` pairs, which are how names compare in XML
+fn expanded(names: &[QName]) -> Vec<(Option<&str>, &str)> {
+    names
+        .iter()
+        .map(|name| (name.namespace_uri.as_deref(), name.local_name.as_str()))
+        .collect()
+}
+
+fn namespace_error(doc: &str) -> NamespaceError {
+    match parse_document(doc).unwrap_err().kind() {
+        ErrorKind::Namespace(e) => e.clone(),
+        kind => panic!("expected a namespace error, got {:?}", kind),
+    }
+}
+
+#[test]
+fn prefixed_names() {
+    let doc = format!(
+        r#"<soap:Envelope xmlns:soap="{}"><soap:Body soap:role="next"/></soap:Envelope>"#,
+        SOAP
+    );
+    let names = names(&root(&doc));
+
+    assert_eq!(names[0].prefix.as_deref(), Some("soap"));
+    assert_eq!(names[0].qualified(), "soap:Envelope");
+    assert_eq!(
+        expanded(&names),
+        vec![
+            (Some(SOAP), "Envelope"),
+            (Some("http://www.w3.org/2000/xmlns/"), "soap"),
+            (Some(SOAP), "Body"),
+            (Some(SOAP), "role"),
+        ]
+    );
+}
+
+#[test]
+fn default_namespace_is_for_elements_only() {
+    let doc = format!(
+        r#"<svg xmlns="{}" xmlns:xlink="{}" width="1"><use xlink:href="a"/></svg>"#,
+        SVG, XLINK
+    );
+
+    assert_eq!(
+        expanded(&names(&root(&doc))),
+        vec![
+            (Some(SVG), "svg"),
+            (Some("http://www.w3.org/2000/xmlns/"), "xmlns"),
+            (Some("http://www.w3.org/2000/xmlns/"), "xlink"),
+            // attributes without a prefix aren't in any namespace
+            (None, "width"),
+            (Some(SVG), "use"),
+            (Some(XLINK), "href"),
+        ]
+    );
+}
+
+#[test]
+fn declarations_are_scoped() {
+    let doc = r#"<a xmlns="urn:1" xmlns:p="urn:p1">
+        <p:b xmlns:p="urn:p2"><p:c/></p:b>
+        <p:d/>
+        <e xmlns=""><f/></e>
+        <g/>
+    </a>"#;
+    let names: Vec<_> = names(&root(doc))
+        .into_iter()
+        .filter(|name| !name.qualified().starts_with("xmlns"))
+        .collect();
+
+    assert_eq!(
+        expanded(&names),
+        vec![
+            (Some("urn:1"), "a"),
+            (Some("urn:p2"), "b"),
+            (Some("urn:p2"), "c"),
+            (Some("urn:p1"), "d"),
+            // `xmlns=""` takes the default namespace away again
+            (None, "e"),
+            (None, "f"),
+            (Some("urn:1"), "g"),
+        ]
+    );
+}
+
+#[test]
+fn xml_prefix_is_always_declared() {
+    let names = names(&root(r#"<a xml:lang="en"/>"#));
+
+    assert_eq!(
+        names[1].namespace_uri.as_deref(),
+        Some("http://www.w3.org/XML/1998/namespace")
+    );
+}
+
+#[test]
+fn undeclared_prefixes_are_errors() {
+    assert_eq!(
+        namespace_error("<soap:Envelope/>"),
+        NamespaceError::UndeclaredPrefix("soap".to_string())
+    );
+    assert_eq!(
+        namespace_error(r#"<a p:x="1"/>"#),
+        NamespaceError::UndeclaredPrefix("p".to_string())
+    );
+    // a declaration only holds inside of the element that makes it
+    assert_eq!(
+        namespace_error(r#"<a><b xmlns:p="urn:p"/><p:c/></a>"#),
+        NamespaceError::UndeclaredPrefix("p".to_string())
+    );
+}
+
+#[test]
+fn invalid_declarations_and_names() {
+    assert_eq!(
+        namespace_error(r#"<a xmlns:p=""/>"#),
+        NamespaceError::EmptyDeclaration("p".to_string())
+    );
+    assert_eq!(
+        namespace_error(r#"<a xmlns:p="urn:x" xmlns:q="urn:x" p:b="1" q:b="2"/>"#),
+        NamespaceError::DuplicateAttribute("q:b".to_string())
+    );
+    assert_eq!(
+        namespace_error(r#"<a:b:c xmlns:a="urn:a"/>"#),
+        NamespaceError::InvalidName("a:b:c".to_string())
+    );
+    assert_eq!(
+        namespace_error("<a :b='1'/>"),
+        NamespaceError::InvalidName(":b".to_string())
+    );
+}
+
+#[test]
+fn errors_point_at_their_start_tag() {
+    let offset = |doc: &str| parse_document(doc).unwrap_err().offset();
+    let read_offset = |doc: &str| {
+        XmlReader::new(doc.as_bytes())
+            .find_map(|event| match event {
+                Err(ReadError::Syntax { offset, .. }) => Some(offset),
+                _ => None,
+            })
+            .unwrap()
+    };
+
+    for (doc, start) in [
+        ("<p:a/>", "".len()),
+        (
+            "<a>\n  <b/>\n  <p:c>text</p:c>\n</a>",
+            "<a>\n  <b/>\n  ".len(),
+        ),
+        (
+            r#"<a><b xmlns:p="urn:p"/><c p:x="1"/></a>"#,
+            r#"<a><b xmlns:p="urn:p"/>"#.len(),
+        ),
+        (r#"<a><b><c xmlns:p=""/></b></a>"#, "<a><b>".len()),
+        // the first element that can't be resolved, even if it holds others that can't be
+        ("<a><p:b><q:c/></p:b></a>", "<a>".len()),
+    ] {
+        assert_eq!(offset(doc), start, "{}", doc);
+        assert_eq!(read_offset(doc), start, "{}", doc);
+    }
+
+    let e = parse_document("<a>\n  <p:b/>\n</a>").unwrap_err();
+    assert_eq!((e.line(), e.column()), (2, 3));
+}
+
+#[test]
+fn errors_come_in_document_order() {
+    // a namespace error before a syntax error is the one that is reported
+    assert_eq!(
+        namespace_error("<a><p:b/><c></a>"),
+        NamespaceError::UndeclaredPrefix("p".to_string())
+    );
+    assert_eq!(
+        namespace_error("<p:a><b></p:a>"),
+        NamespaceError::UndeclaredPrefix("p".to_string())
+    );
+    // and after one, it isn't
+    assert!(matches!(
+        parse_document("<a><c></d><p:b/></a>").unwrap_err().kind(),
+        ErrorKind::WrongCloseTag(_)
+    ));
+}
+
+#[test]
+fn names_with_dots_underscores_and_dashes() {
+    let doc = r#"<my.doc xmlns:x_y="urn:x"><x_y:a-b.c_d/></my.doc>"#;
+    let names = names(&root(doc));
+
+    assert_eq!(names[0].qualified(), "my.doc");
+    assert_eq!(names[2].qualified(), "x_y:a-b.c_d");
+    assert_eq!(names[2].namespace_uri.as_deref(), Some("urn:x"));
+}
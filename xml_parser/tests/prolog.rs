@@ -0,0 +1,191 @@
+use xml_parser::{parse_document, Declaration, Doctype, Element, ErrorKind};
+
+fn pi(target: &str, data: &str) -> Element {
+    Element::ProcessingInstruction {
+        target: target.to_string(),
+        data: data.to_string(),
+    }
+}
+
+fn error(doc: &str) -> (ErrorKind, usize) {
+    let e = parse_document(doc).unwrap_err();
+    (e.kind().clone(), e.offset())
+}
+
+#[test]
+fn declaration() {
+    let document = parse_document(r#"<?xml version="1.0"?><a/>"#).unwrap();
+    assert_eq!(
+        document.declaration,
+        Some(Declaration {
+            version: "1.0".to_string(),
+            encoding: None,
+            standalone: None,
+        })
+    );
+
+    let doc = "<?xml version='1.1' encoding=\"UTF-8\" standalone='yes' ?>\n<a/>";
+    assert_eq!(
+        parse_document(doc).unwrap().declaration,
+        Some(Declaration {
+            version: "1.1".to_string(),
+            encoding: Some("UTF-8".to_string()),
+            standalone: Some(true),
+        })
+    );
+
+    let doc = r#"<?xml version="1.0" standalone="no"?><a/>"#;
+    assert_eq!(
+        parse_document(doc).unwrap().declaration.unwrap().standalone,
+        Some(false)
+    );
+
+    assert_eq!(parse_document("<a/>").unwrap().declaration, None);
+}
+
+#[test]
+fn invalid_declarations() {
+    for doc in [
+        // the version is required, and has to come first
+        r#"<?xml encoding="UTF-8"?><a/>"#,
+        r#"<?xml encoding="UTF-8" version="1.0"?><a/>"#,
+        r#"<?xml version="1.0" standalone="maybe"?><a/>"#,
+        r#"<?xml version="1.0" other="x"?><a/>"#,
+        r#"<?xml version="1.0"<a/>"#,
+    ] {
+        assert_eq!(error(doc).0, ErrorKind::InvalidDeclaration, "{}", doc);
+    }
+}
+
+#[test]
+fn declaration_has_to_come_first() {
+    // anywhere else, even after whitespace, it is a processing instruction with the reserved
+    // target `xml`
+    assert_eq!(
+        error(" <?xml version=\"1.0\"?><a/>"),
+        (ErrorKind::InvalidProcessingInstruction, 1)
+    );
+    assert_eq!(
+        error("<!-- x --><?xml version=\"1.0\"?><a/>"),
+        (ErrorKind::InvalidProcessingInstruction, "<!-- x -->".len())
+    );
+}
+
+#[test]
+fn doctype() {
+    let document = parse_document("<!DOCTYPE html><html/>").unwrap();
+    assert_eq!(
+        document.doctype,
+        Some(Doctype {
+            name: "html".to_string(),
+            public_id: None,
+            system_id: None,
+            internal_subset: None,
+        })
+    );
+
+    let doc = r#"<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd"><svg/>"#;
+    assert_eq!(
+        parse_document(doc).unwrap().doctype,
+        Some(Doctype {
+            name: "svg".to_string(),
+            public_id: Some("-//W3C//DTD SVG 1.1//EN".to_string()),
+            system_id: Some("http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd".to_string()),
+            internal_subset: None,
+        })
+    );
+
+    let doc = "<!DOCTYPE note SYSTEM 'note.dtd' [\n<!ELEMENT note (#PCDATA)>\n]>\n<note/>";
+    assert_eq!(
+        parse_document(doc).unwrap().doctype,
+        Some(Doctype {
+            name: "note".to_string(),
+            public_id: None,
+            system_id: Some("note.dtd".to_string()),
+            internal_subset: Some("\n<!ELEMENT note (#PCDATA)>\n".to_string()),
+        })
+    );
+}
+
+#[test]
+fn internal_subset_is_kept_as_it_is() {
+    // a `]` or `>` inside of a quoted value or a comment doesn't end the subset
+    let subset = r#"<!ENTITY a "]>"><!-- ]> --><!ATTLIST b c CDATA '>'>"#;
+    let doc = format!("<!DOCTYPE b [{}]><b/>", subset);
+
+    assert_eq!(
+        parse_document(&doc)
+            .unwrap()
+            .doctype
+            .unwrap()
+            .internal_subset,
+        Some(subset.to_string())
+    );
+}
+
+#[test]
+fn invalid_doctypes() {
+    for doc in [
+        "<!DOCTYPE><a/>",
+        "<!DOCTYPE a PUBLIC 'only-one'><a/>",
+        "<!DOCTYPE a SYSTEM><a/>",
+        "<!DOCTYPE a [<!ELEMENT a EMPTY><a/>",
+    ] {
+        assert_eq!(error(doc).0, ErrorKind::InvalidDoctype, "{}", doc);
+    }
+}
+
+#[test]
+fn misc_around_the_root_element() {
+    let doc = "<?xml version=\"1.0\"?>\n\
+        <!-- before the doctype -->\n\
+        <?style href=\"a.css\"?>\n\
+        <!DOCTYPE a>\n\
+        <!-- before the root -->\n\
+        <a/>\n\
+        <!-- after the root -->\n\
+        <?done?>\n";
+    let document = parse_document(doc).unwrap();
+
+    assert_eq!(
+        document.prolog,
+        vec![
+            Element::Comment(" before the doctype ".to_string()),
+            pi("style", "href=\"a.css\""),
+            Element::Comment(" before the root ".to_string()),
+        ]
+    );
+    assert_eq!(
+        document.epilog,
+        vec![
+            Element::Comment(" after the root ".to_string()),
+            pi("done", ""),
+        ]
+    );
+    assert!(document.doctype.is_some());
+}
+
+#[test]
+fn one_root_element() {
+    let doc = "<a/><b/>";
+    assert_eq!(error(doc), (ErrorKind::TrailingCharacters, "<a/>".len()));
+
+    let doc = "<a/>text";
+    assert_eq!(error(doc), (ErrorKind::TrailingCharacters, "<a/>".len()));
+
+    assert_eq!(
+        error("<!-- only a comment -->").0,
+        ErrorKind::ExpectedElement
+    );
+    assert_eq!(error("").0, ErrorKind::ExpectedElement);
+
+    // the doctype can't come after the root element, or twice
+    assert_eq!(
+        error("<a/><!DOCTYPE a>"),
+        (ErrorKind::TrailingCharacters, "<a/>".len())
+    );
+    assert_eq!(
+        error("<!DOCTYPE a><!DOCTYPE a><a/>"),
+        (ErrorKind::InvalidName, "<!DOCTYPE a><".len())
+    );
+}
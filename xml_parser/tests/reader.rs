@@ -0,0 +1,229 @@
+use xml_parser::{parse_document, Element, QName};
+use xml_parser::{ReadError, SyntaxError, XmlEvent, XmlReader};
+
+use std::io::{BufReader, Read};
+
+fn start(name: &str, attributes: &[(&str, &str)]) -> XmlEvent {
+    XmlEvent::StartElement {
+        name: QName::new(name.to_string()),
+        attributes: attributes
+            .iter()
+            .map(|(name, value)| (QName::new(name.to_string()), value.to_string()))
+            .collect(),
+    }
+}
+
+fn end(name: &str) -> XmlEvent {
+    XmlEvent::EndElement {
+        name: QName::new(name.to_string()),
+    }
+}
+
+fn text(text: &str) -> XmlEvent {
+    XmlEvent::Text(text.to_string())
+}
+
+fn events(doc: &str) -> Vec<XmlEvent> {
+    XmlReader::new(doc.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+/// The events up to the first error, and the error
+fn error(doc: &str) -> (Vec<XmlEvent>, SyntaxError, usize) {
+    let mut events = Vec::new();
+
+    for event in XmlReader::new(doc.as_bytes()) {
+        match event {
+            Ok(event) => events.push(event),
+            Err(ReadError::Syntax { kind, offset }) => return (events, kind, offset),
+            Err(ReadError::Io(e)) => panic!("{}", e),
+        }
+    }
+
+    panic!("no error in {}", doc);
+}
+
+/// Gives out one byte at a time, so that every piece of markup is split across reads
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((&byte, rest)), Some(first)) => {
+                *first = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+const DOC: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE r [<!ENTITY who "world">]>
+<?style a.css?>
+<r id="1"><a x="&lt;&who;">hello &amp; <![CDATA[<raw>]]></a><!--note--><b/></r>
+<!-- after -->
+"#;
+
+#[test]
+fn events_in_document_order() {
+    assert_eq!(
+        events(DOC),
+        vec![
+            XmlEvent::ProcessingInstruction {
+                target: "style".to_string(),
+                data: "a.css".to_string(),
+            },
+            start("r", &[("id", "1")]),
+            start("a", &[("x", "<world")]),
+            text("hello & "),
+            XmlEvent::CData("<raw>".to_string()),
+            end("a"),
+            XmlEvent::Comment("note".to_string()),
+            // a tag that closes itself still ends with its own event
+            start("b", &[]),
+            end("b"),
+            end("r"),
+            XmlEvent::Comment(" after ".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn reads_the_same_however_the_input_is_split() {
+    let trickled: Vec<_> = XmlReader::new(BufReader::with_capacity(1, Trickle(DOC.as_bytes())))
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(trickled, events(DOC));
+}
+
+#[test]
+fn agrees_with_the_tree_parser() {
+    let doc = r#"<r xmlns:p="urn:p"><p:a p:k="v">one<b>two</b>three</p:a></r>"#;
+    let Element::Node { children, .. } = parse_document(doc).unwrap().root else {
+        panic!("the root isn't a node");
+    };
+    let Element::Node {
+        name, attributes, ..
+    } = &children[0]
+    else {
+        panic!("the child isn't a node");
+    };
+
+    let events = events(doc);
+    assert_eq!(
+        events[1],
+        XmlEvent::StartElement {
+            name: name.clone(),
+            attributes: attributes.clone(),
+        }
+    );
+    assert_eq!(name.namespace_uri.as_deref(), Some("urn:p"));
+}
+
+#[test]
+fn long_text_comes_in_pieces() {
+    let long = "x".repeat(100_000);
+    let doc = format!("<r>{}&amp;{}</r>", long, long);
+
+    // read through a buffer of its own, as a slice would hand over all of it at once
+    let pieces: Vec<_> = XmlReader::new(BufReader::new(doc.as_bytes()))
+        .map(Result::unwrap)
+        .filter_map(|event| match event {
+            XmlEvent::Text(text) => Some(text),
+            _ => None,
+        })
+        .collect();
+
+    assert!(pieces.len() > 2);
+    assert!(pieces.iter().all(|piece| piece.len() <= 16 * 1024));
+    assert_eq!(pieces.concat(), format!("{}&{}", long, long));
+}
+
+#[test]
+fn depth_follows_the_open_elements() {
+    let mut reader = XmlReader::new("<a><b><c/></b></a>".as_bytes());
+    let mut depths = Vec::new();
+
+    while let Some(event) = reader.next() {
+        event.unwrap();
+        depths.push(reader.depth());
+    }
+
+    // a tag that closes itself is never open
+    assert_eq!(depths, vec![1, 2, 2, 2, 1, 0]);
+}
+
+#[test]
+fn tags_have_to_be_balanced() {
+    let (events, kind, offset) = error("<a><b></a>");
+    assert_eq!(events, vec![start("a", &[]), start("b", &[])]);
+    assert!(matches!(
+        kind,
+        SyntaxError::WrongCloseTag { expected, found } if expected == "b" && found == "a"
+    ));
+    assert_eq!(offset, "<a><b>".len());
+
+    let (_, kind, offset) = error("<a><b></b>");
+    assert!(matches!(kind, SyntaxError::Unclosed(name) if name == "a"));
+    assert_eq!(offset, "<a><b></b>".len());
+
+    let (_, kind, _) = error("<a/></a>");
+    assert!(matches!(kind, SyntaxError::UnmatchedCloseTag(name) if name == "a"));
+}
+
+#[test]
+fn one_root_element() {
+    assert!(matches!(
+        error("<a/><b/>").1,
+        SyntaxError::ContentOutsideRoot
+    ));
+    assert!(matches!(
+        error("<a/>text").1,
+        SyntaxError::ContentOutsideRoot
+    ));
+    assert!(matches!(error("<!-- x -->").1, SyntaxError::MissingRoot));
+    assert!(matches!(error("").1, SyntaxError::MissingRoot));
+    assert!(matches!(
+        error("<a/><?xml version=\"1.0\"?>").1,
+        SyntaxError::Misplaced
+    ));
+    assert!(matches!(
+        error("<a/><!DOCTYPE a>").1,
+        SyntaxError::Misplaced
+    ));
+}
+
+#[test]
+fn malformed_markup() {
+    let (events, kind, offset) = error("<r><a x=1/></r>");
+    assert_eq!(events, vec![start("r", &[])]);
+    assert!(matches!(kind, SyntaxError::Malformed));
+    assert_eq!(offset, "<r>".len());
+
+    assert!(matches!(error("<r><!-- open").1, SyntaxError::Malformed));
+    assert!(matches!(
+        error("<r a='1' a='2'/>").1,
+        SyntaxError::DuplicateAttribute(name) if name == "a"
+    ));
+    assert!(matches!(error("<p:r/>").1, SyntaxError::Namespace(_)));
+    assert!(matches!(error("<r>&nope;</r>").1, SyntaxError::Entity(_)));
+    assert!(matches!(
+        XmlReader::new(&b"<r>\xff</r>"[..]).find_map(Result::err),
+        Some(ReadError::Syntax {
+            kind: SyntaxError::InvalidUtf8,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn nothing_comes_after_an_error() {
+    let mut reader = XmlReader::new("<a><b></a><c/>".as_bytes());
+
+    assert!(reader.by_ref().any(|event| event.is_err()));
+    assert!(reader.next().is_none());
+}
@@ -0,0 +1,127 @@
+use xml_parser::{parse_document, Element, ErrorKind, XmlReader};
+
+fn root(doc: &str) -> Element {
+    parse_document(doc)
+        .unwrap_or_else(|e| panic!("{:?}: {}", doc, e))
+        .root
+}
+
+fn error(doc: &str) -> (ErrorKind, usize) {
+    let e = parse_document(doc).unwrap_err();
+    (e.kind().clone(), e.offset())
+}
+
+fn name(element: &Element) -> String {
+    match element {
+        Element::Node { name, .. } => name.qualified(),
+        element => panic!("{:?} isn't a node", element),
+    }
+}
+
+/// The value of the only attribute of the root element
+fn attribute(doc: &str) -> String {
+    match root(doc) {
+        Element::Node { attributes, .. } => attributes[0].1.clone(),
+        element => panic!("{:?} isn't a node", element),
+    }
+}
+
+#[test]
+fn names() {
+    for tag in [
+        "a",
+        "_a",
+        "a-b.c_d9",
+        "a\u{b7}b",
+        "\u{e9}t\u{e9}",
+        "\u{65e5}\u{672c}",
+        "\u{3b1}\u{301}",
+        "a\u{203f}b",
+        "\u{10000}",
+    ] {
+        let doc = format!("<{}/>", tag);
+        assert_eq!(name(&root(&doc)), tag, "{:?}", tag);
+        // the same rules hold for attributes
+        let doc = format!("<r {}='1'/>", tag);
+        assert!(parse_document(&doc).is_ok(), "{:?}", doc);
+    }
+
+    // digits, `-`, `.` and combining characters can't start a name
+    for tag in ["1a", "-a", ".a", "\u{b7}a", "\u{301}a", "\u{203f}a"] {
+        let doc = format!("<{}/>", tag);
+        assert_eq!(error(&doc), (ErrorKind::InvalidName, 1), "{:?}", tag);
+        let doc = format!("<r {}='1'/>", tag);
+        assert!(parse_document(&doc).is_err(), "{:?}", doc);
+    }
+
+    // and some characters can't be anywhere in one, so the name ends before them
+    for tag in ["a\u{d7}b", "a\u{f7}b", "a\u{37e}b", "a\u{2000}b", "a~b"] {
+        let doc = format!("<{}/>", tag);
+        assert_eq!(error(&doc), (ErrorKind::UnclosedTag, 2), "{:?}", tag);
+    }
+}
+
+#[test]
+fn attribute_values_are_normalized() {
+    // a literal tab, line break or carriage return is a space
+    assert_eq!(attribute("<a x='1\t2\n3\r4'/>"), "1 2 3 4");
+    // and a `\r\n` line break is a single one
+    assert_eq!(attribute("<a x='1\r\n2'/>"), "1 2");
+    // while a character reference keeps the character
+    assert_eq!(attribute("<a x='1&#9;2&#10;3&#13;4'/>"), "1\t2\n3\r4");
+    assert_eq!(attribute("<a x='&#x9;&#xA;&#xD;'/>"), "\t\n\r");
+    // other white space is kept as is, and nothing is trimmed
+    assert_eq!(attribute("<a x=' \u{a0}1 '/>"), " \u{a0}1 ");
+}
+
+#[test]
+fn duplicate_attributes() {
+    assert_eq!(
+        error("<a x='1' x='2'/>"),
+        (ErrorKind::DuplicateAttribute("x".to_string()), 0)
+    );
+    assert_eq!(
+        error("<r>\n<a x='1' y='2' x='3'>text</a></r>"),
+        (
+            ErrorKind::DuplicateAttribute("x".to_string()),
+            "<r>\n".len()
+        )
+    );
+    assert_eq!(
+        error("<a xmlns:p='urn:p' p:x='1' p:x='2'/>"),
+        (ErrorKind::DuplicateAttribute("p:x".to_string()), 0)
+    );
+
+    // names are compared as written, so these are different attributes
+    assert!(parse_document("<a x='1' X='2' p:x='3' xmlns:p='urn:p'/>").is_ok());
+}
+
+#[test]
+fn only_xml_white_space_separates_markup() {
+    assert!(parse_document("<a\tx='1'\r\ny='2'\n/>").is_ok());
+    assert!(parse_document("<?xml version='1.0'\t?>\r\n<a\n>\t</a\r\n>\n").is_ok());
+    assert!(parse_document("<!DOCTYPE a\n[\t<!ELEMENT a EMPTY>\r\n]><a/>").is_ok());
+
+    // other characters that Unicode calls white space aren't, in XML
+    for space in ["\u{a0}", "\u{85}", "\u{2028}", "\u{3000}", "\u{b}", "\u{c}"] {
+        for doc in [
+            format!("<a{}x='1'/>", space),
+            format!("<a x{}='1'/>", space),
+            format!("<a{}/>", space),
+            format!("<a></a{}>", space),
+            format!("<a/>{}", space),
+            format!("{}<a/>", space),
+            format!("<!DOCTYPE{}a><a/>", space),
+        ] {
+            assert!(parse_document(&doc).is_err(), "{:?}", doc);
+        }
+
+        // where the reader agrees
+        let doc = format!("<a/>{}", space);
+        assert!(
+            XmlReader::new(doc.as_bytes()).any(|event| event.is_err()),
+            "{:?}",
+            doc
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use xml_parser::{parse_document, Element, ErrorKind};
+
+fn children(doc: &str) -> Vec<Element> {
+    match parse_document(doc).unwrap().root {
+        Element::Node { children, .. } => children,
+        root => panic!("{:?}", root),
+    }
+}
+
+fn text(text: &str) -> Element {
+    Element::Text(text.to_string())
+}
+
+fn name(element: &Element) -> &str {
+    match element {
+        Element::Node { name, .. } => &name.local_name,
+        element => panic!("{:?}", element),
+    }
+}
+
+#[test]
+fn text_and_elements_interleave() {
+    let content = children("<p>Hello <b>world</b>!</p>");
+
+    assert_eq!(content.len(), 3);
+    assert_eq!(content[0], text("Hello "));
+    assert_eq!(name(&content[1]), "b");
+    assert_eq!(content[2], text("!"));
+
+    match &content[1] {
+        Element::Node { children, .. } => assert_eq!(children, &[text("world")]),
+        child => panic!("{:?}", child),
+    }
+
+    let content = children("<p><i>a</i> and <!-- note --><i>b</i></p>");
+    assert_eq!(content.len(), 4);
+    assert_eq!(content[1], text(" and "));
+    assert_eq!(content[2], Element::Comment(" note ".to_string()));
+}
+
+#[test]
+fn cdata_merges_with_the_text_around_it() {
+    assert_eq!(
+        children("<p>a <![CDATA[<b> & c]]> d</p>"),
+        [text("a <b> & c d")]
+    );
+    assert_eq!(
+        children("<p><![CDATA[x]]><![CDATA[]]>&amp;<![CDATA[y]]></p>"),
+        [text("x&y")]
+    );
+    // a comment keeps the text on either side of it apart
+    assert_eq!(
+        children("<p>a<!---->b</p>"),
+        [text("a"), Element::Comment(String::new()), text("b")]
+    );
+}
+
+#[test]
+fn whitespace_is_kept() {
+    assert_eq!(
+        children("<p>  two  spaces\n\tand a tab  </p>"),
+        [text("  two  spaces\n\tand a tab  ")]
+    );
+
+    let content = children("<list>\n  <item/>\n  <item/>\n</list>");
+    assert_eq!(content.len(), 5);
+    assert_eq!(content[0], text("\n  "));
+    assert_eq!(content[2], text("\n  "));
+    assert_eq!(content[4], text("\n"));
+
+    assert_eq!(children("<p><![CDATA[  ]]></p>"), [text("  ")]);
+}
+
+#[test]
+fn unterminated_cdata_is_an_error() {
+    let e = parse_document("<p>a <![CDATA[b</p>").unwrap_err();
+
+    assert_eq!(e.kind(), &ErrorKind::UnterminatedCData);
+}
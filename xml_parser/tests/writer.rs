@@ -0,0 +1,127 @@
+use xml_parser::{parse_document, Document, Element, QName, WriteOptions};
+
+const DOCUMENTS: &[&str] = &[
+    "<a/>",
+    r#"<?xml version="1.0" encoding="UTF-8"?><!--before--><a x="1" y='2'><b/><c>text</c></a><?after?>"#,
+    r#"<!DOCTYPE a PUBLIC "-//x//y" 'say "hi"'><a/>"#,
+    "<p>Hello <b>world</b>!</p>",
+    "<p> <![CDATA[<not a tag> & ]]>  text\r\n </p>",
+    r#"<a v="&lt;&amp;&gt;&quot;'" w="tab&#9;line&#10;return&#13;">&lt;&amp;&gt;"'</a>"#,
+    r#"<root xmlns="urn:default" xmlns:x="urn:x"><x:item x:id="1">one</x:item><item/></root>"#,
+    "<a><!-- comment --><?target some data?><b><c><d>deep</d></c></b></a>",
+];
+
+fn round_trip(doc: &Document, options: WriteOptions) -> Document {
+    let text = doc.formatted(options).to_string();
+
+    parse_document(&text).unwrap_or_else(|e| panic!("{}\n{}", e, text))
+}
+
+/// Removes the text that indentation adds, which is whitespace between the children of an
+/// element that holds no other text
+fn remove_indentation(element: &mut Element) {
+    if let Element::Node { children, .. } = element {
+        let whitespace = |child: &Element| match child {
+            Element::Text(text) => text.trim().is_empty(),
+            _ => false,
+        };
+
+        if children
+            .iter()
+            .all(|child| whitespace(child) || !matches!(child, Element::Text(_)))
+        {
+            children.retain(|child| !whitespace(child));
+        }
+
+        children.iter_mut().for_each(remove_indentation);
+    }
+}
+
+#[test]
+fn compact_output_parses_back_the_same() {
+    for doc in DOCUMENTS {
+        let tree = parse_document(doc).unwrap();
+
+        assert_eq!(round_trip(&tree, WriteOptions::compact()), tree, "{}", doc);
+    }
+}
+
+#[test]
+fn indented_output_only_adds_whitespace() {
+    for doc in DOCUMENTS {
+        let tree = parse_document(doc).unwrap();
+        let mut indented = round_trip(&tree, WriteOptions::pretty());
+
+        remove_indentation(&mut indented.root);
+        assert_eq!(indented, tree, "{}", doc);
+    }
+
+    // the indentation is real text as far as the parser is concerned
+    let tree = parse_document("<a><b/></a>").unwrap();
+    let indented = round_trip(&tree, WriteOptions::pretty());
+    assert_eq!(tree.to_string(), "<a><b/></a>");
+    assert_eq!(format!("{:#}", tree), "<a>\n  <b/>\n</a>");
+    assert_ne!(indented, tree);
+}
+
+#[test]
+fn sorted_attributes_parse_back_in_sorted_order() {
+    let options = WriteOptions {
+        sort_attributes: true,
+        ..WriteOptions::compact()
+    };
+    let tree = parse_document(r#"<a z="1" xmlns:p="urn:p" p:b="2" a="3" xmlns="urn:d"/>"#).unwrap();
+    let sorted = round_trip(&tree, options);
+
+    assert_eq!(
+        sorted.to_string(),
+        r#"<a xmlns="urn:d" xmlns:p="urn:p" a="3" z="1" p:b="2"/>"#
+    );
+    assert_eq!(round_trip(&sorted, options), sorted);
+}
+
+#[test]
+fn built_tree_parses_back_the_same() {
+    let node = |name: &str, attributes: Vec<(&str, &str)>, children| Element::Node {
+        name: QName::new(name.to_string()),
+        attributes: attributes
+            .into_iter()
+            .map(|(name, value)| (QName::new(name.to_string()), value.to_string()))
+            .collect(),
+        children,
+    };
+    let text = |text: &str| Element::Text(text.to_string());
+
+    let tree = node(
+        "root",
+        vec![
+            ("quotes", "\"'"),
+            ("whitespace", " \t\r\n "),
+            ("markup", "<&>"),
+        ],
+        vec![
+            text("\r\n  a < b && c > d  ]]>"),
+            node("empty", vec![], vec![]),
+            Element::Comment(" comment ".to_string()),
+            node("nested", vec![("a", "")], vec![text("\u{1F600}")]),
+        ],
+    );
+    let doc = parse_document(&tree.to_string()).unwrap();
+
+    assert_eq!(doc.root, tree, "{}", tree);
+}
+
+#[test]
+fn empty_text_is_not_written() {
+    let tree = Element::Node {
+        name: QName::new("a".to_string()),
+        attributes: Vec::new(),
+        children: vec![Element::Text(String::new())],
+    };
+
+    assert_eq!(tree.to_string(), "<a/>");
+    assert_eq!(format!("{:#}", tree), "<a/>");
+
+    let doc = parse_document(&tree.to_string()).unwrap();
+    assert!(matches!(doc.root, Element::Node { children, .. } if children.is_empty()));
+}
@@ -0,0 +1,185 @@
+use xml_parser::{parse_document, Element, Node, Value, XPath};
+
+const LIBRARY: &str = r#"<library xmlns:x="urn:x" name="main">
+<shelf id="s1" x:tag="new"><book id="b1" lang="en"><title>Emma</title><author>Austen</author></book><book id="b2"><title>Persuasion</title></book><!--note--></shelf>
+<shelf id="s2"><book id="b3"><title>Ulysses</title><book id="b4" lang="en"><title>Inner</title></book></book>loose text</shelf>
+</library>"#;
+
+fn library() -> Element {
+    parse_document(LIBRARY).unwrap().root
+}
+
+/// The `id` of elements that have one, the name of other elements, `@name=value` for
+/// attributes and the contents of text
+fn describe(node: &Node<'_>) -> String {
+    match node {
+        Node::Element(Element::Node {
+            name, attributes, ..
+        }) => attributes
+            .iter()
+            .find(|(name, _)| name.qualified() == "id")
+            .map_or_else(|| name.qualified(), |(_, id)| id.clone()),
+        Node::Element(Element::Text(text)) => format!("{:?}", text),
+        Node::Element(Element::Comment(text)) => format!("<!--{}-->", text),
+        Node::Element(element) => format!("{:?}", element),
+        Node::Attribute(name, value) => format!("@{}={}", name.qualified(), value),
+    }
+}
+
+fn select(path: &str) -> Vec<String> {
+    library()
+        .query(path)
+        .unwrap_or_else(|e| panic!("{}: {}", path, e))
+        .iter()
+        .map(describe)
+        .collect()
+}
+
+/// What a query that isn't a path comes to, written out
+fn evaluate(path: &str) -> String {
+    match path.parse::<XPath>().unwrap().evaluate(&library()) {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s,
+        Value::Boolean(b) => b.to_string(),
+        Value::Nodes(nodes) => panic!("{} selected {} nodes", path, nodes.len()),
+    }
+}
+
+#[test]
+fn child_steps() {
+    assert_eq!(select("shelf"), ["s1", "s2"]);
+    assert_eq!(select("shelf/book"), ["b1", "b2", "b3"]);
+    // a path from `/` starts above the root element
+    assert_eq!(
+        select("/library/shelf/book/title"),
+        ["title", "title", "title"]
+    );
+    assert!(select("/shelf").is_empty());
+    assert_eq!(select("shelf/book/book"), ["b4"]);
+    assert_eq!(select("."), ["library"]);
+    assert!(select("book").is_empty());
+    assert!(select("shelf/missing").is_empty());
+}
+
+#[test]
+fn descendant_steps() {
+    assert_eq!(select("//book"), ["b1", "b2", "b3", "b4"]);
+    assert_eq!(select("shelf//title").len(), 4);
+    assert_eq!(select("//book//title").len(), 4);
+    // `//` includes the node it starts at
+    assert_eq!(select("//shelf"), ["s1", "s2"]);
+    assert_eq!(select("shelf[2]//book"), ["b3", "b4"]);
+}
+
+#[test]
+fn attributes() {
+    assert_eq!(select("@name"), ["@name=main"]);
+    assert_eq!(select("//book/@lang"), ["@lang=en", "@lang=en"]);
+    assert_eq!(select("shelf/@*"), ["@id=s1", "@x:tag=new", "@id=s2"]);
+    // namespace declarations aren't attributes
+    assert_eq!(select("@*"), ["@name=main"]);
+    assert!(select("@xmlns:x").is_empty());
+    assert!(select("@missing").is_empty());
+}
+
+#[test]
+fn wildcards_and_text() {
+    assert_eq!(select("*"), ["s1", "s2"]);
+    assert_eq!(select("shelf[1]/*"), ["b1", "b2"]);
+    assert_eq!(select("shelf[2]/text()"), ["\"loose text\""]);
+    assert_eq!(select("//title/text()").len(), 4);
+    assert_eq!(select("shelf/comment()"), ["<!--note-->"]);
+    // `node()` is every kind of child, text in between the shelves included
+    assert_eq!(select("shelf[1]/node()"), ["b1", "b2", "<!--note-->"]);
+    assert_eq!(select("node()").len(), 5);
+}
+
+#[test]
+fn positions() {
+    assert_eq!(select("shelf[1]"), ["s1"]);
+    assert_eq!(select("shelf[last()]"), ["s2"]);
+    assert_eq!(select("shelf/book[last()]"), ["b2", "b3"]);
+    assert_eq!(select("shelf/book[position() = 2]"), ["b2"]);
+    assert!(select("shelf[3]").is_empty());
+
+    // a position counts among the nodes of its step from each parent, so `//book[1]` is
+    // every book that is the first book in its parent, and not the first in the document
+    // like `(//book)[1]`
+    assert_eq!(select("//book[1]"), ["b1", "b3", "b4"]);
+    assert_eq!(select("//book[last()]"), ["b2", "b3", "b4"]);
+    assert_eq!(select("//title[1]").len(), 4);
+}
+
+#[test]
+fn comparisons() {
+    assert_eq!(select("//book[@id='b2']"), ["b2"]);
+    assert_eq!(select("//book[@id = \"b2\"]"), ["b2"]);
+    assert_eq!(select("//book[@lang]"), ["b1", "b4"]);
+    assert_eq!(select("//book[@lang != 'en']"), Vec::<String>::new());
+    assert_eq!(select("//book[title = 'Emma']"), ["b1"]);
+    assert_eq!(select("//book[@id='b3']/book/title/text()"), ["\"Inner\""]);
+    assert_eq!(select("//book[@lang='en' and title='Inner']"), ["b4"]);
+    assert_eq!(select("//book[@id='b1' or @id='b4']"), ["b1", "b4"]);
+    assert_eq!(select("//book[(@id='b1' or @id='b2') and @lang]"), ["b1"]);
+}
+
+#[test]
+fn functions() {
+    assert_eq!(evaluate("count(//book)"), "4");
+    assert_eq!(evaluate("count(shelf/@*)"), "3");
+    assert_eq!(evaluate("count(missing)"), "0");
+    assert_eq!(select("shelf[count(book) = 2]"), ["s1"]);
+
+    // a list of nodes stands for the text of the first of them
+    assert_eq!(select("//book[contains(title, 'ss')]"), ["b3"]);
+    assert_eq!(select("//book[contains(title, 'n')]"), ["b2", "b4"]);
+    assert_eq!(select("//book[starts-with(title, 'U')]"), ["b3"]);
+    assert_eq!(select("//book[starts-with(@id, 'b')]").len(), 4);
+    assert_eq!(select("//book[not(@lang)]"), ["b2", "b3"]);
+    assert_eq!(select("//book[not(book)]"), ["b1", "b2", "b4"]);
+
+    assert_eq!(evaluate("contains('haystack', 'st')"), "true");
+    assert_eq!(evaluate("starts-with('haystack', 'st')"), "false");
+    assert_eq!(evaluate("string-length('four')"), "4");
+    assert_eq!(evaluate("not(1 = 1)"), "false");
+}
+
+#[test]
+fn results_are_in_document_order_without_duplicates() {
+    // the inner book is inside of two books, and every book is inside of a shelf and the
+    // library, but each comes up once, in the order of the document
+    assert_eq!(select("//shelf//book"), ["b1", "b2", "b3", "b4"]);
+    assert_eq!(select("//*//book"), ["b1", "b2", "b3", "b4"]);
+    assert_eq!(
+        select("//book//title/text()"),
+        ["\"Emma\"", "\"Persuasion\"", "\"Ulysses\"", "\"Inner\""]
+    );
+    assert_eq!(select("//book[@lang]//@lang"), ["@lang=en", "@lang=en"]);
+    assert_eq!(evaluate("count(//node()//book)"), "4");
+}
+
+#[test]
+fn malformed_queries() {
+    // the column is where the part of the query that couldn't be read starts
+    for (path, column) in [
+        ("", 1),
+        ("@", 1),
+        ("shelf[", 6),
+        ("shelf[1", 6),
+        ("shelf/", 6),
+        ("book[@id = ]", 5),
+        ("a b", 3),
+        // filtering a parenthesized path isn't supported
+        ("(//book)[1]", 9),
+        // neither is calling a function with the wrong number of arguments
+        ("count(a, b)", 6),
+    ] {
+        let e = path.parse::<XPath>().unwrap_err();
+        assert_eq!(e.column(), column, "{}", path);
+    }
+
+    let nested = format!("{}1{}", "not(".repeat(40), ")".repeat(40));
+    assert!(nested.parse::<XPath>().is_err());
+    let nested = format!("{}1{}", "not(".repeat(20), ")".repeat(20));
+    assert!(nested.parse::<XPath>().is_ok());
+}